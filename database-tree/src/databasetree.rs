@@ -2,7 +2,7 @@ use crate::{
     databasetreeitems::DatabaseTreeItems, error::Result, item::DatabaseTreeItemKind,
     tree_iter::TreeIterator,
 };
-use crate::{Database, Table};
+use crate::{Database, Object, Table};
 use std::{collections::BTreeSet, usize};
 
 ///
@@ -103,10 +103,63 @@ impl DatabaseTree {
                     Some((database.clone(), table.clone(), item.id))
                 }
                 DatabaseTreeItemKind::Schema { .. } => None,
+                // views open like tables
+                DatabaseTreeItemKind::Object { object, database } => {
+                    object.as_table().map(|table| (database.clone(), table, item.id))
+                }
             }
         })
     }
 
+    pub fn selected_object(&self) -> Option<(Database, Object)> {
+        self.selection.and_then(|index| match self.items.tree_items[index].kind() {
+            DatabaseTreeItemKind::Object { object, database } => Some((database.clone(), object.clone())),
+            _ => None,
+        })
+    }
+
+    /// the database of the selected item, or the selected database itself
+    pub fn selected_database(&self) -> Option<String> {
+        self.selected_item().map(|item| item.kind().database_name().unwrap_or_else(|| item.kind().name()))
+    }
+
+    pub fn databases(&self) -> Vec<String> {
+        self.items
+            .tree_items
+            .iter()
+            .filter(|item| item.kind().is_database())
+            .map(|item| item.kind().name())
+            .collect()
+    }
+
+    /// the databases expanded before their children were fetched
+    pub fn loading_databases(&self) -> Vec<String> {
+        self.items
+            .tree_items
+            .iter()
+            .filter(|item| item.kind().is_database_loading())
+            .map(|item| item.kind().name())
+            .collect()
+    }
+
+    pub fn set_children(&mut self, database: &Database) {
+        if let Some((index, removed, added)) = self.items.set_children(database) {
+            self.selection = self.selection.map(|selection| match selection {
+                s if s <= index => s,
+                // the old children are gone
+                s if s <= index + removed => index,
+                s => s - removed + added,
+            });
+            self.visual_selection = self.calc_visual_selection();
+        }
+    }
+
+    pub fn set_unloaded(&mut self, name: &str) {
+        // it has no children the selection could be on
+        self.items.set_unloaded(name);
+        self.visual_selection = self.calc_visual_selection();
+    }
+
     pub fn collapse_recursive(&mut self) {
         if let Some(selection) = self.selection {
             self.items.collapse(selection, true);
@@ -455,7 +508,7 @@ impl DatabaseTree {
 
 #[cfg(test)]
 mod test {
-    use crate::{Database, DatabaseTree, MoveSelection, Schema, Table};
+    use crate::{Database, DatabaseTree, MoveSelection, Object, ObjectKind, Schema, Table};
     use std::collections::BTreeSet;
 
     impl Table {
@@ -465,6 +518,8 @@ mod test {
                 create_time: None,
                 update_time: None,
                 engine: None,
+                rows: None,
+                size: None,
                 schema: None,
             }
         }
@@ -475,6 +530,8 @@ mod test {
                 create_time: None,
                 update_time: None,
                 engine: None,
+                rows: None,
+                size: None,
                 schema: Some(schema),
             }
         }
@@ -502,6 +559,7 @@ mod test {
             vec![Schema {
                 name: "b".to_string(),
                 tables: vec![Table::new("c".to_string()).into()],
+                objects: vec![],
             }
             .into()],
         )];
@@ -549,6 +607,7 @@ mod test {
             vec![Schema {
                 name: "b".to_string(),
                 tables: vec![Table::new("c".to_string()).into()],
+                objects: vec![],
             }
             .into()],
         )];
@@ -612,6 +671,7 @@ mod test {
                     .iter()
                     .map(|x| Table::new(x.to_string()).into())
                     .collect(),
+                objects: vec![],
             }
             .into()],
         )];
@@ -665,6 +725,7 @@ mod test {
                 vec![Schema {
                     name: "b".to_string(),
                     tables: vec![Table::new("c".to_string()).into()],
+                    objects: vec![],
                 }
                 .into()],
             ),
@@ -673,6 +734,7 @@ mod test {
                 vec![Schema {
                     name: "e".to_string(),
                     tables: vec![Table::new("f".to_string()).into()],
+                    objects: vec![],
                 }
                 .into()],
             ),
@@ -722,6 +784,7 @@ mod test {
                 vec![Schema {
                     name: "b".to_string(),
                     tables: vec![Table::new("c".to_string()).into()],
+                    objects: vec![],
                 }
                 .into()],
             ),
@@ -730,6 +793,7 @@ mod test {
                 vec![Schema {
                     name: "e".to_string(),
                     tables: vec![Table::new("f".to_string()).into()],
+                    objects: vec![],
                 }
                 .into()],
             ),
@@ -779,6 +843,7 @@ mod test {
             vec![Schema {
                 name: "b".to_string(),
                 tables: vec![Table::new_with_schema("c".to_string(), "a".to_string()).into()],
+                objects: vec![],
             }
             .into()],
         )];
@@ -831,6 +896,7 @@ mod test {
             vec![Schema {
                 name: "b".to_string(),
                 tables: vec![Table::new_with_schema("c".to_string(), "a".to_string()).into()],
+                objects: vec![],
             }
             .into()],
         )];
@@ -892,6 +958,7 @@ mod test {
                 vec![Schema {
                     name: "b".to_string(),
                     tables: vec![Table::new_with_schema("c".to_string(), "a".to_string()).into()],
+                    objects: vec![],
                 }
                 .into()],
             ),
@@ -900,6 +967,7 @@ mod test {
                 vec![Schema {
                     name: "e".to_string(),
                     tables: vec![Table::new_with_schema("f".to_string(), "d".to_string()).into()],
+                    objects: vec![],
                 }
                 .into()],
             ),
@@ -958,6 +1026,7 @@ mod test {
                     Table::new("c".to_string()).into(),
                     Table::new("d".to_string()).into(),
                 ],
+                objects: vec![],
             }
             .into()],
         )];
@@ -1007,6 +1076,7 @@ mod test {
                     Table::new("c".to_string()).into(),
                     Table::new("d".to_string()).into(),
                 ],
+                objects: vec![],
             }
             .into()],
         )];
@@ -1024,4 +1094,85 @@ mod test {
         assert!(tree.move_selection(MoveSelection::End));
         assert_eq!(tree.selection, Some(3));
     }
+
+    #[test]
+    fn test_objects() {
+        let object = |name: &str, kind| Object { name: name.to_string(), kind, schema: Some("b".to_string()), table: None };
+        let items = vec![Database::new(
+            "a".to_string(),
+            vec![Schema {
+                name: "b".to_string(),
+                tables: vec![Table::new_with_schema("c".to_string(), "b".to_string())],
+                objects: vec![object("d", ObjectKind::View), object("e", ObjectKind::Function)],
+            }
+            .into()],
+        )];
+
+        // a
+        //   b
+        //     c
+        //     d
+        //     e
+
+        let mut tree = DatabaseTree::new(&items, &BTreeSet::new()).unwrap();
+        assert!(tree.move_selection(MoveSelection::Right));
+        assert!(tree.move_selection(MoveSelection::Down));
+        assert!(tree.move_selection(MoveSelection::Right));
+        assert!(tree.move_selection(MoveSelection::End));
+        assert_eq!(tree.selection, Some(4));
+        assert!(tree.selected_table().is_none());
+        assert_eq!(tree.selected_object().map(|(_, o)| o.kind), Some(ObjectKind::Function));
+
+        // a view reads like a table
+        assert!(tree.move_selection(MoveSelection::Up));
+        let (_, table, _) = tree.selected_table().unwrap();
+        assert_eq!((table.name.as_str(), table.schema.as_deref()), ("d", Some("b")));
+
+        // collapsing the schema hides its objects
+        assert!(tree.move_selection(MoveSelection::Up));
+        assert!(tree.move_selection(MoveSelection::Up));
+        assert!(tree.move_selection(MoveSelection::Left));
+        assert_eq!(tree.selection, Some(1));
+        assert!(!tree.move_selection(MoveSelection::End));
+    }
+
+    #[test]
+    fn test_lazy_loading() {
+        let items = vec![Database::unloaded("a".to_string()), Database::unloaded("b".to_string())];
+
+        // a
+        // b
+
+        let mut tree = DatabaseTree::new(&items, &BTreeSet::new()).unwrap();
+        assert!(tree.loading_databases().is_empty());
+        assert!(tree.move_selection(MoveSelection::Down));
+        assert!(tree.move_selection(MoveSelection::Right));
+        assert_eq!(tree.loading_databases(), ["b".to_string()]);
+
+        // ▸a
+        // ▾b
+        //   c
+        //   d
+
+        tree.move_selection(MoveSelection::Top);
+        tree.set_children(&Database::new(
+            "b".to_string(),
+            vec![Table::new("c".to_string()).into(), Table::new("d".to_string()).into()],
+        ));
+        assert!(tree.loading_databases().is_empty());
+        assert!(tree.move_selection(MoveSelection::End));
+        assert_eq!(tree.selection, Some(3));
+        assert_eq!(tree.selected_table().map(|(_, t, _)| t.name), Some("d".to_string()));
+        assert_eq!(tree.selected_database(), Some("b".to_string()));
+        assert_eq!(tree.databases(), ["a".to_string(), "b".to_string()]);
+
+        // a failed fetch collapses it to try again
+        assert!(tree.move_selection(MoveSelection::Top));
+        assert!(tree.move_selection(MoveSelection::Right));
+        assert_eq!(tree.loading_databases(), ["a".to_string()]);
+        tree.set_unloaded("a");
+        assert!(tree.loading_databases().is_empty());
+        assert!(tree.move_selection(MoveSelection::Right));
+        assert_eq!(tree.loading_databases(), ["a".to_string()]);
+    }
 }
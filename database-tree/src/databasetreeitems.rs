@@ -1,5 +1,5 @@
 use crate::{error::Result, treeitems_iter::TreeItemsIterator};
-use crate::{item::DatabaseTreeItemKind, DatabaseTreeItem};
+use crate::{item::DatabaseTreeItemKind, item::LoadState, DatabaseTreeItem};
 use crate::{Child, Database};
 use std::{
     collections::{BTreeSet, HashMap},
@@ -64,20 +64,63 @@ impl DatabaseTreeItems {
             {
                 Self::push_databases(e, &mut items, &mut items_added, collapsed)?;
             }
-            for child in &e.children {
-                match child {
-                    Child::Table(table) => items.push(DatabaseTreeItem::new_table(e, table)),
-                    Child::Schema(schema) => {
-                        items.push(DatabaseTreeItem::new_schema(e, schema, true));
-                        for table in &schema.tables {
-                            items.push(DatabaseTreeItem::new_table(e, table))
-                        }
+            Self::push_children(e, &mut items);
+        }
+
+        Ok(items)
+    }
+
+    fn push_children(database: &Database, items: &mut Vec<DatabaseTreeItem>) {
+        for child in &database.children {
+            match child {
+                Child::Table(table) => items.push(DatabaseTreeItem::new_table(database, table)),
+                Child::Schema(schema) => {
+                    items.push(DatabaseTreeItem::new_schema(database, schema, true));
+                    for table in &schema.tables {
+                        items.push(DatabaseTreeItem::new_table(database, table))
+                    }
+                    for object in &schema.objects {
+                        items.push(DatabaseTreeItem::new_object(database, object))
                     }
                 }
+                Child::Object(object) => items.push(DatabaseTreeItem::new_object(database, object)),
             }
         }
+    }
 
-        Ok(items)
+    /// puts the fetched children of a database in place of the ones it had,
+    /// returns its index with how many items were removed and added
+    pub fn set_children(&mut self, database: &Database) -> Option<(usize, usize, usize)> {
+        let index = self.database_index(&database.name)?;
+        let end = self.tree_items[index + 1..]
+            .iter()
+            .position(|item| item.kind().database_name().as_ref() != Some(&database.name))
+            .map_or(self.tree_items.len(), |i| index + 1 + i);
+        let mut children = vec![];
+        Self::push_children(database, &mut children);
+        let added = children.len();
+        self.tree_items.splice(index + 1..end, children);
+        self.tree_items[index].set_load_state(LoadState::Loaded);
+
+        let kind = self.tree_items[index].kind().clone();
+        if !kind.is_database_collapsed() {
+            self.update_visibility(&kind, index + 1);
+        }
+        Some((index, end - index - 1, added))
+    }
+
+    /// a database whose children could not be fetched is collapsed to try again
+    pub fn set_unloaded(&mut self, name: &str) {
+        if let Some(index) = self.database_index(name) {
+            self.collapse(index, false);
+            self.tree_items[index].set_load_state(LoadState::Unloaded);
+        }
+    }
+
+    pub fn database_index(&self, name: &str) -> Option<usize> {
+        self.tree_items
+            .iter()
+            .position(|item| item.kind().is_database() && item.kind().name() == name)
     }
 
     /// how many individual items are in the list
@@ -235,6 +278,13 @@ impl DatabaseTreeItems {
                                 continue;
                             }
                         }
+                        if let DatabaseTreeItemKind::Object { database, .. } =
+                            self.tree_items[i].kind().clone()
+                        {
+                            if database.name == *name {
+                                continue;
+                            }
+                        }
                     }
                     DatabaseTreeItemKind::Schema { schema, .. } => {
                         if let DatabaseTreeItemKind::Table { table, .. } =
@@ -245,6 +295,14 @@ impl DatabaseTreeItems {
                                 continue;
                             }
                         }
+                        if let DatabaseTreeItemKind::Object { object, .. } =
+                            self.tree_items[i].kind().clone()
+                        {
+                            if matches!(object.schema, Some(object_schema) if schema.name == object_schema)
+                            {
+                                continue;
+                            }
+                        }
                     }
                     _ => (),
                 }
@@ -267,19 +325,32 @@ impl DatabaseTreeItems {
                         }
                     }
 
-                    if let DatabaseTreeItemKind::Table { database, .. } = item_kind {
+                    if let DatabaseTreeItemKind::Table { database, .. } = item_kind.clone() {
+                        if *name == database.name {
+                            self.tree_items[i].info_mut().set_visible(true);
+                        }
+                    }
+
+                    if let DatabaseTreeItemKind::Object { database, .. } = item_kind {
                         if *name == database.name {
                             self.tree_items[i].info_mut().set_visible(true);
                         }
                     }
                 }
                 DatabaseTreeItemKind::Schema { schema, .. } => {
-                    if let DatabaseTreeItemKind::Table { table, .. } = item_kind {
+                    if let DatabaseTreeItemKind::Table { table, .. } = item_kind.clone() {
                         if matches!(table.schema, Some(table_schema) if schema.name == table_schema)
                         {
                             self.tree_items[i].info_mut().set_visible(true);
                         }
                     }
+
+                    if let DatabaseTreeItemKind::Object { object, .. } = item_kind {
+                        if matches!(object.schema, Some(object_schema) if schema.name == object_schema)
+                        {
+                            self.tree_items[i].info_mut().set_visible(true);
+                        }
+                    }
                 }
                 _ => (),
             }
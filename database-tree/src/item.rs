@@ -1,4 +1,4 @@
-use crate::{Database, Schema, Table};
+use crate::{Database, Object, Schema, Table};
 use super::next_id;
 
 #[derive(Debug, Clone)]
@@ -29,12 +29,21 @@ impl TreeItemInfo {
     }
 }
 
+/// whether the children of a database are in the tree
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LoadState {
+    Unloaded,
+    Loading,
+    Loaded,
+}
+
 /// `DatabaseTreeItem` can be of two kinds
 #[derive(PartialEq, Debug, Clone)]
 pub enum DatabaseTreeItemKind {
     Database {
         name: String,
         collapsed: bool,
+        state: LoadState,
     },
     Table {
         database: Database,
@@ -45,6 +54,10 @@ pub enum DatabaseTreeItemKind {
         schema: Schema,
         collapsed: bool,
     },
+    Object {
+        database: Database,
+        object: Object,
+    },
 }
 
 impl DatabaseTreeItemKind {
@@ -60,11 +73,16 @@ impl DatabaseTreeItemKind {
         matches!(self, Self::Schema { .. })
     }
 
+    pub const fn is_object(&self) -> bool {
+        matches!(self, Self::Object { .. })
+    }
+
     pub const fn is_database_collapsed(&self) -> bool {
         match self {
             Self::Database { collapsed, .. } => *collapsed,
             Self::Table { .. } => false,
             Self::Schema { .. } => false,
+            Self::Object { .. } => false,
         }
     }
 
@@ -73,14 +91,20 @@ impl DatabaseTreeItemKind {
             Self::Database { .. } => false,
             Self::Table { .. } => false,
             Self::Schema { collapsed, .. } => *collapsed,
+            Self::Object { .. } => false,
         }
     }
 
+    pub const fn is_database_loading(&self) -> bool {
+        matches!(self, Self::Database { state: LoadState::Loading, .. })
+    }
+
     pub fn name(&self) -> String {
         match self {
             Self::Database { name, .. } => name.to_string(),
             Self::Table { table, .. } => table.name.clone(),
             Self::Schema { schema, .. } => schema.name.clone(),
+            Self::Object { object, .. } => object.name.clone(),
         }
     }
 
@@ -89,6 +113,7 @@ impl DatabaseTreeItemKind {
             Self::Database { .. } => None,
             Self::Table { database, .. } => Some(database.name.clone()),
             Self::Schema { database, .. } => Some(database.name.clone()),
+            Self::Object { database, .. } => Some(database.name.clone()),
         }
     }
 
@@ -97,6 +122,7 @@ impl DatabaseTreeItemKind {
             Self::Database { .. } => None,
             Self::Table { table, .. } => table.schema.clone(),
             Self::Schema { .. } => None,
+            Self::Object { object, .. } => object.schema.clone(),
         }
     }
 }
@@ -121,6 +147,17 @@ impl DatabaseTreeItem {
         }
     }
 
+    pub fn new_object(database: &Database, object: &Object) -> Self {
+        Self {
+            id: next_id(),
+            info: TreeItemInfo::new(if object.schema.is_some() { 2 } else { 1 }, false),
+            kind: DatabaseTreeItemKind::Object {
+                database: database.clone(),
+                object: object.clone(),
+            },
+        }
+    }
+
     pub fn new_schema(database: &Database, schema: &Schema, _collapsed: bool) -> Self {
         Self {
             id: next_id(),
@@ -140,16 +177,14 @@ impl DatabaseTreeItem {
             kind: DatabaseTreeItemKind::Database {
                 name: database.name.to_string(),
                 collapsed: true,
+                state: if database.loaded { LoadState::Loaded } else { LoadState::Unloaded },
             },
         }
     }
 
     pub fn set_collapsed(&mut self, collapsed: bool) {
-        if let DatabaseTreeItemKind::Database { name, .. } = self.kind() {
-            self.kind = DatabaseTreeItemKind::Database {
-                name: name.to_string(),
-                collapsed,
-            }
+        if let DatabaseTreeItemKind::Database { collapsed: c, .. } = &mut self.kind {
+            *c = collapsed;
         }
     }
 
@@ -166,20 +201,24 @@ impl DatabaseTreeItem {
     }
 
     pub fn collapse_database(&mut self) {
-        if let DatabaseTreeItemKind::Database { name, .. } = &self.kind {
-            self.kind = DatabaseTreeItemKind::Database {
-                name: name.to_string(),
-                collapsed: true,
-            }
+        if let DatabaseTreeItemKind::Database { collapsed, .. } = &mut self.kind {
+            *collapsed = true;
         }
     }
 
+    // an unloaded database waits for its children
     pub fn expand_database(&mut self) {
-        if let DatabaseTreeItemKind::Database { name, .. } = &self.kind {
-            self.kind = DatabaseTreeItemKind::Database {
-                name: name.to_string(),
-                collapsed: false,
-            };
+        if let DatabaseTreeItemKind::Database { collapsed, state, .. } = &mut self.kind {
+            *collapsed = false;
+            if *state == LoadState::Unloaded {
+                *state = LoadState::Loading;
+            }
+        }
+    }
+
+    pub fn set_load_state(&mut self, load_state: LoadState) {
+        if let DatabaseTreeItemKind::Database { state, .. } = &mut self.kind {
+            *state = load_state;
         }
     }
 
@@ -222,6 +261,7 @@ impl DatabaseTreeItem {
             DatabaseTreeItemKind::Database { name, .. } => name.contains(filter_text),
             DatabaseTreeItemKind::Table { table, .. } => table.name.contains(filter_text),
             DatabaseTreeItemKind::Schema { schema, .. } => schema.name.contains(filter_text),
+            DatabaseTreeItemKind::Object { object, .. } => object.name.contains(filter_text),
         }
     }
 
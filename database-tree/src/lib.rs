@@ -8,7 +8,7 @@ mod treeitems_iter;
 pub use crate::{
     databasetree::DatabaseTree,
     databasetree::MoveSelection,
-    item::{DatabaseTreeItem, TreeItemInfo},
+    item::{DatabaseTreeItem, DatabaseTreeItemKind, LoadState, TreeItemInfo},
 };
 use std::sync::atomic::{AtomicUsize, Ordering};
 
@@ -16,12 +16,14 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 pub struct Database {
     pub name: String,
     pub children: Vec<Child>,
+    pub loaded: bool, // false until the children are fetched
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Child {
     Table(Table),
     Schema(Schema),
+    Object(Object),
 }
 
 impl From<Table> for Child {
@@ -36,11 +38,27 @@ impl From<Schema> for Child {
     }
 }
 
+impl From<Object> for Child {
+    fn from(o: Object) -> Self {
+        Child::Object(o)
+    }
+}
+
 impl Database {
     pub fn new(database: String, children: Vec<Child>) -> Self {
         Self {
             name: database,
             children,
+            loaded: true,
+        }
+    }
+
+    // only the name, the children are fetched when it is expanded
+    pub fn unloaded(database: String) -> Self {
+        Self {
+            name: database,
+            children: vec![],
+            loaded: false,
         }
     }
 }
@@ -49,6 +67,7 @@ impl Database {
 pub struct Schema {
     pub name: String,
     pub tables: Vec<Table>,
+    pub objects: Vec<Object>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -57,6 +76,8 @@ pub struct Table {
     pub create_time: Option<chrono::DateTime<chrono::Utc>>,
     pub update_time: Option<chrono::DateTime<chrono::Utc>>,
     pub engine: Option<String>,
+    pub rows: Option<u64>, // estimated by the database
+    pub size: Option<u64>, // bytes, with the indexes
     pub schema: Option<String>,
 }
 
@@ -68,6 +89,59 @@ impl Table {
     }
 }
 
+// in the order they are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ObjectKind {
+    View,
+    MaterializedView,
+    Function,
+    Procedure,
+    Trigger,
+    Sequence,
+}
+
+impl ObjectKind {
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::View => "view",
+            Self::MaterializedView => "materialized view",
+            Self::Function => "function",
+            Self::Procedure => "procedure",
+            Self::Trigger => "trigger",
+            Self::Sequence => "sequence",
+        }
+    }
+
+    // has records like a table
+    pub const fn is_relation(&self) -> bool {
+        matches!(self, Self::View | Self::MaterializedView)
+    }
+}
+
+/// anything in a database or schema besides its base tables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub name: String,
+    pub kind: ObjectKind,
+    pub schema: Option<String>,
+    pub table: Option<String>, // the table a trigger is on
+}
+
+impl Object {
+    // views are read like tables
+    pub fn as_table(&self) -> Option<Table> {
+        self.kind.is_relation().then(|| Table {
+            name: self.name.clone(),
+            create_time: None,
+            update_time: None,
+            engine: None,
+            rows: None,
+            size: None,
+            schema: self.schema.clone(),
+        })
+    }
+}
+
 static COUNTER: AtomicUsize = AtomicUsize::new(0);
 pub(crate) fn next_id() -> usize {
     COUNTER.fetch_add(1, Ordering::Relaxed)
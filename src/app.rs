@@ -2,14 +2,15 @@ use crate::clipboard::copy_to_clipboard;
 use crate::components::{
     HelpInfo, Component as _, DrawableComponent as _, EventState, StatefulDrawableComponent,
 };
-use crate::database::{MySqlPool, Pool, PostgresPool, SqlitePool, MssqlPool, ColType};
+use crate::database::{compare, dump_database, export_rows, import_rows, restore_database, load_schema, MySqlPool, Pool, PostgresPool, SqlitePool, MssqlPool, ColType, Comparison, ExecuteResult, ExportFormat, ExportSource, Header, ImportRequest, Page, PlanNode, RowSink, Value};
+use database_tree::{Database, Table};
 use crate::event::{Key, Event, Store};
 use crate::config::DatabaseType;
 use crate::{
     components::tab::Tab,
     components::{
-        help_info, ConnectionsComponent, DatabasesComponent, ErrorComponent, HelpComponent,
-        PropertiesComponent, RecordTableComponent, SqlEditorComponent, TabComponent, RecentComponent, Recent
+        help_info, utils::format_count, CompareComponent, ConnectionsComponent, DatabasesComponent, ErrorComponent, ExplainComponent, HelpComponent,
+        ProgressComponent, PropertiesComponent, RecordTableComponent, SqlEditorComponent, TabComponent, RecentComponent, Recent
     },
     config::{Config, Connection},
 };
@@ -20,8 +21,49 @@ use tui::{
     Frame,
 };
 use tokio::sync::mpsc;
-use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+
+// what a query run in the background hands back through `Event::QueryFinished`
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // boxed in the event already
+pub enum QueryOutput {
+    Records {
+        database: Database,
+        table: Table,
+        headers: Vec<Header>,
+        rows: Vec<Vec<Value>>,
+        page: Page,
+        selected_column: usize,
+        focus: bool,
+    },
+    NextPage(Vec<Vec<Value>>),
+    Count(u64),
+    Plan { query: String, analyze: bool, plan: PlanNode },
+    Comparison(Comparison),
+    Exported { path: PathBuf, rows: u64 },
+    Imported { table: String, read: u64, written: u64 },
+    Dumped { path: PathBuf, tables: usize, rows: u64 },
+    Restored { database: String, path: PathBuf, statements: usize },
+    Streamed, // the rows came as `Event::QueryRows`
+    Connected { conn: Connection, pool: OpenPool, focus: bool },
+    Tested(String),
+}
+
+// a pool opened in the background, for `QueryOutput::Connected`
+#[derive(Clone)]
+pub struct OpenPool(Arc<Box<dyn Pool>>);
+
+impl fmt::Debug for OpenPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OpenPool({})", self.0.database_type())
+    }
+}
 
 pub enum Focus {
     DabataseList,
@@ -33,6 +75,8 @@ pub struct App<'a> {
     record_table: RecordTableComponent,
     properties: PropertiesComponent<'a>,
     sql_editor: SqlEditorComponent<'a>,
+    explain: ExplainComponent,
+    compare: CompareComponent,
     focus: Focus,
     tab: TabComponent<'a>,
     help: HelpComponent<'a>,
@@ -40,7 +84,10 @@ pub struct App<'a> {
     show_database: bool,
     connections: ConnectionsComponent<'a>,
     recents: RecentComponent<'a>,
-    pool: Option<Box<dyn Pool>>,
+    pool: Option<Arc<Box<dyn Pool>>>,
+    progress: ProgressComponent,
+    more_rows: Option<mpsc::Sender<()>>, // asks the sql editor query for its next batch
+    waiting_rows: Option<JoinHandle<()>>, // that query, while it waits to be asked
     left_main_chunk_percentage: u16,
     updater: Updater,
     pub config: Config,
@@ -54,10 +101,12 @@ impl<'a> App<'a> {
         let store = Store::new(sender);
         let mut app = Self {
             config: config.clone(),
-            connections: ConnectionsComponent::new(&config.key_config, &config.conn, &config.settings),
+            connections: ConnectionsComponent::new(&config.key_config, config.conn.clone(), config.path.clone(), &config.settings),
             record_table: RecordTableComponent::new(config.key_config.clone(), config.settings.clone()),
             properties: PropertiesComponent::new(&config.key_config, &config.settings),
             sql_editor: SqlEditorComponent::new(&config.key_config, &config.settings, DatabaseType::Sqlite),
+            explain: ExplainComponent::new(config.key_config.clone(), config.settings.clone()),
+            compare: CompareComponent::new(config.key_config.clone(), config.settings.clone()),
             tab: TabComponent::new(&config.key_config),
             help: HelpComponent::new(&config.key_config),
             databases: DatabasesComponent::new(&config.key_config, &config.settings),
@@ -66,6 +115,9 @@ impl<'a> App<'a> {
             error: ErrorComponent::new(&config.key_config),
             focus: Focus::ConnectionList,
             pool: None,
+            progress: ProgressComponent::new(config.key_config.clone()),
+            more_rows: None,
+            waiting_rows: None,
             left_main_chunk_percentage: 15,
             store,
             keys: Vec::with_capacity(8),
@@ -136,7 +188,16 @@ impl<'a> App<'a> {
                 self.properties
                     .draw(f, right_chunks[1], matches!(self.focus, Focus::Table))?;
             }
+            Tab::Explain => {
+                self.explain
+                    .draw(f, right_chunks[1], matches!(self.focus, Focus::Table))?;
+            }
+            Tab::Compare => {
+                self.compare
+                    .draw(f, right_chunks[1], matches!(self.focus, Focus::Table))?;
+            }
         }
+        self.progress.draw(f, right_chunks[1], false)?;
         self.error.draw(f, Rect::default(), false)?;
         self.help.draw(f, Rect::default(), false)?;
         Ok(())
@@ -149,6 +210,10 @@ impl<'a> App<'a> {
     fn helps(&self) -> Vec<HelpInfo> {
         let mut res = vec![
             HelpInfo::new(help_info::filter(&self.config.key_config)),
+            HelpInfo::new(help_info::cancel_query(&self.config.key_config)),
+            HelpInfo::new(help_info::execute_statement(&self.config.key_config)),
+            HelpInfo::new(help_info::explain(&self.config.key_config)),
+            HelpInfo::new(help_info::switch_result(&self.config.key_config)),
             HelpInfo::new(help_info::help(&self.config.key_config)),
             HelpInfo::new(help_info::toggle_tabs(&self.config.key_config)),
             HelpInfo::new(help_info::scroll(&self.config.key_config)),
@@ -165,76 +230,323 @@ impl<'a> App<'a> {
         self.databases.helps(&mut res);
         self.record_table.helps(&mut res);
         self.properties.helps(&mut res);
+        self.explain.helps(&mut res);
+        self.compare.helps(&mut res);
         res
     }
 
-    pub async fn update_databases_internal(&mut self, conn: Option<&Connection>) -> anyhow::Result<()> {
-        if conn.is_none() {
-            return Ok(())
+    pub fn update_databases_internal(&mut self, conn: Option<Connection>) -> anyhow::Result<()> {
+        match conn {
+            Some(conn) => self.open_connection(conn, true),
+            None => Ok(()),
         }
-        let conn = conn.unwrap();
+    }
+
+    fn update_databases(&mut self, is_focus: bool) -> anyhow::Result<()> {
+        match self.connections.selected_connection() {
+            Some(conn) => self.open_connection(conn.clone(), is_focus),
+            None => Ok(()),
+        }
+    }
+
+    // the password may come from password_command, so the pool opens off the event loop
+    fn open_connection(&mut self, conn: Connection, focus: bool) -> anyhow::Result<()> {
+        self.progress.abort();
+        let page_size = self.config.settings.page_size;
+        self.spawn_query("connecting", async move {
+            let pool = connect(&conn, page_size).await?;
+            Ok(QueryOutput::Connected { conn, pool: OpenPool(Arc::new(pool)), focus })
+        })
+    }
+
+    async fn connected(&mut self, conn: Connection, pool: Arc<Box<dyn Pool>>, focus: bool) -> anyhow::Result<()> {
         if let Some(pool) = self.pool.as_ref() {
             pool.close().await;
         }
-        self.pool = Some(self.get_pool(conn).await?);
+        self.pool = Some(pool);
+        self.sql_editor.set_database_type(conn.get_type());
         self.databases
-            .update(conn, self.pool.as_ref().unwrap(), &mut self.updater)
+            .update(&conn, self.pool.as_ref().unwrap(), &mut self.updater)
             .await?;
-        self.focus = Focus::DabataseList;
+        self.compare.set_targets(&conn, &self.databases.tree().databases(), &self.config.conn);
+        if focus { self.focus = Focus::DabataseList; }
         self.record_table.reset();
         self.tab.reset();
         Ok(())
     }
 
-    async fn update_databases(&mut self, is_focus: bool) -> anyhow::Result<()> {
-        if let Some(conn) = self.connections.selected_connection() {
-            if let Some(pool) = self.pool.as_ref() {
-                pool.close().await;
-            }
-            self.pool = Some(self.get_pool(conn).await?);
-            self.sql_editor.set_database_type(conn.get_type());
-            self.databases
-                .update(conn, self.pool.as_ref().unwrap(), &mut self.updater)
-                .await?;
-            if is_focus { self.focus = Focus::DabataseList; }
-            self.record_table.reset();
-            self.tab.reset();
+    // opens and closes a pool with the connection of the form
+    fn test_connection(&mut self) -> anyhow::Result<()> {
+        let conn = self.connections.form_connection()?;
+        let page_size = self.config.settings.page_size;
+        self.spawn_query("testing connection", async move {
+            let pool = connect(&conn, page_size).await?;
+            pool.close().await;
+            Ok(QueryOutput::Tested(format!("connected to {}", conn.configured_url()?)))
+        })
+    }
+
+    // run a query off the event loop, the result comes back as `Event::QueryFinished`
+    fn spawn_query<F>(&mut self, label: &str, query: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = anyhow::Result<QueryOutput>> + Send + 'static,
+    {
+        if self.progress.is_running() {
+            anyhow::bail!("a query is already running, cancel it with {}", self.config.key_config.cancel_query);
         }
+        let store = self.store.clone();
+        let handle = tokio::spawn(async move {
+            let result = query.await.map(Box::new).map_err(|e| e.to_string());
+            let _ = store.dispatch(Event::QueryFinished(result)).await;
+        });
+        self.progress.start(label, handle);
         Ok(())
     }
 
-    async fn get_pool(&self, conn: &Connection) -> anyhow::Result<Box<dyn Pool>> {
+    // like spawn_query for the records of a table, a load with `replace` cancels the load still running
+    fn spawn_load<F>(&mut self, label: &str, replace: bool, query: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = anyhow::Result<QueryOutput>> + Send + 'static,
+    {
+        if self.progress.is_running() && !(replace && self.progress.is_load()) {
+            anyhow::bail!("a query is already running, cancel it with {}", self.config.key_config.cancel_query);
+        }
+        let previous = self.progress.take_load();
+        let pool = self.pool.clone().unwrap();
+        let store = self.store.clone();
+        let superseded = Arc::new(AtomicBool::new(false));
+        let current = superseded.clone();
+        let handle = tokio::spawn(async move {
+            if let Some((handle, superseded)) = previous {
+                // the replaced load reports nothing, its query is canceled first so the connection comes back free
+                superseded.store(true, Ordering::SeqCst);
+                // a canceled query ends on its own, dropping it in the middle would leave it running
+                if !matches!(pool.cancel().await, Ok(true)) {
+                    handle.abort();
+                }
+                let _ = handle.await;
+            }
+            let result = query.await.map(Box::new).map_err(|e| e.to_string());
+            if !current.load(Ordering::SeqCst) {
+                let _ = store.dispatch(Event::QueryFinished(result)).await;
+            }
+        });
+        self.progress.start_load(label, handle, superseded);
+        Ok(())
+    }
+
+    async fn cancel_query(&mut self) -> anyhow::Result<()> {
+        let canceled = self.pool.as_ref().unwrap().cancel().await;
+        // an interrupted query reports back as an error, anything else is dropped here
+        if !matches!(canceled, Ok(true)) {
+            self.progress.abort();
+        }
+        canceled.map(|_| ())
+    }
+
+    // fetch the children of the expanded databases alongside any running query
+    fn load_tables(&mut self) {
+        for database in self.databases.load_requests() {
+            self.fetch_tables(database);
+        }
+    }
+
+    fn fetch_tables(&self, database: String) {
+        let pool = self.pool.clone().unwrap();
+        let store = self.store.clone();
+        tokio::spawn(async move {
+            let children = pool.get_tables(database.clone()).await.map_err(|e| e.to_string());
+            let _ = store.dispatch(Event::TablesLoaded(database, children)).await;
+        });
+    }
+
+    fn compare_schemas(&mut self, target: Connection) -> anyhow::Result<()> {
+        let source = self.databases.tree().selected_database()
+            .ok_or_else(|| anyhow::anyhow!("select the database to compare in the tree"))?;
+        let pool = self.pool.clone().unwrap();
+        let page_size = self.config.settings.page_size;
+        self.spawn_query("comparing schemas", async move {
+            let source_tables = load_schema(&pool, &source).await?;
+            // a database without a name in the config is the one of the same name
+            let database = target.database.clone().unwrap_or_else(|| source.clone());
+            let target_pool = connect(&target, page_size).await?;
+            let target_tables = load_schema(&target_pool, &database).await;
+            target_pool.close().await;
+            let differences = compare(&source_tables, &target_tables?);
+            let script = target_pool.database_type().migration(&database, &differences);
+            let target = target.database_url_with_name().unwrap_or(database);
+            Ok(QueryOutput::Comparison(Comparison { source, target, differences, script }))
+        })
+    }
+
+    fn export(&mut self, source: ExportSource, format: ExportFormat, path: PathBuf) -> anyhow::Result<()> {
+        let pool = self.pool.clone().unwrap();
+        // a query runs without the filter of the records tab
+        let filter = match source {
+            ExportSource::Table { .. } => self.table_filter(),
+            ExportSource::Query(_) => None,
+        };
+        let limit = self.config.settings.row_limit as usize;
+        let store = self.store.clone();
+        self.spawn_query("exporting rows", async move {
+            let rows = export_rows(&pool, &source, format, &path, filter, limit, &store).await?;
+            Ok(QueryOutput::Exported { path, rows })
+        })
+    }
+
+    fn import(&mut self, request: ImportRequest) -> anyhow::Result<()> {
+        let pool = self.pool.clone().unwrap();
+        let store = self.store.clone();
+        self.spawn_query("importing rows", async move {
+            let (read, written) = import_rows(&pool, &request, &store).await?;
+            Ok(QueryOutput::Imported { table: request.table.name, read, written })
+        })
+    }
+
+    fn dump(&mut self, database: String, path: PathBuf) -> anyhow::Result<()> {
+        let pool = self.pool.clone().unwrap();
+        let connection = self.other_database(&database);
         let page_size = self.config.settings.page_size;
-        let database_url = conn.database_url()?;
-        match conn.get_type() {
-            DatabaseType::MySql => Ok(Box::new(MySqlPool::new(&database_url, page_size).await?)),
-            DatabaseType::Postgres => Ok(Box::new(PostgresPool::new(&database_url, page_size).await?)),
-            DatabaseType::Mssql => Ok(Box::new(MssqlPool::new(&database_url, page_size).await?)),
-            DatabaseType::Sqlite => Ok(Box::new(SqlitePool::new(&database_url, page_size).await?)),
+        let store = self.store.clone();
+        self.spawn_query("dumping database", async move {
+            let own = match &connection {
+                Some(connection) => Some(connect(connection, page_size).await?),
+                None => None,
+            };
+            let result = dump_database(own.as_ref().unwrap_or(&*pool), &database, &path, &store).await;
+            if let Some(own) = own {
+                own.close().await;
+            }
+            let (tables, rows) = result?;
+            Ok(QueryOutput::Dumped { path, tables, rows })
+        })
+    }
+
+    fn restore(&mut self, database: String, path: PathBuf) -> anyhow::Result<()> {
+        let pool = self.pool.clone().unwrap();
+        let connection = self.other_database(&database);
+        let page_size = self.config.settings.page_size;
+        let store = self.store.clone();
+        self.spawn_query("restoring database", async move {
+            let own = match &connection {
+                Some(connection) => Some(connect(connection, page_size).await?),
+                None => None,
+            };
+            let result = restore_database(own.as_ref().unwrap_or(&*pool), &path, &store).await;
+            if let Some(own) = own {
+                own.close().await;
+            }
+            Ok(QueryOutput::Restored { database, path, statements: result? })
+        })
+    }
+
+    // a database the pool does not reach, postgres connects to a single one and a mysql script names none
+    fn other_database(&self, database: &str) -> Option<Connection> {
+        self.connections.selected_connection()
+            .filter(|c| !matches!(c.get_type(), DatabaseType::Sqlite) && c.database.as_deref() != Some(database))
+            .map(|c| c.with_database(database))
+    }
+
+    fn table_filter(&self) -> Option<String> {
+        if self.record_table.filter.input_str().is_empty() {
+            None
+        } else {
+            Some(self.record_table.filter.input_str())
         }
     }
 
-    async fn update_record_table(&mut self, focus: bool, orderby: Option<String>, selected_column: usize) -> anyhow::Result<()> {
+    fn load_records(&mut self, database: Database, table: Table, orderby: Option<String>, filter: Option<String>, selected_column: usize, focus: bool) -> anyhow::Result<()> {
+        let pool = self.pool.clone().unwrap();
+        self.spawn_load("loading records", true, async move {
+            // keyset pagination needs the primary key
+            let key = pool.database_type().primary_key_columns(&pool, &database, &table).await?;
+            let page = Page::new(orderby, key);
+            // handle null value
+            let column_headers = pool.get_headers(&database, &table).await?;
+            let (mut headers, rows) = pool.get_records(&database, &table, &page, filter).await?;
+            if headers.is_empty() {
+                headers = column_headers;
+            } else {
+                // merge headers, use real column type
+                for (i, header) in headers.iter_mut().enumerate() {
+                    if let Some(h) = column_headers.get(i) {
+                        if h.name == header.name && h.col_type != ColType::Unknown {
+                            header.col_type = h.col_type.clone();
+                        }
+                    }
+                }
+            }
+            Ok(QueryOutput::Records { database, table, headers, rows, page, selected_column, focus })
+        })
+    }
+
+    fn update_record_table(&mut self, focus: bool, orderby: Option<String>, selected_column: usize) -> anyhow::Result<()> {
         if let Some((database, table, _)) = self.databases.tree().selected_table() {
-            let (headers, records) = self
-                .pool
-                .as_ref()
-                .unwrap()
-                .get_records(
-                    &database,
-                    &table,
-                    0,
-                    if self.record_table.filter.input_str().is_empty() {
-                        None
-                    } else {
-                        Some(self.record_table.filter.input_str())
-                    },
-                    orderby,
-                )
-                .await?;
-            self.record_table
-                .update(records, headers, database.clone(), table.clone(), selected_column);
-            if focus { self.record_table.focus = crate::components::record_table::Focus::Table; }
+            let filter = self.table_filter();
+            self.load_records(database, table, orderby, filter, selected_column, focus)?;
+        }
+        Ok(())
+    }
+
+    async fn query_finished(&mut self, output: QueryOutput) -> anyhow::Result<()> {
+        match output {
+            QueryOutput::Records { database, table, headers, rows, page, selected_column, focus } => {
+                if self.updater.update_columns(&database, &table, &headers) {
+                    self.sql_editor.update_db_metadata(self.updater.db_metadata());
+                }
+                self.record_table.update(rows, headers, database, table, selected_column);
+                self.record_table.table.page = page;
+                if focus { self.record_table.focus = crate::components::record_table::Focus::Table; }
+            },
+            QueryOutput::NextPage(rows) => {
+                if !rows.is_empty() {
+                    self.record_table.table.append(rows);
+                } else {
+                    self.record_table.table.end()
+                }
+            },
+            QueryOutput::Count(count) => self.record_table.table.total_rows = Some(count),
+            QueryOutput::Plan { query, analyze, plan } => {
+                self.explain.update(query, analyze, plan);
+                self.tab.selected_tab = Tab::Explain;
+            },
+            QueryOutput::Comparison(comparison) => {
+                self.compare.update(comparison);
+                self.tab.selected_tab = Tab::Compare;
+            },
+            QueryOutput::Exported { path, rows } => self.progress.notify(format!("exported {} rows to {}", rows, path.display())),
+            QueryOutput::Imported { table, read, written } => {
+                // a skipped duplicate is not written
+                self.progress.notify(if written < read {
+                    format!("imported {} of {} rows into {}, {} skipped", written, read, table, read - written)
+                } else {
+                    format!("imported {} rows into {}", read, table)
+                });
+                self.update_record_table(false, None, 0)?;
+            },
+            QueryOutput::Dumped { path, tables, rows } => {
+                self.progress.notify(format!("dumped {} tables and {} rows to {}", tables, rows, path.display()))
+            },
+            QueryOutput::Restored { database, path, statements } => {
+                self.progress.notify(format!("restored {} into {}, {} statements", path.display(), database, statements));
+                self.databases.request_reload(&database);
+                self.fetch_tables(database);
+            },
+            QueryOutput::Streamed => {},
+            QueryOutput::Connected { conn, pool, focus } => self.connected(conn, pool.0, focus).await?,
+            QueryOutput::Tested(message) => self.connections.form.set_message(message),
+        }
+        Ok(())
+    }
+
+    // scrolling to the last row of the sql editor results asks the query for its next batch
+    async fn fetch_more_rows(&mut self) -> anyhow::Result<()> {
+        if !self.sql_editor.wants_more_rows() || self.progress.is_running() {
+            return Ok(());
+        }
+        if let (Some(more), Some(task)) = (self.more_rows.as_ref(), self.waiting_rows.take()) {
+            more.send(()).await?;
+            self.progress.start("fetching rows", task);
         }
         Ok(())
     }
@@ -245,6 +557,10 @@ impl<'a> App<'a> {
 
     pub async fn event(&mut self, key: Key) -> anyhow::Result<EventState> {
         // self.update_commands();
+        if key == self.config.key_config.cancel_query && self.progress.is_running() {
+            self.cancel_query().await?;
+            return Ok(EventState::Consumed);
+        }
         self.keys.push(key);
         if self.components_event(self.keys.clone()).await?.is_consumed() {
             self.keys.clear();
@@ -262,16 +578,94 @@ impl<'a> App<'a> {
     pub async fn action_event(&mut self, r: Event) -> anyhow::Result<EventState> {
         match r {
             Event::RedrawDatabase(focus) => {
-                self.update_databases(focus).await?;
+                self.update_databases(focus)?;
                 return Ok(EventState::Consumed)
             },
             Event::RedrawTable(focus) => {
-                self.update_record_table(focus, None, 0).await?;
+                self.update_record_table(focus, None, 0)?;
                 return Ok(EventState::Consumed)
             },
             Event::OrderByTable((order, selected_column)) => {
                 let orderby = if order.len() > 0 { Some(order) } else { None };
-                self.update_record_table(true, orderby, selected_column).await?;
+                self.update_record_table(true, orderby, selected_column)?;
+                return Ok(EventState::Consumed)
+            }
+            Event::ExecuteSql(queries) => {
+                let pool = self.pool.clone().unwrap();
+                let (mut sink, more) = RowSink::new(self.config.settings.row_limit as usize, self.store.clone());
+                self.spawn_query("running query", async move {
+                    pool.stream(&queries, &mut sink).await?;
+                    Ok(QueryOutput::Streamed)
+                })?;
+                // dropping the sender ends the previous query if it still waits for more
+                self.more_rows = Some(more);
+                self.waiting_rows = None;
+                return Ok(EventState::Consumed)
+            }
+            Event::ExplainSql(query, analyze) => {
+                let pool = self.pool.clone().unwrap();
+                self.spawn_query(if analyze { "analyzing query" } else { "explaining query" }, async move {
+                    let plan = pool.explain(&query, analyze).await?;
+                    Ok(QueryOutput::Plan { query, analyze, plan })
+                })?;
+                return Ok(EventState::Consumed)
+            }
+            Event::CompareSchemas(target) => {
+                self.compare_schemas(target)?;
+                return Ok(EventState::Consumed)
+            }
+            Event::Export(source, format, path) => {
+                self.export(source, format, path)?;
+                return Ok(EventState::Consumed)
+            }
+            Event::ExportProgress(rows) => {
+                self.progress.set_label(format!("exporting {} rows", format_count(rows)));
+                return Ok(EventState::Consumed)
+            }
+            Event::Import(request) => {
+                self.import(request)?;
+                return Ok(EventState::Consumed)
+            }
+            Event::ImportProgress(rows) => {
+                self.progress.set_label(format!("importing {} rows", format_count(rows)));
+                return Ok(EventState::Consumed)
+            }
+            Event::Dump(database, path) => {
+                self.dump(database, path)?;
+                return Ok(EventState::Consumed)
+            }
+            Event::DumpProgress(rows) => {
+                self.progress.set_label(format!("dumping {} rows", format_count(rows)));
+                return Ok(EventState::Consumed)
+            }
+            Event::Restore(database, path) => {
+                self.restore(database, path)?;
+                return Ok(EventState::Consumed)
+            }
+            Event::RestoreProgress(done, percent) => {
+                self.progress.set_label(format!("restoring statement {}, {}% of the script read", done, percent));
+                return Ok(EventState::Consumed)
+            }
+            Event::QueryRows(batch) => {
+                // the task goes on with the next statement unless it waits for more rows
+                if batch.more || batch.done {
+                    let task = self.progress.stop();
+                    self.waiting_rows = if batch.more { task } else { None };
+                }
+                self.sql_editor.set_rows(*batch);
+                return Ok(EventState::Consumed)
+            }
+            Event::QueryFinished(result) => {
+                // a streamed script already stopped the progress with its last batch
+                if !matches!(result.as_deref(), Ok(QueryOutput::Streamed)) {
+                    self.progress.stop();
+                }
+                self.query_finished(*result.map_err(|e| anyhow::anyhow!(e))?).await?;
+                return Ok(EventState::Consumed)
+            }
+            Event::TablesLoaded(database, children) => {
+                let children = children.map_err(|e| anyhow::anyhow!(e));
+                self.databases.set_children(database, children, &mut self.updater)?;
                 return Ok(EventState::Consumed)
             }
             Event::ToggleTree => {
@@ -294,12 +688,18 @@ impl<'a> App<'a> {
 
         match self.focus {
             Focus::ConnectionList => {
+                if self.connections.form.is_visible() && key[0] == self.config.key_config.test_connection {
+                    self.test_connection()?;
+                    return Ok(EventState::Consumed);
+                }
                 if self.connections.event(&key)?.is_consumed() {
+                    // the compare tab lists the connections too
+                    self.config.conn = self.connections.connections().to_vec();
                     return Ok(EventState::Consumed);
                 }
                 
                 if key[0] == self.config.key_config.enter {
-                    self.update_databases(true).await?;
+                    self.update_databases(true)?;
                     self.recents.reset();
                     return Ok(EventState::Consumed);
                 }
@@ -311,17 +711,10 @@ impl<'a> App<'a> {
                 if key[0] == self.config.key_config.enter {
                     let recent = self.recents.selected_recent().map(|r| r.clone());
                     if let Some(Recent{id, database, table}) = recent {
+                        self.load_records(database.clone(), table.clone(), None, None, 0, false)?;
                         self.databases.set_selection(id);
                         self.recents.add(id, &database, &table);
                         self.record_table.reset();
-                        let (headers, records) = self
-                            .pool
-                            .as_ref()
-                            .unwrap()
-                            .get_records(&database, &table, 0, None, None)
-                            .await?;
-                        self.record_table
-                            .update(records, headers, database.clone(), table.clone(), 0);
                         self.properties
                             .update(database.clone(), table.clone(), self.pool.as_ref().unwrap())
                             .await?;
@@ -336,43 +729,19 @@ impl<'a> App<'a> {
                 return Ok(EventState::Consumed);
             }
             Focus::DabataseList => {
-                if self.databases.event(&key)?.is_consumed() ||
-                    self.databases.async_event(key[0], self.pool.as_ref().unwrap(), &self.store).await?.is_consumed() {
+                if self.databases.event(&key)?.is_consumed() {
+                    self.load_tables();
+                    return Ok(EventState::Consumed);
+                }
+                if self.databases.async_event(key[0], self.pool.as_ref().unwrap(), &self.store).await?.is_consumed() {
                     return Ok(EventState::Consumed);
                 }
 
                 if key[0] == self.config.key_config.enter && self.databases.tree_focused() {
                     if let Some((database, table, id)) = self.databases.tree().selected_table() {
+                        self.load_records(database.clone(), table.clone(), None, None, 0, false)?;
                         self.recents.add(id, &database, &table);
                         self.record_table.reset();
-                        // handle null value
-                        let column_headers = self.pool.
-                            as_ref()
-                            .unwrap()
-                            .get_headers(&database, &table).await?;
-                        let (mut headers, records) = self
-                            .pool
-                            .as_ref()
-                            .unwrap()
-                            .get_records(&database, &table, 0, None, None)
-                            .await?;
-                        if headers.len() < 1 {
-                            headers = column_headers;
-                        } else {
-                            // merge headers, use real column type
-                            for (i, header) in headers.iter_mut().enumerate() {
-                                if let Some(h) = column_headers.get(i) {
-                                    if h.name == header.name && h.col_type != ColType::Unknown {
-                                        header.col_type = h.col_type.clone();
-                                    }
-                                }
-                            } 
-                        }
-                        if self.updater.update_columns(&database, &table, &headers) {
-                            self.sql_editor.update_db_metadata(self.updater.db_metadata());
-                        }
-                        self.record_table
-                            .update(records, headers, database.clone(), table.clone(), 0);
                         self.properties
                             .update(database.clone(), table.clone(), self.pool.as_ref().unwrap())
                             .await?;
@@ -395,9 +764,26 @@ impl<'a> App<'a> {
                             }
                         }
 
+                        if key[0] == self.config.key_config.count_rows {
+                            if let Some((database, table, _)) = self.databases.tree().selected_table() {
+                                let query = self.pool.as_ref().unwrap().database_type().count_query(&database, &table, self.table_filter().as_deref());
+                                let pool = self.pool.clone().unwrap();
+                                self.spawn_load("counting rows", false, async move {
+                                    match pool.execute(&query).await? {
+                                        ExecuteResult::Read { rows, .. } => match rows.first().and_then(|row| row.first()) {
+                                            Some(count) => Ok(QueryOutput::Count(count.data.parse()?)),
+                                            None => anyhow::bail!("no count of {}", table.name),
+                                        },
+                                        _ => anyhow::bail!("no count of {}", table.name),
+                                    }
+                                })?;
+                            }
+                            return Ok(EventState::Consumed);
+                        }
+
                         if key[0] == self.config.key_config.enter && self.record_table.filter_focused()
                         {
-                            self.update_record_table(true, None, 0).await?;
+                            self.update_record_table(true, None, 0)?;
                             self.record_table.filter.save_history()
                         }
 
@@ -411,32 +797,17 @@ impl<'a> App<'a> {
                         }
 
                         if let Some(index) = self.record_table.table.selected_row.selected() {
-                            if index.saturating_add(1) % self.config.settings.page_size as usize == 0 {
+                            if index.saturating_add(1) % self.config.settings.page_size as usize == 0 && !self.progress.is_running() {
                                 if let Some((database, table, _)) =
                                     self.databases.tree().selected_table()
                                 {
-                                    let (_, records) = self
-                                        .pool
-                                        .as_ref()
-                                        .unwrap()
-                                        .get_records(
-                                            &database,
-                                            &table,
-                                            index as u16,
-                                            if self.record_table.filter.input_str().is_empty() {
-                                                None
-                                            } else {
-                                                Some(self.record_table.filter.input_str())
-                                            },
-                                            None,
-                                        )
-                                        .await?;
-                                    if !records.is_empty() {
-                                        let records = records.into_iter().map(|row| row.into_iter().map(|cell| Arc::new(RwLock::new(cell))).collect::<Vec<_>>()).collect::<Vec<_>>();
-                                        self.record_table.table.rows.extend(records);
-                                    } else {
-                                        self.record_table.table.end()
-                                    }
+                                    let page = self.record_table.table.next_page();
+                                    let filter = self.table_filter();
+                                    let pool = self.pool.clone().unwrap();
+                                    self.spawn_load("loading more records", false, async move {
+                                        let (_, rows) = pool.get_records(&database, &table, &page, filter).await?;
+                                        Ok(QueryOutput::NextPage(rows))
+                                    })?;
                                 }
                             }
                         };
@@ -449,6 +820,7 @@ impl<'a> App<'a> {
                                 .await?
                                 .is_consumed()
                         {
+                            self.fetch_more_rows().await?;
                             return Ok(EventState::Consumed);
                         };
                     }
@@ -457,6 +829,20 @@ impl<'a> App<'a> {
                             return Ok(EventState::Consumed);
                         };
                     }
+                    Tab::Explain => {
+                        if self.explain.event(&key)?.is_consumed()
+                            || self.explain.async_event(key[0], self.pool.as_ref().unwrap(), &self.store).await?.is_consumed()
+                        {
+                            return Ok(EventState::Consumed);
+                        };
+                    }
+                    Tab::Compare => {
+                        if self.compare.event(&key)?.is_consumed()
+                            || self.compare.async_event(key[0], self.pool.as_ref().unwrap(), &self.store).await?.is_consumed()
+                        {
+                            return Ok(EventState::Consumed);
+                        };
+                    }
                 };
             }
         }
@@ -534,6 +920,16 @@ impl<'a> App<'a> {
     }
 }
 
+async fn connect(conn: &Connection, page_size: u16) -> anyhow::Result<Box<dyn Pool>> {
+    let database_url = conn.database_url().await?;
+    match conn.get_type() {
+        DatabaseType::MySql => Ok(Box::new(MySqlPool::new(&database_url, page_size).await?)),
+        DatabaseType::Postgres => Ok(Box::new(PostgresPool::new(&database_url, page_size).await?)),
+        DatabaseType::Mssql => Ok(Box::new(MssqlPool::new(&database_url, page_size).await?)),
+        DatabaseType::Sqlite => Ok(Box::new(SqlitePool::new(&database_url, page_size).await?)),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{App, Config, EventState, Key};
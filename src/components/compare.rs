@@ -0,0 +1,242 @@
+use super::{utils::scroll_vertical::VerticalScroll, Component, DrawableComponent, EventState};
+use crate::clipboard::copy_to_clipboard;
+use crate::components::help_info::{self, HelpInfo};
+use crate::config::{Connection, DatabaseType, KeyConfig, Settings};
+use crate::database::{Comparison, Pool};
+use crate::event::{Event, Key, Store};
+use crate::ui::scrolllist::draw_list_block;
+use anyhow::Result;
+use async_trait::async_trait;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+#[derive(PartialEq)]
+enum Focus {
+    Targets,
+    Result,
+}
+
+// the database selected in the tree against another database of the server or a configured connection
+pub struct CompareComponent {
+    targets: Vec<(String, Connection)>,
+    selection: usize,
+    comparison: Option<Comparison>,
+    scroll: u16,
+    focus: Focus,
+    list_scroll: VerticalScroll,
+    key_config: KeyConfig,
+    settings: Settings,
+}
+
+impl CompareComponent {
+    pub fn new(key_config: KeyConfig, settings: Settings) -> Self {
+        Self {
+            targets: vec![],
+            selection: 0,
+            comparison: None,
+            scroll: 0,
+            focus: Focus::Targets,
+            list_scroll: VerticalScroll::new(false, false),
+            key_config,
+            settings,
+        }
+    }
+
+    pub fn set_targets(&mut self, current: &Connection, databases: &[String], connections: &[Connection]) {
+        // a sqlite file is a single database
+        let databases = match current.get_type() {
+            DatabaseType::Sqlite => vec![],
+            _ => databases.iter().map(|name| current.with_database(name)).collect(),
+        };
+        let current_url = current.configured_url().ok();
+        let others = connections.iter().filter(|c| c.configured_url().ok() != current_url).cloned();
+        self.targets = databases.into_iter()
+            .chain(others)
+            .flat_map(|conn| conn.database_url_with_name().ok().map(|label| (label, conn)))
+            .collect();
+        self.selection = 0;
+        self.comparison = None;
+        self.focus = Focus::Targets;
+        self.list_scroll.reset();
+    }
+
+    pub fn update(&mut self, comparison: Comparison) {
+        self.comparison = Some(comparison);
+        self.scroll = 0;
+        self.focus = Focus::Result;
+    }
+
+    fn script(&self) -> Option<String> {
+        self.comparison.as_ref().map(|c| c.script.join("\n"))
+    }
+
+    fn lines(&self) -> Vec<Spans<'static>> {
+        let comparison = match &self.comparison {
+            Some(comparison) => comparison,
+            None => return vec![],
+        };
+        if comparison.differences.is_empty() {
+            return vec![Spans::from("the schemas are the same")];
+        }
+        let mut lines = comparison.differences.iter().map(|d| {
+            let line = d.to_string();
+            let color = match line.chars().next() {
+                Some('+') => Color::Green,
+                Some('-') => Color::Red,
+                _ => Color::Yellow,
+            };
+            Spans::from(Span::styled(line, Style::default().fg(color)))
+        }).collect::<Vec<_>>();
+        lines.push(Spans::from(""));
+        lines.extend(comparison.script.iter().flat_map(|s| s.lines()).map(|l| Spans::from(l.to_string())));
+        lines
+    }
+
+    fn move_selection(&mut self, lines: isize) {
+        let last = self.targets.len().saturating_sub(1) as isize;
+        self.selection = (self.selection as isize + lines).clamp(0, last) as usize;
+    }
+
+    fn scroll_result(&mut self, lines: i32) {
+        let max = self.lines().len().saturating_sub(1) as i32;
+        self.scroll = (self.scroll as i32 + lines).clamp(0, max) as u16;
+    }
+}
+
+impl DrawableComponent for CompareComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(area);
+        let style = |focus: Focus| if focused && self.focus == focus { Style::default() } else { Style::default().fg(Color::DarkGray) };
+
+        let block = Block::default().title("Compare with").borders(Borders::ALL).style(style(Focus::Targets));
+        let inner = block.inner(chunks[0]);
+        f.render_widget(block, chunks[0]);
+        self.list_scroll.update(self.selection, self.targets.len(), inner.height as usize);
+        let items = self.targets.iter()
+            .enumerate()
+            .skip(self.list_scroll.get_top())
+            .take(inner.height as usize)
+            .map(|(i, (label, _))| {
+                let style = if i == self.selection { Style::default().bg(self.settings.color) } else { Style::default() };
+                Spans::from(Span::styled(format!("{:w$}", label, w = inner.width as usize), style))
+            });
+        draw_list_block(f, inner, Block::default().borders(Borders::NONE), items);
+
+        let title = match &self.comparison {
+            Some(c) => format!("{} -> {} ({} differences)", c.source, c.target, c.differences.len()),
+            None => "Schema diff".to_string(),
+        };
+        let block = Block::default().title(title).borders(Borders::ALL).style(style(Focus::Result));
+        let paragraph = if self.comparison.is_some() {
+            Paragraph::new(self.lines()).scroll((self.scroll, 0))
+        } else {
+            Paragraph::new(format!(
+                "compare the database selected in the tree with the one picked here [{}], the migration brings the picked one in line",
+                self.key_config.enter,
+            ))
+        };
+        f.render_widget(paragraph.block(block), chunks[1]);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Component for CompareComponent {
+    fn helps(&self, out: &mut Vec<HelpInfo>) {
+        out.push(HelpInfo::new(help_info::compare_schemas(&self.key_config)));
+        out.push(HelpInfo::new(help_info::copy_migration(&self.key_config)));
+    }
+
+    fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        let key = key[0];
+        match self.focus {
+            Focus::Targets => {
+                if key == self.key_config.scroll_down || key == self.key_config.move_down {
+                    self.move_selection(1);
+                } else if key == self.key_config.scroll_up || key == self.key_config.move_up {
+                    self.move_selection(-1);
+                } else if key == self.key_config.scroll_down_multiple_lines {
+                    self.move_selection(10);
+                } else if key == self.key_config.scroll_up_multiple_lines {
+                    self.move_selection(-10);
+                } else if key == self.key_config.scroll_to_top {
+                    self.selection = 0;
+                } else if key == self.key_config.scroll_to_bottom {
+                    self.move_selection(self.targets.len() as isize);
+                } else if key == self.key_config.focus_right && self.comparison.is_some() {
+                    self.focus = Focus::Result;
+                } else {
+                    return Ok(EventState::NotConsumed);
+                }
+            },
+            Focus::Result => {
+                if key == self.key_config.scroll_down || key == self.key_config.move_down {
+                    self.scroll_result(1);
+                } else if key == self.key_config.scroll_up || key == self.key_config.move_up {
+                    self.scroll_result(-1);
+                } else if key == self.key_config.scroll_down_multiple_lines {
+                    self.scroll_result(10);
+                } else if key == self.key_config.scroll_up_multiple_lines {
+                    self.scroll_result(-10);
+                } else if key == self.key_config.scroll_to_top {
+                    self.scroll = 0;
+                } else if key == self.key_config.scroll_to_bottom {
+                    self.scroll_result(i32::MAX / 2);
+                } else if key == self.key_config.focus_left {
+                    self.focus = Focus::Targets;
+                } else if key == self.key_config.copy {
+                    if let Some(script) = self.script() {
+                        copy_to_clipboard(&script)?;
+                    }
+                } else {
+                    return Ok(EventState::NotConsumed);
+                }
+            },
+        }
+        Ok(EventState::Consumed)
+    }
+
+    async fn async_event(&mut self, key: Key, _pool: &Box<dyn Pool>, store: &Store) -> Result<EventState> {
+        if key == self.key_config.enter && self.focus == Focus::Targets {
+            if let Some((_, target)) = self.targets.get(self.selection) {
+                store.dispatch(Event::CompareSchemas(target.clone())).await?;
+            }
+            return Ok(EventState::Consumed);
+        }
+        Ok(EventState::NotConsumed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompareComponent, Connection, KeyConfig, Settings};
+    use crate::event::Key;
+    use crate::components::Component as _;
+
+    #[test]
+    fn test_set_targets() {
+        let mut compare = CompareComponent::new(KeyConfig::default(), Settings::default());
+        let current = Connection::new("postgres://root@localhost:5432/app").unwrap();
+        let staging = Connection::new("postgres://root@staging:5432/app").unwrap();
+        compare.set_targets(&current, &["app".to_string(), "app_test".to_string()], &[current.clone(), staging]);
+        let labels = compare.targets.iter().map(|(label, _)| label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, [
+            "postgres://root:@localhost:5432/app?sslmode=disable",
+            "postgres://root:@localhost:5432/app_test?sslmode=disable",
+            "postgres://root@staging:5432/app",
+        ]);
+        assert!(compare.event(&[Key::Char('G')]).unwrap().is_consumed());
+        assert_eq!(compare.selection, 2);
+        // nothing to look at yet
+        assert!(!compare.event(&[Key::Right]).unwrap().is_consumed());
+    }
+}
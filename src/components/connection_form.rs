@@ -0,0 +1,206 @@
+use super::{Component, DrawableComponent, EventState};
+use crate::components::help_info::HelpInfo;
+use crate::config::{Connection, KeyConfig, CONNECTION_FIELDS};
+use crate::event::Key;
+use anyhow::Result;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+// adds a connection or edits one of the list, config.toml is written on enter
+pub struct ConnectionFormComponent {
+    values: Vec<String>,
+    selected: usize,
+    index: Option<usize>, // the edited connection, none for a new one
+    message: Option<String>,
+    visible: bool,
+    key_config: KeyConfig,
+}
+
+impl ConnectionFormComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            values: vec![],
+            selected: 0,
+            index: None,
+            message: None,
+            visible: false,
+            key_config,
+        }
+    }
+
+    pub fn open(&mut self, index: Option<usize>, conn: Option<&Connection>) -> Result<()> {
+        self.values = match conn {
+            Some(conn) => CONNECTION_FIELDS.iter().map(|key| conn.field(key)).collect(),
+            None => CONNECTION_FIELDS.iter().map(|key| match *key {
+                "type" => "postgres".to_string(),
+                "host" => "localhost".to_string(),
+                "port" => "5432".to_string(),
+                _ => String::new(),
+            }).collect(),
+        };
+        self.selected = 0;
+        self.index = index;
+        self.message = None;
+        self.show()
+    }
+
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+}
+
+impl DrawableComponent for ConnectionFormComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, _area: Rect, _focused: bool) -> Result<()> {
+        if self.visible {
+            const SIZE: (u16, u16) = (72, CONNECTION_FIELDS.len() as u16 + 5);
+            let area = Rect::new(
+                (f.size().width.saturating_sub(SIZE.0)) / 2,
+                (f.size().height.saturating_sub(SIZE.1)) / 2,
+                SIZE.0.min(f.size().width),
+                SIZE.1.min(f.size().height),
+            );
+
+            f.render_widget(Clear, area);
+            f.render_widget(
+                Block::default()
+                    .title(if self.index.is_some() { "Edit connection" } else { "New connection" })
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick),
+                area,
+            );
+
+            let chunks = Layout::default()
+                .vertical_margin(1)
+                .horizontal_margin(1)
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(1), Constraint::Length(1)].as_ref())
+                .split(area);
+
+            const LABEL: usize = 14;
+            let lines = CONNECTION_FIELDS.iter().zip(self.values.iter()).enumerate().map(|(i, (key, value))| {
+                let value = if *key == "password" { "*".repeat(value.chars().count()) } else { value.clone() };
+                let style = if i == self.selected { Style::default().fg(Color::Yellow) } else { Style::default() };
+                Spans::from(vec![
+                    Span::styled(format!("{:width$}", key, width = LABEL), Style::default().fg(Color::DarkGray)),
+                    Span::styled(value, style),
+                ])
+            }).collect::<Vec<_>>();
+            f.render_widget(Paragraph::new(lines), chunks[0]);
+
+            // a url takes the place of the other fields
+            let message = self.message.clone().unwrap_or_else(|| "database_url, when set, is used instead of the fields".to_string());
+            f.render_widget(Paragraph::new(Span::styled(message, Style::default().fg(Color::DarkGray))), chunks[1]);
+            f.render_widget(
+                Paragraph::new(Spans::from(format!(
+                    "save [{}] test [{}] next field [{}] close [{}]",
+                    self.key_config.enter, self.key_config.test_connection, Key::Tab, self.key_config.exit_popup
+                )))
+                .alignment(Alignment::Right),
+                chunks[2],
+            );
+
+            let width = self.values.get(self.selected).map_or(0, |v| v.chars().count());
+            f.set_cursor(
+                (chunks[0].x + (LABEL + width) as u16).min(chunks[0].right().saturating_sub(1)),
+                chunks[0].y + self.selected as u16,
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Component for ConnectionFormComponent {
+    fn helps(&self, _out: &mut Vec<HelpInfo>) {}
+
+    // enter and the test need the list and a pool, see ConnectionsComponent and App
+    fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        if !self.visible {
+            return Ok(EventState::NotConsumed);
+        }
+        let value = &mut self.values[self.selected];
+        match key[0] {
+            key if key == self.key_config.exit_popup => self.hide(),
+            Key::Tab | Key::Down => self.selected = (self.selected + 1) % CONNECTION_FIELDS.len(),
+            Key::Up => self.selected = self.selected.checked_sub(1).unwrap_or(CONNECTION_FIELDS.len() - 1),
+            Key::Backspace | Key::Delete => {
+                value.pop();
+            },
+            // the last word, like the other editors
+            Key::Ctrl('w') => {
+                let end = value.trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_').len();
+                if end == value.len() {
+                    value.pop();
+                } else {
+                    value.truncate(end);
+                }
+            },
+            Key::Char(c) => value.push(c),
+            _ => return Ok(EventState::NotConsumed),
+        }
+        self.message = None;
+        Ok(EventState::Consumed)
+    }
+
+    fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn show(&mut self) -> Result<()> {
+        self.visible = true;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Component, ConnectionFormComponent, KeyConfig};
+    use crate::config::Connection;
+    use crate::event::Key;
+
+    #[test]
+    fn test_edit_fields() {
+        let mut form = ConnectionFormComponent::new(KeyConfig::default());
+        form.open(None, None).unwrap();
+        let write = |form: &mut ConnectionFormComponent, text: &str| text.chars().for_each(|c| { form.event(&[Key::Char(c)]).unwrap(); });
+        write(&mut form, "app");
+        // up from the name is the url at the bottom
+        form.event(&[Key::Up]).unwrap();
+        form.event(&[Key::Backspace]).unwrap();
+        for _ in 0..5 {
+            form.event(&[Key::Tab]).unwrap();
+        }
+        write(&mut form, "postgres ");
+        form.event(&[Key::Ctrl('w')]).unwrap();
+        form.event(&[Key::Ctrl('w')]).unwrap();
+        write(&mut form, "root");
+        assert_eq!(form.values()[0], "app");
+        assert_eq!(form.values()[4], "root");
+        assert_eq!(form.values()[9], "");
+
+        let conn = Connection::from_fields(form.values(), None).unwrap();
+        assert_eq!(conn.field("host"), "localhost");
+        assert_eq!(conn.field("port"), "5432");
+
+        form.event(&[Key::Esc]).unwrap();
+        assert!(!form.is_visible());
+    }
+}
@@ -1,9 +1,10 @@
-use super::{Component, EventState, StatefulDrawableComponent};
+use super::{Component, ConnectionFormComponent, DrawableComponent, EventState, StatefulDrawableComponent};
 use crate::components::help_info::HelpInfo;
-use crate::config::{Connection, KeyConfig, Settings};
+use crate::config::{delete_connection, save_connection, Connection, KeyConfig, Settings};
 use crate::event::Key;
 use crate::clipboard::copy_to_clipboard;
 use anyhow::Result;
+use std::path::PathBuf;
 use tui::{
     backend::Backend,
     layout::Rect,
@@ -14,14 +15,17 @@ use tui::{
 };
 
 pub struct ConnectionsComponent<'a> {
-    connections: &'a Vec<Connection>,
+    connections: Vec<Connection>,
     state: ListState,
+    path: Option<PathBuf>, // config.toml, where added and edited connections go
+    pub form: ConnectionFormComponent,
+    deleting: bool, // the selected connection goes on the next delete
     key_config: &'a KeyConfig,
     settings: &'a Settings,
 }
 
 impl<'a> ConnectionsComponent<'a> {
-    pub fn new(key_config: &'a KeyConfig, connections: &'a Vec<Connection>, settings: &'a Settings) -> Self {
+    pub fn new(key_config: &'a KeyConfig, connections: Vec<Connection>, path: Option<PathBuf>, settings: &'a Settings) -> Self {
         let mut state = ListState::default();
         if !connections.is_empty() {
             state.select(Some(0));
@@ -30,10 +34,50 @@ impl<'a> ConnectionsComponent<'a> {
             connections,
             key_config,
             state,
+            path,
+            form: ConnectionFormComponent::new(key_config.clone()),
+            deleting: false,
             settings,
         }
     }
 
+    pub fn connections(&self) -> &[Connection] {
+        &self.connections
+    }
+
+    // the connection of the form, to test it before it is saved
+    pub fn form_connection(&self) -> Result<Connection> {
+        let edited = self.form.index().and_then(|i| self.connections.get(i));
+        Connection::from_fields(self.form.values(), edited)
+    }
+
+    fn config_path(&self) -> Result<&PathBuf> {
+        self.path.as_ref().ok_or_else(|| anyhow::anyhow!("there is no config file to save connections to"))
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let conn = self.form_connection()?;
+        save_connection(self.config_path()?, &self.connections, self.form.index(), self.form.values())?;
+        match self.form.index() {
+            Some(index) => self.connections[index] = conn,
+            None => {
+                self.connections.push(conn);
+                self.state.select(Some(self.connections.len() - 1));
+            },
+        }
+        self.form.hide();
+        Ok(())
+    }
+
+    fn delete(&mut self) -> Result<()> {
+        if let Some(index) = self.state.selected() {
+            delete_connection(self.config_path()?, &self.connections, index)?;
+            self.connections.remove(index);
+            self.state.select(if self.connections.is_empty() { None } else { Some(index.min(self.connections.len() - 1)) });
+        }
+        Ok(())
+    }
+
     fn next_connection(&mut self, lines: usize) {
         let i = match self.state.selected() {
             Some(i) => {
@@ -96,8 +140,12 @@ impl<'a> StatefulDrawableComponent for ConnectionsComponent<'a> {
                     .style(Style::default()),
             )
         }
+        let title = match self.selected_connection() {
+            Some(c) if self.deleting => format!("Delete {}? press {} again", c.database_url_with_name()?, self.key_config.delete),
+            _ => format!("Connections, add [{}] edit [{}] delete [{}]", self.key_config.insert_row, self.key_config.edit_cell, self.key_config.delete),
+        };
         let connections = List::new(connections)
-            .block(Block::default().borders(Borders::ALL).title("Connections"))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .highlight_style(Style::default().bg(self.settings.color))
             .style(Style::default());
 
@@ -110,6 +158,7 @@ impl<'a> StatefulDrawableComponent for ConnectionsComponent<'a> {
 
         f.render_widget(Clear, area);
         f.render_stateful_widget(connections, area, &mut self.state);
+        self.form.draw(f, area, false)?;
         Ok(())
     }
 }
@@ -118,8 +167,32 @@ impl<'a> Component for ConnectionsComponent<'a> {
     fn helps(&self, _out: &mut Vec<HelpInfo>) {}
 
     fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        if self.form.is_visible() {
+            if key[0] == self.key_config.enter {
+                self.save()?;
+                return Ok(EventState::Consumed);
+            }
+            self.form.event(key)?;
+            return Ok(EventState::Consumed);
+        }
         let key = key[0];
-        if key == self.key_config.scroll_down {
+        let deleting = std::mem::take(&mut self.deleting);
+        if key == self.key_config.insert_row {
+            self.form.open(None, None)?;
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.edit_cell {
+            if let Some(index) = self.state.selected() {
+                self.form.open(Some(index), self.connections.get(index))?;
+            }
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.delete {
+            if deleting {
+                self.delete()?;
+            } else {
+                self.deleting = self.state.selected().is_some();
+            }
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_down {
             self.next_connection(1);
             return Ok(EventState::Consumed);
         } else if key == self.key_config.scroll_up {
@@ -139,7 +212,8 @@ impl<'a> Component for ConnectionsComponent<'a> {
             return Ok(EventState::Consumed);
         } else if key == self.key_config.copy {
             if let Some(c) = self.selected_connection() {
-                let s = c.database_url()?;
+                // the password lookup stays off the clipboard
+                let s = c.configured_url()?;
                 copy_to_clipboard(&s)?;
             }
             return Ok(EventState::Consumed);
@@ -1,17 +1,17 @@
 use super::{
-    utils::scroll_vertical::VerticalScroll, Component, DatabaseFilterComponent, DrawableComponent,
-    EventState,
+    utils::{format_count, format_size, scroll_vertical::VerticalScroll}, CommandEditorComponent, Component, DatabaseFilterComponent,
+    DrawableComponent, EventState, StatefulDrawableComponent,
 };
 use crate::components::help_info::{self, HelpInfo};
 use crate::config::{Connection, KeyConfig, Settings};
-use crate::database::{Pool};
-use crate::event::{Key, Store};
+use crate::database::{expand_path, Pool};
+use crate::event::{Event, Key, Store};
 use crate::clipboard::copy_to_clipboard;
 use crate::ui::common_nav;
 use crate::ui::scrolllist::draw_list_block;
 use crate::sql::Updater;
 use anyhow::Result;
-use database_tree::{Database, DatabaseTree, DatabaseTreeItem};
+use database_tree::{Child, Database, DatabaseTree, DatabaseTreeItem, DatabaseTreeItemKind, Table};
 use async_trait::async_trait;
 use std::collections::BTreeSet;
 use std::convert::From;
@@ -34,12 +34,15 @@ const EMPTY_STR: &str = "";
 pub enum Focus {
     Filter,
     Tree,
+    Command, // `:dump` and `:restore` of the selected database
 }
 
 pub struct DatabasesComponent<'a> {
     tree: DatabaseTree,
     filter: DatabaseFilterComponent,
+    command_editor: CommandEditorComponent,
     filterd_tree: Option<DatabaseTree>,
+    loading: BTreeSet<String>, // databases whose children are being fetched
     scroll: VerticalScroll,
     focus: Focus,
     key_config: &'a KeyConfig,
@@ -57,7 +60,9 @@ impl<'a> DatabasesComponent<'a> {
         Self {
             tree: DatabaseTree::default(),
             filter: DatabaseFilterComponent::new(),
+            command_editor: CommandEditorComponent::new("".to_string()),
             filterd_tree: None,
+            loading: BTreeSet::new(),
             scroll: VerticalScroll::new(false, false),
             focus: Focus::Tree,
             key_config,
@@ -66,7 +71,7 @@ impl<'a> DatabasesComponent<'a> {
     }
 
     pub async fn update(&mut self, connection: &Connection, pool: &Box<dyn Pool>, updater: &mut Updater) -> Result<()> {
-        // TODO: load schema first
+        // without a database only the names are listed, the children come when one is expanded
         let databases = match &connection.database {
             Some(database) => vec![Database::new(
                 database.clone(),
@@ -76,11 +81,48 @@ impl<'a> DatabasesComponent<'a> {
         };
         self.tree = DatabaseTree::new(databases.as_slice(), &BTreeSet::new())?;
         self.filterd_tree = None;
+        self.loading.clear();
         self.filter.reset();
         updater.update_from_databases(&databases);
         Ok(())
     }
 
+    // the expanded databases whose children are not asked for yet
+    pub fn load_requests(&mut self) -> Vec<String> {
+        let requests = self.tree.loading_databases().into_iter()
+            .filter(|name| !self.loading.contains(name))
+            .collect::<Vec<_>>();
+        self.loading.extend(requests.iter().cloned());
+        requests
+    }
+
+    // the children of `name` are taken again when they come, a restore changed them
+    pub fn request_reload(&mut self, name: &str) {
+        self.loading.insert(name.to_string());
+    }
+
+    pub fn set_children(&mut self, name: String, children: Result<Vec<Child>>, updater: &mut Updater) -> Result<()> {
+        // an answer for a previous connection
+        if !self.loading.remove(&name) {
+            return Ok(());
+        }
+        match children {
+            Ok(children) => {
+                let database = Database::new(name, children);
+                self.tree.set_children(&database);
+                if self.filterd_tree.is_some() {
+                    self.filterd_tree = Some(self.tree.filter(self.filter.input_str()));
+                }
+                updater.update_from_databases(&[database]);
+                Ok(())
+            },
+            Err(e) => {
+                self.tree.set_unloaded(&name);
+                Err(e)
+            },
+        }
+    }
+
     pub fn set_selection(&mut self, id: usize) {
         self.tree.set_selection(id);
     }
@@ -152,6 +194,26 @@ impl<'a> DatabasesComponent<'a> {
             }
         }
 
+        let suffix = match item.kind() {
+            DatabaseTreeItemKind::Object { object, .. } => Some(object.kind.label().to_string()),
+            DatabaseTreeItemKind::Table { table, .. } => table_stats(table),
+            kind if kind.is_database_loading() => Some("loading...".to_string()),
+            _ => None,
+        };
+        if let Some(suffix) = suffix {
+            let label = format!("{}{}{} ", indent_str, arrow, name);
+            let style = if selected {
+                Style::default().bg(self.settings.color)
+            } else {
+                Style::default()
+            };
+            let suffix = format!("{:w$}", suffix, w = (width as usize).saturating_sub(label.chars().count()));
+            return Spans::from(vec![
+                Span::styled(label, style),
+                Span::styled(suffix, style.fg(Color::DarkGray)),
+            ]);
+        }
+
         Spans::from(Span::styled(
             format!("{}{}{:w$}", indent_str, arrow, name, w = width as usize),
             if selected {
@@ -162,7 +224,7 @@ impl<'a> DatabasesComponent<'a> {
         ))
     }
 
-    fn draw_tree<B: Backend>(&self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
+    fn draw_tree<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
         f.render_widget(
             Block::default()
                 .title("Databases")
@@ -179,7 +241,11 @@ impl<'a> DatabasesComponent<'a> {
             .vertical_margin(1)
             .horizontal_margin(1)
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(2), Constraint::Min(1)].as_ref())
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Min(1),
+                Constraint::Length(if self.focus == Focus::Command { 2 } else { 0 }),
+            ].as_ref())
             .split(area);
 
         self.filter
@@ -218,13 +284,33 @@ impl<'a> DatabasesComponent<'a> {
 
         draw_list_block(f, chunks[1], Block::default().borders(Borders::NONE), items);
         self.scroll.draw(f, chunks[1]);
+        if self.focus == Focus::Command {
+            self.command_editor.draw(f, chunks[2], focused)?;
+        }
 
         Ok(())
     }
+
+    async fn dispatch_command(&mut self, command: &str, store: &Store) -> Result<()> {
+        let (command, args) = command.split_once(' ').map_or((command, ""), |(c, a)| (c, a.trim()));
+        let database = || self.tree().selected_database().ok_or_else(|| anyhow::anyhow!("select a database in the tree"));
+        match command {
+            // `~/<database>.sql` without a path
+            "dump" => {
+                let database = database()?;
+                let path = if args.is_empty() { format!("~/{}.sql", database) } else { args.to_string() };
+                store.dispatch(Event::Dump(database, expand_path(&path))).await?;
+            },
+            "restore" if args.is_empty() => anyhow::bail!("a path is needed, like ~/app.sql"),
+            "restore" => store.dispatch(Event::Restore(database()?, expand_path(args))).await?,
+            _ => {},
+        }
+        Ok(())
+    }
 }
 
-impl<'a> DrawableComponent for DatabasesComponent<'a> {
-    fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
+impl<'a> StatefulDrawableComponent for DatabasesComponent<'a> {
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(100)].as_ref())
@@ -238,7 +324,8 @@ impl<'a> DrawableComponent for DatabasesComponent<'a> {
 #[async_trait]
 impl<'a> Component for DatabasesComponent<'a> {
     fn helps(&self, out: &mut Vec<HelpInfo>) {
-        out.push(HelpInfo::new(help_info::expand_collapse(&self.key_config)))
+        out.push(HelpInfo::new(help_info::expand_collapse(&self.key_config)));
+        out.push(HelpInfo::new(help_info::dump_restore(self.key_config)));
     }
 
     fn event(&mut self, key: &[Key]) -> Result<EventState> {
@@ -246,6 +333,19 @@ impl<'a> Component for DatabasesComponent<'a> {
             self.focus = Focus::Filter;
             return Ok(EventState::Consumed);
         }
+        if key[0] == self.key_config.edit_command && self.focus == Focus::Tree {
+            self.focus = Focus::Command;
+            return Ok(EventState::Consumed);
+        }
+        // enter runs the command in async_event
+        if self.focus == Focus::Command {
+            if key[0] == self.key_config.exit_popup {
+                self.command_editor.reset();
+                self.focus = Focus::Tree;
+                return Ok(EventState::Consumed);
+            }
+            return self.command_editor.event(key);
+        }
 
         if matches!(self.focus, Focus::Filter) {
             self.filterd_tree = if self.filter.input_str().is_empty() {
@@ -293,11 +393,18 @@ impl<'a> Component for DatabasesComponent<'a> {
         &mut self,
         key: crate::event::Key,
         pool: &Box<dyn Pool>,
-        _store: &Store,
+        store: &Store,
     ) -> Result<EventState> {
+        if key == self.key_config.enter && self.focus == Focus::Command {
+            self.focus = Focus::Tree;
+            let command = self.command_editor.value();
+            self.command_editor.reset();
+            self.dispatch_command(command.trim(), store).await?;
+            return Ok(EventState::Consumed)
+        }
         // delete table
         if key == self.key_config.delete {
-            if let Some((database, table, id)) = self.tree.selected_table() {
+            if let (Some((database, table, id)), None) = (self.tree.selected_table(), self.tree.selected_object()) {
                 let sql = pool.database_type().drop_table(&database, &table);
                 pool.execute(&sql).await?;
                 self.tree = self.tree.filter_by_id(id, true);
@@ -306,7 +413,10 @@ impl<'a> Component for DatabasesComponent<'a> {
         }
         // self.key_config.advanced_copy
         if key == self.key_config.advanced_copy {
-            if let Some((database, table, _)) = self.tree.selected_table() {
+            if let Some((database, object)) = self.tree.selected_object() {
+                let definition = pool.get_definition(&database, &object).await?;
+                copy_to_clipboard(&definition)?;
+            } else if let Some((database, table, _)) = self.tree.selected_table() {
                 let ddl = pool.database_type().show_schema(pool, &database, &table).await?;
                 copy_to_clipboard(&ddl)?;
             }
@@ -315,6 +425,16 @@ impl<'a> Component for DatabasesComponent<'a> {
     }
 }
 
+// estimated rows and size, like `~1.2k 64kB`
+fn table_stats(table: &Table) -> Option<String> {
+    let rows = table.rows.map(|rows| format!("~{}", format_count(rows)));
+    let size = table.size.map(format_size);
+    match (rows, size) {
+        (None, None) => None,
+        (rows, size) => Some(rows.into_iter().chain(size).collect::<Vec<_>>().join(" ")),
+    }
+}
+
 fn tree_nav(tree: &mut DatabaseTree, key: Key, key_config: &KeyConfig) -> bool {
     if let Some(common_nav) = common_nav(key, key_config) {
         tree.move_selection(common_nav)
@@ -325,8 +445,9 @@ fn tree_nav(tree: &mut DatabaseTree, key: Key, key_config: &KeyConfig) -> bool {
 
 #[cfg(test)]
 mod test {
-    use super::{Color, Database, DatabaseTreeItem, DatabasesComponent, Span, Spans, Style, KeyConfig, Settings};
-    use database_tree::Table;
+    use super::{Color, Component, Database, DatabaseTree, DatabaseTreeItem, DatabasesComponent, Span, Spans, Style, KeyConfig, Settings, Updater};
+    use database_tree::{MoveSelection, Table};
+    use std::collections::BTreeSet;
 
     #[test]
     fn test_tree_database_tree_item_to_span() {
@@ -340,6 +461,7 @@ mod test {
                     &Database {
                         name: "foo".to_string(),
                         children: Vec::new(),
+                        loaded: true,
                     },
                     false,
                 ),
@@ -360,6 +482,7 @@ mod test {
                     &Database {
                         name: "foo".to_string(),
                         children: Vec::new(),
+                        loaded: true,
                     },
                     false,
                 ),
@@ -386,12 +509,15 @@ mod test {
                     &Database {
                         name: "foo".to_string(),
                         children: Vec::new(),
+                        loaded: true,
                     },
                     &Table {
                         name: "bar".to_string(),
                         create_time: None,
                         update_time: None,
                         engine: None,
+                        rows: None,
+                        size: None,
                         schema: None
                     },
                 ),
@@ -412,12 +538,15 @@ mod test {
                     &Database {
                         name: "foo".to_string(),
                         children: Vec::new(),
+                        loaded: true,
                     },
                     &Table {
                         name: "bar".to_string(),
                         create_time: None,
                         update_time: None,
                         engine: None,
+                        rows: None,
+                        size: None,
                         schema: None
                     },
                 ),
@@ -444,12 +573,15 @@ mod test {
                     &Database {
                         name: "foo".to_string(),
                         children: Vec::new(),
+                        loaded: true,
                     },
                     &Table {
                         name: "barbaz".to_string(),
                         create_time: None,
                         update_time: None,
                         engine: None,
+                        rows: None,
+                        size: None,
                         schema: None
                     },
                 ),
@@ -470,12 +602,15 @@ mod test {
                     &Database {
                         name: "foo".to_string(),
                         children: Vec::new(),
+                        loaded: true,
                     },
                     &Table {
                         name: "barbaz".to_string(),
                         create_time: None,
                         update_time: None,
                         engine: None,
+                        rows: None,
+                        size: None,
                         schema: None
                     },
                 ),
@@ -493,4 +628,52 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn test_load_requests() {
+        let key = KeyConfig::default();
+        let settings = Settings::default();
+        let mut dc = DatabasesComponent::new(&key, &settings);
+        dc.tree = DatabaseTree::new(&[Database::unloaded("foo".to_string())], &BTreeSet::new()).unwrap();
+        let mut updater = Updater::default();
+        assert!(dc.event(&[key.enter]).unwrap().is_consumed());
+        assert_eq!(dc.load_requests(), ["foo".to_string()]);
+        // asked only once
+        assert!(dc.load_requests().is_empty());
+
+        let item = dc.tree.selected_item().unwrap().clone();
+        let spans = dc.tree_item_to_span(item, false, 20, None);
+        assert_eq!(spans.0[1].content.trim_end(), "loading...");
+        assert_eq!(spans.0[1].style, Style::default().fg(Color::DarkGray));
+
+        let table = Table { name: "bar".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        dc.set_children("foo".to_string(), Ok(vec![table.into()]), &mut updater).unwrap();
+        assert!(dc.tree.move_selection(MoveSelection::Down));
+        assert_eq!(dc.tree.selected_table().map(|(_, t, _)| t.name), Some("bar".to_string()));
+        // an answer nobody waits for
+        assert!(dc.set_children("foo".to_string(), Err(anyhow::anyhow!("gone")), &mut updater).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_command() {
+        use super::{Event, Focus, Key, Store};
+        use std::path::Path;
+
+        let key = KeyConfig::default();
+        let settings = Settings::default();
+        let mut dc = DatabasesComponent::new(&key, &settings);
+        dc.tree = DatabaseTree::new(&[Database::unloaded("foo".to_string())], &BTreeSet::new()).unwrap();
+        assert!(dc.event(&[Key::Char(':')]).unwrap().is_consumed());
+        // the tree does not move while typing
+        assert!(dc.event(&[Key::Char('j')]).unwrap().is_consumed());
+        assert!(dc.focus == Focus::Command);
+        assert!(dc.event(&[Key::Esc]).unwrap().is_consumed());
+        assert!(dc.focus == Focus::Tree);
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let store = Store::new(tx);
+        assert!(dc.dispatch_command("restore", &store).await.is_err());
+        dc.dispatch_command("dump /tmp/foo dump.sql", &store).await.unwrap();
+        assert!(matches!(rx.recv().await, Some(Event::Dump(database, path)) if database == "foo" && path == Path::new("/tmp/foo dump.sql")));
+    }
 }
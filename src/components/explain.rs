@@ -0,0 +1,282 @@
+use super::{utils::scroll_vertical::VerticalScroll, Component, DrawableComponent, EventState};
+use crate::components::help_info::{self, HelpInfo};
+use crate::config::{KeyConfig, Settings};
+use crate::database::{PlanNode, Pool};
+use crate::event::{Event, Key, Store};
+use crate::ui::common_nav;
+use crate::ui::scrolllist::draw_list_block;
+use anyhow::Result;
+use async_trait::async_trait;
+use database_tree::MoveSelection;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+// ▸
+const FOLDER_ICON_COLLAPSED: &str = "\u{25b8}";
+// ▾
+const FOLDER_ICON_EXPANDED: &str = "\u{25be}";
+// the steps that take the most on their own are highlighted
+const HIGHLIGHTED: usize = 3;
+
+// a node of the plan, flattened in tree order like the database tree items
+struct PlanItem {
+    node: PlanNode, // without its children
+    own_weight: Option<f64>,
+    indent: u8,
+    has_children: bool,
+    collapsed: bool,
+    visible: bool,
+    rank: Option<usize>, // among the most expensive steps
+}
+
+impl PlanItem {
+    fn details(&self) -> String {
+        let mut details = vec![];
+        if let Some(cost) = self.node.cost {
+            details.push(format!("cost={:.2}", cost));
+        }
+        if let Some(rows) = self.node.rows {
+            details.push(format!("rows={}", rows));
+        }
+        if let Some(time) = self.node.time {
+            details.push(format!("time={:.3}ms", time));
+        }
+        details.join(" ")
+    }
+}
+
+pub struct ExplainComponent {
+    query: Option<String>,
+    analyzed: bool,
+    items: Vec<PlanItem>,
+    selection: usize,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    settings: Settings,
+}
+
+impl ExplainComponent {
+    pub fn new(key_config: KeyConfig, settings: Settings) -> Self {
+        Self {
+            query: None,
+            analyzed: false,
+            items: vec![],
+            selection: 0,
+            scroll: VerticalScroll::new(false, false),
+            key_config,
+            settings,
+        }
+    }
+
+    pub fn update(&mut self, query: String, analyzed: bool, plan: PlanNode) {
+        self.items.clear();
+        flatten(plan, 0, &mut self.items);
+        let mut ranked = self.items.iter().enumerate()
+            .filter_map(|(i, item)| item.own_weight.filter(|w| *w > 0.0).map(|w| (i, w)))
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (rank, (i, _)) in ranked.into_iter().take(HIGHLIGHTED).enumerate() {
+            self.items[i].rank = Some(rank);
+        }
+        self.query = Some(query);
+        self.analyzed = analyzed;
+        self.selection = 0;
+        self.scroll.reset();
+    }
+
+    fn update_visibility(&mut self) {
+        let mut collapsed: Option<u8> = None;
+        for item in self.items.iter_mut() {
+            if let Some(indent) = collapsed {
+                if item.indent > indent {
+                    item.visible = false;
+                    continue;
+                }
+                collapsed = None;
+            }
+            item.visible = true;
+            if item.collapsed {
+                collapsed = Some(item.indent);
+            }
+        }
+    }
+
+    fn parent(&self, index: usize) -> Option<usize> {
+        let indent = self.items.get(index)?.indent;
+        (0..index).rev().find(|i| self.items[*i].indent < indent)
+    }
+
+    // the visible item `n` steps away from the selection
+    fn step(&self, n: isize) -> usize {
+        let visible = self.items.iter().enumerate().filter(|(_, item)| item.visible).map(|(i, _)| i).collect::<Vec<_>>();
+        let position = visible.iter().position(|i| *i == self.selection).unwrap_or(0) as isize;
+        let position = (position + n).clamp(0, visible.len().saturating_sub(1) as isize);
+        visible.get(position as usize).copied().unwrap_or(0)
+    }
+
+    fn move_selection(&mut self, nav: MoveSelection) -> bool {
+        if self.items.is_empty() {
+            return false;
+        }
+        let item = &self.items[self.selection];
+        match nav {
+            MoveSelection::Up => self.selection = self.step(-1),
+            MoveSelection::Down => self.selection = self.step(1),
+            MoveSelection::MultipleUp => self.selection = self.step(-10),
+            MoveSelection::MultipleDown => self.selection = self.step(10),
+            MoveSelection::Top => self.selection = 0,
+            MoveSelection::End => self.selection = self.step(self.items.len() as isize),
+            MoveSelection::Left if item.has_children && !item.collapsed => self.set_collapsed(true),
+            MoveSelection::Left => self.selection = self.parent(self.selection).unwrap_or(self.selection),
+            MoveSelection::Right if item.collapsed => self.set_collapsed(false),
+            MoveSelection::Right => self.selection = self.step(1),
+            MoveSelection::Enter if item.has_children => self.set_collapsed(!item.collapsed),
+            _ => return false,
+        }
+        true
+    }
+
+    fn set_collapsed(&mut self, collapsed: bool) {
+        self.items[self.selection].collapsed = collapsed;
+        self.update_visibility();
+    }
+
+    fn item_to_spans(&self, item: &PlanItem, selected: bool, width: u16) -> Spans<'static> {
+        let indent = " ".repeat(item.indent as usize * 2);
+        let arrow = match (item.has_children, item.collapsed) {
+            (false, _) => " ",
+            (true, true) => FOLDER_ICON_COLLAPSED,
+            (true, false) => FOLDER_ICON_EXPANDED,
+        };
+        let style = match item.rank {
+            Some(0) => Style::default().fg(Color::Red),
+            Some(_) => Style::default().fg(Color::Yellow),
+            None => Style::default(),
+        };
+        let style = if selected { style.bg(self.settings.color) } else { style };
+        let label = format!("{}{}{}", indent, arrow, item.node.label);
+        let details = format!("  {}", item.details());
+        let padding = (width as usize).saturating_sub(label.chars().count() + details.chars().count());
+        Spans::from(vec![
+            Span::styled(label, style),
+            Span::styled(format!("{}{:w$}", details, "", w = padding), if selected { style } else { Style::default().fg(Color::DarkGray) }),
+        ])
+    }
+}
+
+fn flatten(mut node: PlanNode, indent: u8, items: &mut Vec<PlanItem>) {
+    let own_weight = node.own_weight();
+    let children = std::mem::take(&mut node.children);
+    items.push(PlanItem {
+        node,
+        own_weight,
+        indent,
+        has_children: !children.is_empty(),
+        collapsed: false,
+        visible: true,
+        rank: None,
+    });
+    for child in children {
+        flatten(child, indent.saturating_add(1), items);
+    }
+}
+
+impl DrawableComponent for ExplainComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
+        let block = Block::default()
+            .title(if self.analyzed { "Plan (analyzed)" } else { "Plan" })
+            .borders(Borders::ALL)
+            .style(if focused { Style::default() } else { Style::default().fg(Color::DarkGray) });
+        if self.items.is_empty() {
+            let hint = format!("explain the statement under the cursor in the SQL tab with [{}]", self.key_config.explain);
+            f.render_widget(Paragraph::new(hint).block(block), area);
+            return Ok(());
+        }
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let visible = self.items.iter().enumerate().filter(|(_, item)| item.visible).collect::<Vec<_>>();
+        let position = visible.iter().position(|(i, _)| *i == self.selection).unwrap_or(0);
+        self.scroll.update(position, visible.len(), inner.height as usize);
+        let items = visible.iter()
+            .skip(self.scroll.get_top())
+            .take(inner.height as usize)
+            .map(|(i, item)| self.item_to_spans(item, focused && *i == self.selection, inner.width));
+        draw_list_block(f, inner, Block::default().borders(Borders::NONE), items);
+        self.scroll.draw(f, area);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Component for ExplainComponent {
+    fn helps(&self, out: &mut Vec<HelpInfo>) {
+        out.push(HelpInfo::new(help_info::explain_analyze(&self.key_config)));
+    }
+
+    fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        if let Some(nav) = common_nav(key[0], &self.key_config) {
+            return Ok(self.move_selection(nav).into());
+        }
+        Ok(EventState::NotConsumed)
+    }
+
+    async fn async_event(&mut self, key: Key, _pool: &Box<dyn Pool>, store: &Store) -> Result<EventState> {
+        if key == self.key_config.explain_analyze {
+            if let Some(query) = self.query.clone() {
+                store.dispatch(Event::ExplainSql(query, true)).await?;
+            }
+            return Ok(EventState::Consumed);
+        }
+        Ok(EventState::NotConsumed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExplainComponent, KeyConfig, MoveSelection, PlanNode, Settings};
+
+    fn node(label: &str, cost: f64, children: Vec<PlanNode>) -> PlanNode {
+        PlanNode { label: label.to_string(), cost: Some(cost), rows: None, time: None, children }
+    }
+
+    #[test]
+    fn test_update() {
+        let mut explain = ExplainComponent::new(KeyConfig::default(), Settings::default());
+        explain.update("SELECT 1".to_string(), false, node("Hash Join", 100.0, vec![
+            node("Seq Scan on a", 60.0, vec![]),
+            node("Hash", 30.0, vec![node("Seq Scan on b", 25.0, vec![])]),
+        ]));
+        let labels = explain.items.iter().map(|i| (i.node.label.as_str(), i.indent)).collect::<Vec<_>>();
+        assert_eq!(labels, [("Hash Join", 0), ("Seq Scan on a", 1), ("Hash", 1), ("Seq Scan on b", 2)]);
+        // own costs: 10, 60, 5, 25
+        assert_eq!(explain.items.iter().map(|i| i.rank).collect::<Vec<_>>(), [Some(2), Some(0), None, Some(1)]);
+    }
+
+    #[test]
+    fn test_collapse() {
+        let mut explain = ExplainComponent::new(KeyConfig::default(), Settings::default());
+        explain.update("SELECT 1".to_string(), false, node("Hash Join", 100.0, vec![
+            node("Hash", 30.0, vec![node("Seq Scan on b", 25.0, vec![])]),
+            node("Seq Scan on a", 60.0, vec![]),
+        ]));
+        explain.move_selection(MoveSelection::Down);
+        assert!(explain.move_selection(MoveSelection::Left));
+        assert!(explain.items[1].collapsed && !explain.items[2].visible && explain.items[3].visible);
+        explain.move_selection(MoveSelection::Down);
+        assert_eq!(explain.selection, 3);
+        // a leaf goes to its parent
+        explain.move_selection(MoveSelection::Left);
+        assert_eq!(explain.selection, 0);
+        explain.move_selection(MoveSelection::Enter);
+        assert!(explain.items[1..].iter().all(|i| !i.visible));
+        explain.move_selection(MoveSelection::Enter);
+        assert!(explain.items[1].visible && !explain.items[2].visible);
+    }
+}
@@ -106,6 +106,52 @@ pub fn extend_or_shorten_widget_width(key: &KeyConfig) -> HelpText {
     )
 }
 
+pub fn insert_row(key: &KeyConfig) -> HelpText {
+    HelpText::new(format!("Insert row in staged edits [{}]", key.insert_row), CMD_GROUP_TABLE)
+}
+
+pub fn staged_edits(key: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!(
+            "Stage/review/commit/rollback edits [{k}stage,{k}review,{k}commit,{k}rollback]",
+            k = key.edit_command
+        ),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn dump_restore(key: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Dump the selected database to a sql file, or restore one [{}dump <path>, {}restore <path>]", key.edit_command, key.edit_command),
+        CMD_GROUP_DATABASES,
+    )
+}
+
+pub fn export(key: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Export every row as csv/tsv/json/ndjson/markdown/sql [{}export <format> <path>]", key.edit_command),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn yank(key: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Yank the selection as tsv/csv/markdown/json/insert/update/delete/where in [{}]", key.advanced_copy),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn import(key: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Import a csv/tsv/json/ndjson file into the table [{}import <format> <path>]", key.edit_command),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn count_rows(key: &KeyConfig) -> HelpText {
+    HelpText::new(format!("Count rows exactly [{}]", key.count_rows), CMD_GROUP_TABLE)
+}
+
 pub fn tab_records(key: &KeyConfig) -> HelpText {
     HelpText::new(format!("Records [{}]", key.tab_records), CMD_GROUP_TABLE)
 }
@@ -136,6 +182,14 @@ pub fn tab_sql_editor(key: &KeyConfig) -> HelpText {
     HelpText::new(format!("SQL [{}]", key.tab_sql_editor), CMD_GROUP_TABLE)
 }
 
+pub fn tab_explain(key: &KeyConfig) -> HelpText {
+    HelpText::new(format!("Explain [{}]", key.tab_explain), CMD_GROUP_TABLE)
+}
+
+pub fn tab_compare(key: &KeyConfig) -> HelpText {
+    HelpText::new(format!("Compare [{}]", key.tab_compare), CMD_GROUP_TABLE)
+}
+
 pub fn tab_properties(key: &KeyConfig) -> HelpText {
     HelpText::new(
         format!("Properties [{}]", key.tab_properties),
@@ -146,8 +200,8 @@ pub fn tab_properties(key: &KeyConfig) -> HelpText {
 pub fn toggle_tabs(key_config: &KeyConfig) -> HelpText {
     HelpText::new(
         format!(
-            "Tab [{},{},{}]",
-            key_config.tab_records, key_config.tab_properties, key_config.tab_sql_editor
+            "Tab [{},{},{},{},{}]",
+            key_config.tab_records, key_config.tab_properties, key_config.tab_sql_editor, key_config.tab_explain, key_config.tab_compare
         ),
         CMD_GROUP_GENERAL,
     )
@@ -166,6 +220,58 @@ pub fn toggle_property_tabs(key_config: &KeyConfig) -> HelpText {
     )
 }
 
+pub fn cancel_query(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Cancel running query [{}]", key_config.cancel_query),
+        CMD_GROUP_GENERAL,
+    )
+}
+
+pub fn execute_statement(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Run statement under cursor [{}]", key_config.execute_statement),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn explain(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Explain statement under cursor [{}]", key_config.explain),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn explain_analyze(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Explain again with ANALYZE [{}]", key_config.explain_analyze),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn compare_schemas(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Compare the schema with the selected target [{}]", key_config.enter),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn copy_migration(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!("Copy the migration script [{}]", key_config.copy),
+        CMD_GROUP_TABLE,
+    )
+}
+
+pub fn switch_result(key_config: &KeyConfig) -> HelpText {
+    HelpText::new(
+        format!(
+            "Previous/Next statement result [{},{}]",
+            key_config.previous_result, key_config.next_result
+        ),
+        CMD_GROUP_TABLE,
+    )
+}
+
 pub fn help(key_config: &KeyConfig) -> HelpText {
     HelpText::new(
         format!("Help [{}]", key_config.open_help),
@@ -0,0 +1,269 @@
+use super::{utils::scroll_vertical::VerticalScroll, Component, DrawableComponent, EventState};
+use crate::components::help_info::HelpInfo;
+use crate::config::{KeyConfig, Settings};
+use crate::database::{Conflict, ExportFormat, ImportColumn, ImportFile, ImportRequest, Param};
+use crate::event::Key;
+use crate::ui::scrolllist::draw_list_block;
+use anyhow::Result;
+use database_tree::{Database, Table};
+use std::path::PathBuf;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+// rows of the file shown above the mapping
+pub const PREVIEW_ROWS: usize = 5;
+const PREVIEW_CELL_WIDTH: usize = 16;
+
+// maps the fields of a file to the columns of the table before `:import` loads it
+pub struct ImportComponent {
+    target: Option<(Database, Table, ExportFormat, PathBuf)>,
+    file: ImportFile, // the first rows only
+    columns: Vec<(String, String)>, // name and declared type
+    mapping: Vec<Option<usize>>, // the field of each column, none leaves it to the default
+    conflict: Conflict,
+    selection: usize,
+    visible: bool,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    settings: Settings,
+}
+
+impl ImportComponent {
+    pub fn new(key_config: KeyConfig, settings: Settings) -> Self {
+        Self {
+            target: None,
+            file: ImportFile::default(),
+            columns: vec![],
+            mapping: vec![],
+            conflict: Conflict::Fail,
+            selection: 0,
+            visible: false,
+            scroll: VerticalScroll::new(false, false),
+            key_config,
+            settings,
+        }
+    }
+
+    // fields go to the column of the same name, ignoring case
+    pub fn open(&mut self, database: Database, table: Table, format: ExportFormat, path: PathBuf, file: ImportFile, columns: Vec<(String, String)>) -> Result<()> {
+        self.mapping = columns.iter()
+            .map(|(name, _)| file.columns.iter().position(|c| c.eq_ignore_ascii_case(name)))
+            .collect();
+        self.target = Some((database, table, format, path));
+        self.file = file;
+        self.columns = columns;
+        self.conflict = Conflict::Fail;
+        self.selection = 0;
+        self.scroll.reset();
+        self.show()
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn request(&self) -> Option<ImportRequest> {
+        let (database, table, format, path) = self.target.clone()?;
+        let columns = self.columns.iter().zip(self.mapping.iter())
+            .filter_map(|((name, col_type), field)| field.map(|field| ImportColumn { name: name.clone(), col_type: col_type.clone(), field }))
+            .collect();
+        Some(ImportRequest { database, table, format, path, columns, conflict: self.conflict })
+    }
+
+    // through the fields of the file and back to not importing the column
+    fn cycle_field(&mut self, step: isize) {
+        if let Some(field) = self.mapping.get_mut(self.selection) {
+            let choices = self.file.columns.len() as isize + 1;
+            let current = field.map_or(0, |f| f as isize + 1);
+            let next = (current + step).rem_euclid(choices);
+            *field = if next == 0 { None } else { Some(next as usize - 1) };
+        }
+    }
+
+    fn move_selection(&mut self, lines: isize) {
+        let last = self.columns.len().saturating_sub(1) as isize;
+        self.selection = (self.selection as isize + lines).clamp(0, last) as usize;
+    }
+
+    fn preview(&self) -> Vec<Spans<'static>> {
+        let line = |cells: Vec<String>| cells.iter().map(|c| truncate(c, PREVIEW_CELL_WIDTH)).collect::<Vec<_>>().join(" | ");
+        let mut lines = vec![Spans::from(Span::styled(line(self.file.columns.clone()), Style::default().fg(Color::DarkGray)))];
+        lines.extend(self.file.rows.iter().take(PREVIEW_ROWS).map(|row| Spans::from(line(row.iter().map(cell).collect()))));
+        lines
+    }
+}
+
+fn cell(param: &Param) -> String {
+    match param {
+        Param::Null => "NULL".to_string(),
+        Param::Int(v) => v.to_string(),
+        Param::Float(v) => v.to_string(),
+        Param::Bool(v) => v.to_string(),
+        Param::Text(v) => v.replace('\n', " "),
+        Param::Bytes(v) => format!("{} bytes", v.len()),
+    }
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return format!("{:width$}", s, width = width)
+    }
+    let mut out = String::new();
+    for c in s.chars() {
+        if out.width() + 2 > width {
+            break
+        }
+        out.push(c);
+    }
+    format!("{:width$}", format!("{}…", out), width = width)
+}
+
+impl DrawableComponent for ImportComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, _area: Rect, _focused: bool) -> Result<()> {
+        if self.visible {
+            const SIZE: (u16, u16) = (80, 24);
+            let area = Rect::new(
+                (f.size().width.saturating_sub(SIZE.0)) / 2,
+                (f.size().height.saturating_sub(SIZE.1)) / 2,
+                SIZE.0.min(f.size().width),
+                SIZE.1.min(f.size().height),
+            );
+
+            let title = self.target.as_ref().map_or(String::new(), |(_, table, _, path)| {
+                format!("Import {} into {}", path.display(), table.name)
+            });
+            f.render_widget(Clear, area);
+            f.render_widget(Block::default().title(title).borders(Borders::ALL).border_type(BorderType::Thick), area);
+
+            let chunks = Layout::default()
+                .vertical_margin(1)
+                .horizontal_margin(1)
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(PREVIEW_ROWS as u16 + 2),
+                    Constraint::Min(1),
+                    Constraint::Length(1),
+                ].as_ref())
+                .split(area);
+
+            f.render_widget(
+                Paragraph::new(self.preview()).block(Block::default().title("Preview").borders(Borders::BOTTOM)),
+                chunks[0],
+            );
+
+            let name_width = self.columns.iter().map(|(name, _)| name.width()).max().unwrap_or(0);
+            let type_width = self.columns.iter().map(|(_, typ)| typ.width()).max().unwrap_or(0);
+            self.scroll.update(self.selection, self.columns.len(), chunks[1].height as usize);
+            let items = self.columns.iter().zip(self.mapping.iter())
+                .enumerate()
+                .skip(self.scroll.get_top())
+                .take(chunks[1].height as usize)
+                .map(|(i, ((name, typ), field))| {
+                    let field = field.and_then(|f| self.file.columns.get(f)).map_or("(not imported)".to_string(), |f| format!("<- {}", f));
+                    let line = format!("{:nw$} {:tw$} {}", name, typ, field, nw = name_width, tw = type_width);
+                    let style = if i == self.selection { Style::default().bg(self.settings.color) } else { Style::default() };
+                    Spans::from(Span::styled(format!("{:w$}", line, w = chunks[1].width as usize), style))
+                });
+            draw_list_block(f, chunks[1], Block::default().borders(Borders::NONE), items);
+
+            f.render_widget(
+                Paragraph::new(Spans::from(format!(
+                    "on conflict: {}  field [{}/{}] conflict [{}] import [{}] close [{}]",
+                    self.conflict,
+                    self.key_config.scroll_left,
+                    self.key_config.scroll_right,
+                    self.key_config.space,
+                    self.key_config.enter,
+                    self.key_config.exit_popup,
+                )))
+                .alignment(Alignment::Right),
+                chunks[2],
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Component for ImportComponent {
+    fn helps(&self, _out: &mut Vec<HelpInfo>) {}
+
+    fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        if !self.visible {
+            return Ok(EventState::NotConsumed);
+        }
+        let key = key[0];
+        if key == self.key_config.exit_popup {
+            self.hide();
+        } else if key == self.key_config.scroll_down || key == self.key_config.move_down {
+            self.move_selection(1);
+        } else if key == self.key_config.scroll_up || key == self.key_config.move_up {
+            self.move_selection(-1);
+        } else if key == self.key_config.scroll_right || key == self.key_config.focus_right {
+            self.cycle_field(1);
+        } else if key == self.key_config.scroll_left || key == self.key_config.focus_left {
+            self.cycle_field(-1);
+        } else if key == self.key_config.space {
+            self.conflict = self.conflict.next();
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+        Ok(EventState::Consumed)
+    }
+
+    fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn show(&mut self) -> Result<()> {
+        self.visible = true;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportComponent, KeyConfig, Settings};
+    use crate::components::Component as _;
+    use crate::database::{Conflict, ExportFormat, ImportColumn, ImportFile, Param};
+    use crate::event::Key;
+    use database_tree::{Database, Table};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_mapping() {
+        let mut import = ImportComponent::new(KeyConfig::default(), Settings::default());
+        let file = ImportFile {
+            columns: vec!["Name".to_string(), "id".to_string()],
+            rows: vec![vec![Param::Text("a".to_string()), Param::Text("1".to_string())]],
+        };
+        let table = Table { name: "users".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let columns = vec![("id".to_string(), "integer".to_string()), ("name".to_string(), "text".to_string()), ("note".to_string(), "text".to_string())];
+        import.open(Database::new("app".to_string(), vec![]), table, ExportFormat::Csv, PathBuf::from("users.csv"), file, columns).unwrap();
+        assert_eq!(import.mapping, vec![Some(1), Some(0), None]);
+
+        // id goes back to not imported, note takes the first field
+        assert!(import.event(&[Key::Char('l')]).unwrap().is_consumed());
+        assert_eq!(import.mapping[0], None);
+        import.event(&[Key::Char('j')]).unwrap();
+        import.event(&[Key::Char('j')]).unwrap();
+        import.event(&[Key::Right]).unwrap();
+        import.event(&[Key::Char(' ')]).unwrap();
+        let request = import.request().unwrap();
+        assert_eq!(request.columns, vec![
+            ImportColumn { name: "name".to_string(), col_type: "text".to_string(), field: 0 },
+            ImportColumn { name: "note".to_string(), col_type: "text".to_string(), field: 0 },
+        ]);
+        assert_eq!(request.conflict, Conflict::Skip);
+
+        assert!(import.event(&[Key::Esc]).unwrap().is_consumed());
+        assert!(!import.is_visible());
+    }
+}
@@ -1,13 +1,19 @@
 pub mod completion;
 pub mod connections;
+pub mod connection_form;
 pub mod command_editor;
+pub mod compare;
 pub mod database_filter;
 pub mod databases;
 pub mod error;
+pub mod explain;
 pub mod help;
 pub mod help_info;
+pub mod import;
+pub mod progress;
 pub mod properties;
 pub mod record_table;
+pub mod review;
 pub mod sql_editor;
 pub mod line_editor;
 pub mod tab;
@@ -16,6 +22,7 @@ pub mod table_filter;
 pub mod table_status;
 pub mod table_value;
 pub mod utils;
+pub mod yank;
 pub mod recent;
 
 #[cfg(debug_assertions)]
@@ -23,13 +30,19 @@ pub mod debug;
 
 pub use help_info::{HelpInfo, HelpText};
 pub use completion::{CompletionComponent, PlainCompletionComponent, AdvanceCompletionComponent};
+pub use compare::CompareComponent;
 pub use connections::ConnectionsComponent;
+pub use connection_form::ConnectionFormComponent;
 pub use database_filter::DatabaseFilterComponent;
 pub use databases::DatabasesComponent;
 pub use error::ErrorComponent;
+pub use explain::ExplainComponent;
 pub use help::HelpComponent;
+pub use import::ImportComponent;
+pub use progress::ProgressComponent;
 pub use properties::PropertiesComponent;
 pub use record_table::RecordTableComponent;
+pub use review::ReviewComponent;
 pub use sql_editor::SqlEditorComponent;
 pub use tab::TabComponent;
 pub use table::TableComponent;
@@ -37,6 +50,7 @@ pub use table_filter::TableFilterComponent;
 pub use table_status::TableStatusComponent;
 pub use table_value::TableValueComponent;
 pub use line_editor::LineEditorComponent;
+pub use yank::YankComponent;
 pub use command_editor::CommandEditorComponent;
 pub use recent::{RecentComponent, Recent};
 
@@ -0,0 +1,139 @@
+use super::DrawableComponent;
+use crate::config::KeyConfig;
+use anyhow::Result;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+// how long the outcome of a finished task stays
+const NOTICE: Duration = Duration::from_secs(5);
+
+// spinner and elapsed time of the query running in the background
+pub struct ProgressComponent {
+    running: Option<(String, Instant, JoinHandle<()>)>,
+    load: Option<Arc<AtomicBool>>, // set once the running records load is replaced
+    notice: Option<(String, Instant)>,
+    key_config: KeyConfig,
+}
+
+impl ProgressComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self { running: None, load: None, notice: None, key_config }
+    }
+
+    // a long task tells how far it got
+    pub fn set_label(&mut self, label: String) {
+        if let Some(running) = self.running.as_mut() {
+            running.0 = label;
+        }
+    }
+
+    pub fn notify(&mut self, notice: String) {
+        self.notice = Some((notice, Instant::now()));
+    }
+
+    pub fn start(&mut self, label: &str, handle: JoinHandle<()>) {
+        self.running = Some((label.to_string(), Instant::now(), handle));
+        self.load = None;
+    }
+
+    // a load of records the next load takes over
+    pub fn start_load(&mut self, label: &str, handle: JoinHandle<()>, superseded: Arc<AtomicBool>) {
+        self.running = Some((label.to_string(), Instant::now(), handle));
+        self.load = Some(superseded);
+    }
+
+    pub fn is_load(&self) -> bool {
+        self.load.is_some()
+    }
+
+    // the running load, for the next one to cancel
+    pub fn take_load(&mut self) -> Option<(JoinHandle<()>, Arc<AtomicBool>)> {
+        let superseded = self.load.take()?;
+        self.running.take().map(|(_, _, handle)| (handle, superseded))
+    }
+
+    // the task keeps going when a streamed query waits for more rows to be asked for
+    pub fn stop(&mut self) -> Option<JoinHandle<()>> {
+        self.load = None;
+        self.running.take().map(|(_, _, handle)| handle)
+    }
+
+    // drop the task when the backend can't cancel the query itself
+    pub fn abort(&mut self) {
+        if let Some(handle) = self.stop() {
+            handle.abort();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn text(&self) -> Option<String> {
+        let notice = self.notice.as_ref().filter(|(_, at)| at.elapsed() < NOTICE).map(|(notice, _)| format!(" {} ", notice));
+        self.running.as_ref().map(|(label, started, _)| {
+            let elapsed = started.elapsed();
+            let frame = SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()];
+            format!(" {} {} {:.1}s, cancel [{}] ", frame, label, elapsed.as_secs_f32(), self.key_config.cancel_query)
+        }).or(notice)
+    }
+}
+
+impl DrawableComponent for ProgressComponent {
+    // drawn over the bottom border of the area
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, _focused: bool) -> Result<()> {
+        if let Some(text) = self.text() {
+            let width = (text.width() as u16).min(area.width.saturating_sub(2));
+            let area = Rect::new(
+                area.right().saturating_sub(width + 1),
+                area.bottom().saturating_sub(1),
+                width,
+                1.min(area.height),
+            );
+            f.render_widget(Clear, area);
+            f.render_widget(
+                Paragraph::new(text).style(Style::default().fg(Color::Yellow)).alignment(Alignment::Right),
+                area,
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_progress() {
+        let mut progress = ProgressComponent::new(KeyConfig::default());
+        assert_eq!(progress.text(), None);
+        progress.start("loading records", tokio::spawn(async {}));
+        assert!(progress.is_running());
+        let text = progress.text().unwrap();
+        assert!(text.contains(" loading records 0."), "{}", text);
+        assert!(text.ends_with("s, cancel [<Ctrl+x>] "), "{}", text);
+        progress.set_label("exporting 1.2k rows".to_string());
+        assert!(progress.text().unwrap().contains(" exporting 1.2k rows "));
+        assert!(!progress.is_load() && progress.take_load().is_none());
+        progress.abort();
+        assert!(!progress.is_running());
+        progress.start_load("loading records", tokio::spawn(async {}), Arc::new(AtomicBool::new(false)));
+        assert!(progress.is_load());
+        assert!(progress.take_load().is_some());
+        assert!(!progress.is_running() && !progress.is_load());
+        progress.notify("exported 1234 rows".to_string());
+        assert_eq!(progress.text().unwrap(), " exported 1234 rows ");
+    }
+}
@@ -1,5 +1,5 @@
-use super::{Component, EventState, StatefulDrawableComponent};
-use crate::components::help_info::HelpInfo;
+use super::{Component, DrawableComponent, EventState, StatefulDrawableComponent};
+use crate::components::help_info::{self, HelpInfo};
 use crate::components::{TableComponent, TableFilterComponent};
 use crate::config::{KeyConfig, Settings};
 use crate::event::Key;
@@ -71,6 +71,9 @@ impl StatefulDrawableComponent for RecordTableComponent {
 
         self.filter
             .draw(f, layout[0], focused && matches!(self.focus, Focus::Filter))?;
+        self.table.review.draw(f, Rect::default(), false)?;
+        self.table.import.draw(f, Rect::default(), false)?;
+        self.table.yank.draw(f, Rect::default(), false)?;
         Ok(())
     }
 }
@@ -78,7 +81,8 @@ impl StatefulDrawableComponent for RecordTableComponent {
 #[async_trait]
 impl Component for RecordTableComponent {
     fn helps(&self, out: &mut Vec<HelpInfo>) {
-        self.table.helps(out)
+        self.table.helps(out);
+        out.push(HelpInfo::new(help_info::count_rows(&self.key_config)));
     }
 
     fn event(&mut self, key: &[Key]) -> Result<EventState> {
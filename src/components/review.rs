@@ -0,0 +1,124 @@
+use super::{Component, DrawableComponent, EventState};
+use crate::components::help_info::HelpInfo;
+use crate::config::KeyConfig;
+use crate::event::Key;
+use anyhow::Result;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+// lists the sql of the staged changes before they are committed
+pub struct ReviewComponent {
+    sql: Vec<String>,
+    visible: bool,
+    scroll: u16,
+    key_config: KeyConfig,
+}
+
+impl ReviewComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            sql: vec![],
+            visible: false,
+            scroll: 0,
+            key_config,
+        }
+    }
+
+    pub fn set(&mut self, sql: Vec<String>) -> Result<()> {
+        self.sql = sql;
+        self.scroll = 0;
+        self.show()
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+}
+
+impl DrawableComponent for ReviewComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, _area: Rect, _focused: bool) -> Result<()> {
+        if self.visible {
+            const SIZE: (u16, u16) = (80, 24);
+            let area = Rect::new(
+                (f.size().width.saturating_sub(SIZE.0)) / 2,
+                (f.size().height.saturating_sub(SIZE.1)) / 2,
+                SIZE.0.min(f.size().width),
+                SIZE.1.min(f.size().height),
+            );
+
+            f.render_widget(Clear, area);
+            f.render_widget(
+                Block::default()
+                    .title(format!("Pending changes ({})", self.sql.len()))
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick),
+                area,
+            );
+
+            let chunks = Layout::default()
+                .vertical_margin(1)
+                .horizontal_margin(1)
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+                .split(area);
+
+            let text = if self.sql.is_empty() {
+                vec![Spans::from(Span::styled("no pending changes", Style::default().fg(Color::DarkGray)))]
+            } else {
+                self.sql.iter().map(|sql| Spans::from(format!("{};", sql))).collect()
+            };
+            f.render_widget(
+                Paragraph::new(text).wrap(Wrap { trim: false }).scroll((self.scroll, 0)),
+                chunks[0],
+            );
+
+            f.render_widget(
+                Paragraph::new(Spans::from(format!(
+                    "commit [{}] rollback [{}] close [{}]",
+                    self.key_config.enter, self.key_config.delete, self.key_config.exit_popup
+                )))
+                .alignment(Alignment::Right),
+                chunks[1],
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Component for ReviewComponent {
+    fn helps(&self, _out: &mut Vec<HelpInfo>) {}
+
+    fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        if !self.visible {
+            return Ok(EventState::NotConsumed);
+        }
+        let key = key[0];
+        if key == self.key_config.exit_popup {
+            self.hide();
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_down {
+            self.scroll = self.scroll.saturating_add(1);
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_up {
+            self.scroll = self.scroll.saturating_sub(1);
+            return Ok(EventState::Consumed);
+        }
+        Ok(EventState::NotConsumed)
+    }
+
+    fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn show(&mut self) -> Result<()> {
+        self.visible = true;
+
+        Ok(())
+    }
+}
@@ -1,33 +1,48 @@
 use std::sync::{Arc, RwLock};
 use super::{
-    compute_character_width, AdvanceCompletionComponent, Component, EventState, MovableComponent,
+    compute_character_width, AdvanceCompletionComponent, Component, DrawableComponent, EventState, MovableComponent,
     StatefulDrawableComponent, TableComponent,
+    table::Focus as TableFocus,
     utils::highlight_sql,
 };
 use crate::components::help_info::HelpInfo;
 use crate::config::{KeyConfig, Settings, DatabaseType};
-use crate::database::{ExecuteResult, Pool};
+use crate::database::{Pool, RowBatch};
+use database_tree::{Database, Table};
 use crate::sql::DbMetadata;
-use crate::event::{Key, Store};
+use crate::event::{Event, Key, Store};
+use crate::sql::token::tokenizer::Tokenizer;
 use crate::ui::stateful_paragraph::{ParagraphState, StatefulParagraph};
 use anyhow::Result;
 use async_trait::async_trait;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    style::{Color, Modifier, Style},
+    text::Spans,
+    widgets::{Block, Borders, Paragraph, Tabs, Wrap},
     Frame,
 };
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthStr;
 
-struct QueryResult {
-    updated_rows: u64,
+// how long the statement run under the cursor stays highlighted
+const FLASH: Duration = Duration::from_millis(500);
+
+// what one statement of the script gave back
+enum QueryResult {
+    Rows(Box<TableComponent>),
+    Write(u64),
 }
 
 impl QueryResult {
-    fn result_str(&self) -> String {
-        format!("Query OK, {} row affected", self.updated_rows)
+    fn title(&self, index: usize) -> String {
+        match self {
+            QueryResult::Rows(table) => format!("{}: {} rows", index + 1, table.rows.len()),
+            QueryResult::Write(updated_rows) => format!("{}: {} affected", index + 1, updated_rows),
+        }
     }
 }
 
@@ -40,12 +55,14 @@ pub struct SqlEditorComponent<'a> {
     input: Vec<char>,
     input_cursor_position_x: u16,
     input_idx: usize,
-    table: TableComponent,
-    query_result: Option<QueryResult>,
+    results: Vec<(usize, QueryResult)>, // keyed by the index of the statement
+    queries: Vec<String>, // the statements of the last run
+    selected_result: usize,
     completion: AdvanceCompletionComponent,
     key_config: &'a KeyConfig,
     settings: &'a Settings,
     paragraph_state: ParagraphState,
+    flash: Option<(Range<usize>, Instant)>, // byte range of the statement run under the cursor
     focus: Focus,
     database_type: DatabaseType,
 }
@@ -58,11 +75,13 @@ impl<'a> SqlEditorComponent<'a> {
             input: Vec::new(),
             input_idx: 0,
             input_cursor_position_x: 0,
-            table: TableComponent::new(key_config.clone(), settings.clone()),
+            results: vec![],
+            queries: vec![],
+            selected_result: 0,
             completion: completion,
             focus: Focus::Editor,
+            flash: None,
             paragraph_state: ParagraphState::default(),
-            query_result: None,
             key_config,
             settings,
             database_type,
@@ -73,6 +92,57 @@ impl<'a> SqlEditorComponent<'a> {
         self.database_type = database_type;
     }
 
+    pub fn set_rows(&mut self, batch: RowBatch) {
+        if batch.statement == 0 && !batch.append {
+            self.results.clear();
+        }
+        if let Some(updated_rows) = batch.updated_rows {
+            self.results.push((batch.statement, QueryResult::Write(updated_rows)));
+        } else if batch.append {
+            if let Some((_, QueryResult::Rows(table))) = self.results.iter_mut().rev().find(|(i, _)| *i == batch.statement) {
+                table.append(batch.rows);
+            }
+        } else {
+            let mut table = TableComponent::new(self.key_config.clone(), self.settings.clone());
+            table.update(
+                batch.rows,
+                batch.headers,
+                Database::new("-".to_string(), vec![]),
+                Table { name: "-".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None },
+                0,
+            );
+            table.query = self.queries.get(batch.statement).cloned();
+            self.results.push((batch.statement, QueryResult::Rows(Box::new(table))));
+            self.focus = Focus::Table;
+        }
+        self.selected_result = self.results.len().saturating_sub(1);
+        if !batch.more {
+            if let Some((_, QueryResult::Rows(table))) = self.results.last_mut() {
+                table.end();
+            }
+        }
+    }
+
+    // the last loaded row of the last result is selected and the query has rows left
+    pub fn wants_more_rows(&self) -> bool {
+        matches!(self.focus, Focus::Table)
+            && self.selected_result + 1 == self.results.len()
+            && matches!(self.results.last(), Some((_, QueryResult::Rows(table)))
+                if !table.eod && table.selected_row.selected().is_some_and(|i| i + 1 == table.rows.len()))
+    }
+
+    // highlighted for a moment as it is run
+    fn statement_under_cursor(&mut self) -> Result<Option<String>> {
+        let input = self.input.iter().collect::<String>();
+        let cursor = input.char_indices().nth(self.input_idx).map_or(input.len(), |(i, _)| i);
+        let statements = Tokenizer::try_from(self.database_type.clone())?.statements(&input);
+        Ok(statement_at(&statements, cursor).map(|range| {
+            let query = input[range.clone()].to_string();
+            self.flash = Some((range, Instant::now()));
+            query
+        }))
+    }
+
     pub fn update_db_metadata(&mut self, db_metadata: Arc<RwLock<DbMetadata>>) {
         self.completion.update_candidates(&vec![], Some(db_metadata));
     }
@@ -170,14 +240,34 @@ impl<'a> StatefulDrawableComponent for SqlEditorComponent<'a> {
             .split(area);
 
         let input = self.input.iter().collect::<String>();
-        let editor = StatefulParagraph::new(highlight_sql(input.trim(), &self.settings, &self.database_type))
+        let trimmed = input.len() - input.trim_start().len();
+        let selected = self.flash.as_ref()
+            .filter(|(_, at)| at.elapsed() < FLASH)
+            .map(|(range, _)| range.start.saturating_sub(trimmed)..range.end.saturating_sub(trimmed));
+        let editor = StatefulParagraph::new(highlight_sql(input.trim(), &self.settings, &self.database_type, selected))
             .wrap(Wrap { trim: true })
             .block(Block::default().borders(Borders::ALL));
 
         f.render_stateful_widget(editor, layout[0], &mut self.paragraph_state);
 
-        if let Some(result) = self.query_result.as_ref() {
-            let result = Paragraph::new(result.result_str())
+        let mut result_area = layout[1];
+        if self.results.len() > 1 {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)])
+                .split(layout[1]);
+            let titles = self.results.iter().map(|(i, result)| Spans::from(result.title(*i))).collect();
+            let tabs = Tabs::new(titles)
+                .select(self.selected_result)
+                .style(Style::default().fg(Color::DarkGray))
+                .highlight_style(Style::default().fg(Color::Reset).add_modifier(Modifier::UNDERLINED));
+            f.render_widget(tabs, chunks[0]);
+            result_area = chunks[1];
+        }
+
+        match self.results.get_mut(self.selected_result) {
+            Some((_, QueryResult::Write(updated_rows))) => {
+                let result = Paragraph::new(format!("Query OK, {} row affected", updated_rows))
                 .block(Block::default().borders(Borders::ALL).style(
                     if focused && matches!(self.focus, Focus::Editor) {
                         Style::default()
@@ -186,10 +276,13 @@ impl<'a> StatefulDrawableComponent for SqlEditorComponent<'a> {
                     },
                 ))
                 .wrap(Wrap { trim: true });
-            f.render_widget(result, layout[1]);
-        } else {
-            self.table
-                .draw(f, layout[1], focused && matches!(self.focus, Focus::Table))?;
+                f.render_widget(result, result_area);
+            }
+            Some((_, QueryResult::Rows(table))) => {
+                table.draw(f, result_area, focused && matches!(self.focus, Focus::Table))?;
+                table.yank.draw(f, Rect::default(), false)?;
+            }
+            None => f.render_widget(Block::default().borders(Borders::ALL).style(Style::default().fg(Color::DarkGray)), result_area),
         }
 
         if focused && matches!(self.focus, Focus::Editor) {
@@ -219,6 +312,15 @@ impl<'a> StatefulDrawableComponent for SqlEditorComponent<'a> {
     }
 }
 
+// the statement the cursor is in or right after, the first one when it is before all of them
+fn statement_at(statements: &[Range<usize>], cursor: usize) -> Option<Range<usize>> {
+    statements.iter()
+        .rev()
+        .find(|range| range.start <= cursor)
+        .or_else(|| statements.first())
+        .cloned()
+}
+
 #[async_trait]
 impl<'a> Component for SqlEditorComponent<'a> {
     fn helps(&self, _out: &mut Vec<HelpInfo>) {}
@@ -289,31 +391,61 @@ impl<'a> Component for SqlEditorComponent<'a> {
                 }
                 return Ok(EventState::Consumed);
             }
-            key if matches!(self.focus, Focus::Table) => return self.table.event(key),
+            [key] if matches!(self.focus, Focus::Table) && *key == self.key_config.next_result => {
+                if self.selected_result + 1 < self.results.len() {
+                    self.selected_result += 1;
+                }
+                return Ok(EventState::Consumed);
+            }
+            [key] if matches!(self.focus, Focus::Table) && *key == self.key_config.previous_result => {
+                self.selected_result = self.selected_result.saturating_sub(1);
+                return Ok(EventState::Consumed);
+            }
+            key if matches!(self.focus, Focus::Table) => {
+                if let Some((_, QueryResult::Rows(table))) = self.results.get_mut(self.selected_result) {
+                    return table.event(key);
+                }
+            }
             _ => (),
         }
         return Ok(EventState::NotConsumed);
     }
 
-    async fn async_event(&mut self, key: Key, pool: &Box<dyn Pool>, _store: &Store) -> Result<EventState> {
+    async fn async_event(&mut self, key: Key, pool: &Box<dyn Pool>, store: &Store) -> Result<EventState> {
+        // only the commands and the yank of a result, its rows are not in a table that can be edited
+        if let (Focus::Table, Some((_, QueryResult::Rows(table)))) = (&self.focus, self.results.get_mut(self.selected_result)) {
+            if table.focus == TableFocus::Command || table.yank.is_visible() {
+                return table.async_event(key, pool, store).await;
+            }
+        }
+
         if key == self.key_config.enter && matches!(self.focus, Focus::Editor) {
-            let query = self.input.iter().collect::<String>();
-            let result = pool.execute(&query).await?;
-            match result {
-                ExecuteResult::Read {
-                    headers,
-                    rows,
-                    database,
-                    table,
-                } => {
-                    // let header_names = headers.into_iter().map(|h| h.name).collect();
-                    self.table.update(rows, headers, database, table, 0);
-                    self.focus = Focus::Table;
-                    self.query_result = None;
-                }
-                ExecuteResult::Write { updated_rows } => {
-                    self.query_result = Some(QueryResult { updated_rows })
-                }
+            let input = self.input.iter().collect::<String>();
+            let queries = Tokenizer::try_from(self.database_type.clone())?
+                .statements(&input)
+                .into_iter()
+                .map(|range| input[range].to_string())
+                .collect::<Vec<_>>();
+            if queries.is_empty() {
+                return Ok(EventState::Consumed);
+            }
+            // the app runs them in the background and hands each result to `set_rows`
+            self.queries = queries.clone();
+            store.dispatch(Event::ExecuteSql(queries)).await?;
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.execute_statement && matches!(self.focus, Focus::Editor) {
+            if let Some(query) = self.statement_under_cursor()? {
+                self.queries = vec![query.clone()];
+                store.dispatch(Event::ExecuteSql(vec![query])).await?;
+            }
+            return Ok(EventState::Consumed);
+        }
+
+        if key == self.key_config.explain && matches!(self.focus, Focus::Editor) {
+            if let Some(query) = self.statement_under_cursor()? {
+                store.dispatch(Event::ExplainSql(query, false)).await?;
             }
             return Ok(EventState::Consumed);
         }
@@ -321,3 +453,21 @@ impl<'a> Component for SqlEditorComponent<'a> {
         Ok(EventState::NotConsumed)
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::statement_at;
+
+    #[test]
+    fn test_statement_at() {
+        // "  SELECT 1; SELECT 2;  SELECT 3"
+        let statements = [2..10, 12..20, 23..31];
+        assert_eq!(statement_at(&statements, 0), Some(2..10));
+        assert_eq!(statement_at(&statements, 5), Some(2..10));
+        assert_eq!(statement_at(&statements, 10), Some(2..10));
+        assert_eq!(statement_at(&statements, 11), Some(2..10));
+        assert_eq!(statement_at(&statements, 12), Some(12..20));
+        assert_eq!(statement_at(&statements, 22), Some(12..20));
+        assert_eq!(statement_at(&statements, 31), Some(23..31));
+        assert_eq!(statement_at(&[], 0), None);
+    }
+}
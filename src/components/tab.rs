@@ -18,6 +18,8 @@ pub enum Tab {
     Records,
     Properties,
     Sql,
+    Explain,
+    Compare,
 }
 
 impl std::fmt::Display for Tab {
@@ -48,6 +50,8 @@ impl<'a> TabComponent<'a> {
             help_info::tab_records(&self.key_config).name,
             help_info::tab_properties(&self.key_config).name,
             help_info::tab_sql_editor(&self.key_config).name,
+            help_info::tab_explain(self.key_config).name,
+            help_info::tab_compare(self.key_config).name,
         ]
     }
 }
@@ -79,6 +83,12 @@ impl<'a> Component for TabComponent<'a> {
         } else if key == [self.key_config.tab_sql_editor] {
             self.selected_tab = Tab::Sql;
             return Ok(EventState::Consumed);
+        } else if key == [self.key_config.tab_explain] {
+            self.selected_tab = Tab::Explain;
+            return Ok(EventState::Consumed);
+        } else if key == [self.key_config.tab_compare] {
+            self.selected_tab = Tab::Compare;
+            return Ok(EventState::Consumed);
         } else if key == [self.key_config.tab_properties] {
             self.selected_tab = Tab::Properties;
             return Ok(EventState::Consumed);
@@ -1,14 +1,16 @@
 use super::{
-    utils::scroll_vertical::VerticalScroll, Component, DrawableComponent, EventState,
+    import::PREVIEW_ROWS, utils::scroll_vertical::VerticalScroll, Component, DrawableComponent, EventState,
     StatefulDrawableComponent, TableStatusComponent, LineEditorComponent, CommandEditorComponent,
+    ImportComponent, ReviewComponent, YankComponent,
 };
 use crate::components::help_info::{self, HelpInfo};
 use crate::config::{KeyConfig, Settings};
 use crate::event::{Key, Store, Event};
-use crate::database::{Pool, Header, Value};
+use crate::database::{export_args, is_read_only, query_table, read_file, yank, yank_table, Pool, ColType, ExportSource, Header, Keyset, Page, Param, RowKeys, Statement, Value, YankFormat, SQLITE_ROWID};
 use crate::clipboard::copy_to_clipboard;
 use anyhow::Result;
 use database_tree::{Database, Table as DTable};
+use std::collections::HashMap;
 use std::convert::{From, Into};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
@@ -30,6 +32,13 @@ pub enum Focus {
     Command,
 }
 
+// staged change, run against the database on commit
+enum Change {
+    Update { row: usize, column: usize, statement: Statement },
+    Delete { rows: Vec<usize>, statement: Statement },
+    Insert { row: usize },
+}
+
 #[derive(Copy, Clone)]
 pub enum Movement {
     Forward(char),
@@ -54,7 +63,16 @@ pub struct TableComponent {
     cell_editor: LineEditorComponent,
     command_editor: CommandEditorComponent,
     orderby_status: Option<String>,
-    movement: Option<Movement>
+    movement: Option<Movement>,
+    pub review: ReviewComponent,
+    pub import: ImportComponent,
+    pub yank: YankComponent,
+    pub page: Page, // how the rows were loaded, to continue with the next page
+    pub total_rows: Option<u64>, // exact count of the filtered table, on demand
+    pub query: Option<String>, // the statement of a sql editor result
+    staging: bool,
+    changes: Vec<Change>,
+    rowids: HashMap<usize, Value>, // sqlite rowids of staged rows, their values may differ from the database
 }
 
 impl TableComponent {
@@ -72,19 +90,32 @@ impl TableComponent {
             column_page_start: AtomicUsize::new(0),
             scroll: VerticalScroll::new(false, false),
             eod: false,
-            key_config,
             area_width: 0,
-            settings,
             focus: Focus::Status,
             orderby_status: None,
             movement: None,
+            review: ReviewComponent::new(key_config.clone()),
+            import: ImportComponent::new(key_config.clone(), settings.clone()),
+            yank: YankComponent::new(key_config.clone()),
+            page: Page::default(),
+            total_rows: None,
+            query: None,
+            staging: settings.stage_edits,
+            changes: vec![],
+            rowids: HashMap::new(),
+            key_config,
+            settings,
         }
     }
 
     fn title(&self) -> String {
-        self.table.as_ref().map_or(" - ".to_string(), |table| {
+        let title = self.table.as_ref().map_or(" - ".to_string(), |table| {
             format!("{}.{}", table.0.name, table.1.name)
-        })
+        });
+        if self.staging {
+            return format!("{} [staged: {}]", title, self.changes.len())
+        }
+        title
     }
 
     pub fn update(
@@ -108,6 +139,14 @@ impl TableComponent {
         self.scroll = VerticalScroll::new(false, false);
         self.eod = false;
         self.table = Some((database, table));
+        self.total_rows = None;
+        // staged changes point at row indexes, so they don't survive a reload
+        self.changes = vec![];
+        self.rowids = HashMap::new();
+    }
+
+    pub fn append(&mut self, rows: Vec<Vec<Value>>) {
+        self.rows.extend(rows.into_iter().map(|row| row.into_iter().map(|cell| Arc::new(RwLock::new(cell))).collect::<Vec<_>>()));
     }
 
     pub fn reset(&mut self) {
@@ -125,6 +164,10 @@ impl TableComponent {
         self.focus = Focus::Status;
         self.orderby_status = None;
         self.movement = None;
+        self.changes = vec![];
+        self.rowids = HashMap::new();
+        self.page = Page::default();
+        self.total_rows = None;
     }
 
     fn reset_selection(&mut self) {
@@ -345,18 +388,23 @@ impl TableComponent {
         Some(())
     }
 
-    pub fn selected_rows(&self) -> Option<Vec<Vec<Arc<RwLock<Value>>>>> {
-        if let Some((_x, y)) = self.selection_area_corner {
-            let selected_row_index = self.selected_row.selected()?;
-            return Some(
-                self.rows[y.min(selected_row_index)..y.max(selected_row_index) + 1]
-                    .iter()
-                    .map(|row| row.clone())
-                    .collect()
-            );
+    fn selected_column_indexes(&self) -> Vec<usize> {
+        match self.selection_area_corner {
+            Some((x, _)) => (x.min(self.selected_column)..x.max(self.selected_column) + 1).collect(),
+            None => vec![self.selected_column],
         }
-        let rows = self.rows.get(self.selected_row.selected()?)?;
-        Some(vec![rows.to_vec()])
+    }
+
+    // the selected rows as `format`, statements go to the table of the rows or the one a query read
+    async fn yank_text(&self, format: YankFormat, pool: &Box<dyn Pool>) -> anyhow::Result<String> {
+        let rows = self.row_values(&self.selected_row_indexes(), &(0..self.headers.len()).collect::<Vec<_>>());
+        let table = match (&self.table, &self.query) {
+            _ if !format.needs_table() => None,
+            (_, Some(query)) => query_table(pool, query).await?,
+            (Some((database, table)), None) => Some(yank_table(pool, database, table).await?),
+            (None, None) => None,
+        };
+        yank(format, &pool.database_type(), table.as_ref(), &self.headers, &rows, &self.selected_column_indexes())
     }
 
     fn selected_column_index(&self) -> usize {
@@ -538,38 +586,212 @@ impl TableComponent {
         )
     }
 
-    // column order: primary_key,id,first column
-    async fn primary_key_value(&self, pool: &Box<dyn Pool>, database: &Database, table: &DTable) -> anyhow::Result<(String, Vec<Value>)> {
+    // continue after the last loaded row, staged inserts are not in the database yet
+    pub fn next_page(&self) -> Page {
+        let loaded = (0..self.rows.len()).filter(|i| !self.is_inserted_row(*i)).collect::<Vec<_>>();
+        let keyset = self.page.keyset.clone().map(|keyset| {
+            let after = loaded.last().and_then(|row| {
+                keyset.columns().into_iter().map(|column| {
+                    let index = self.headers.iter().position(|h| h.name == *column)?;
+                    Some(Param::new(&self.headers[index], &self.rows[*row][index].read().unwrap()))
+                }).collect::<Option<Vec<_>>>()
+            });
+            Keyset { after, ..keyset }
+        });
+        Page { offset: loaded.len() as u64, orderby: self.page.orderby.clone(), keyset }
+    }
+
+    fn selected_row_indexes(&self) -> Vec<usize> {
+        match (self.selected_row.selected(), self.selection_area_corner) {
+            (Some(index), Some((_, y))) => (y.min(index)..y.max(index) + 1).collect(),
+            (Some(index), None) => vec![index],
+            _ => vec![],
+        }
+    }
+
+    fn row_values(&self, rows: &[usize], columns: &[usize]) -> Vec<Vec<Value>> {
+        rows.iter()
+            .filter_map(|i| self.rows.get(*i))
+            .map(|row| columns.iter().filter_map(|i| row.get(*i)).map(|v| v.read().unwrap().clone()).collect())
+            .collect()
+    }
+
+    // key columns of the given rows, or every column when the table has no key
+    async fn row_keys(&mut self, pool: &Box<dyn Pool>, database: &Database, table: &DTable, rows: &[usize]) -> anyhow::Result<RowKeys> {
         let database_type = pool.database_type();
-        let columns = database_type.primary_key_columns(pool, &database, &table).await?;
-        if  let Some(primary_key) = columns.iter().next() {
-            if let Some(index) = self.headers.iter().position(|h| h.name == *primary_key) {
-                if let Some(value) = self.selected_rows()
-                        .map(|rows| rows.iter().filter_map(|row| row.get(index).map(|s| s.clone()))
-                        .map(|v| v.read().unwrap().clone())
-                        .collect::<Vec<_>>()) {
-                    return Ok((primary_key.to_string(), value))
-                }
+        let columns = database_type.primary_key_columns(pool, database, table).await?;
+        let indexes = columns.iter()
+            .map(|c| self.headers.iter().position(|h| h.name == *c))
+            .collect::<Option<Vec<_>>>()
+            .filter(|indexes| !indexes.is_empty());
+        let exact = indexes.is_some();
+        let indexes = indexes.unwrap_or_else(|| (0..self.headers.len()).collect());
+        let headers = indexes.iter().map(|i| self.headers[*i].clone()).collect::<Vec<_>>();
+        if exact || columns != [SQLITE_ROWID] {
+            return Ok(RowKeys { headers, rows: self.row_values(rows, &indexes), exact })
+        }
+        // rowid is not part of the records, so resolve it from the full row once and keep it,
+        // later edits change the row in the grid before they reach the database
+        let missing = rows.iter().copied().filter(|i| !self.rowids.contains_key(i)).collect::<Vec<_>>();
+        let keys = RowKeys { headers, rows: self.row_values(&missing, &indexes), exact };
+        let taken = self.rowids.values().cloned().collect::<Vec<_>>();
+        let rowids = database_type.sqlite_rowids(pool, database, table, &keys, &taken).await?;
+        self.rowids.extend(missing.into_iter().zip(rowids));
+        Ok(RowKeys {
+            headers: vec![Header::new(SQLITE_ROWID.to_string(), ColType::Int)],
+            rows: rows.iter().filter_map(|i| self.rowids.get(i)).map(|v| vec![v.clone()]).collect(),
+            exact: true,
+        })
+    }
+
+    fn is_inserted_row(&self, row: usize) -> bool {
+        self.changes.iter().any(|c| matches!(c, Change::Insert { row: r } if *r == row))
+    }
+
+    fn is_deleted_row(&self, row: usize) -> bool {
+        self.changes.iter().any(|c| matches!(c, Change::Delete { rows, .. } if rows.contains(&row)))
+    }
+
+    fn is_updated_cell(&self, row: usize, column: usize) -> bool {
+        self.changes.iter().any(|c| matches!(c, Change::Update { row: r, column: col, .. } if *r == row && *col == column))
+    }
+
+    fn ensure_no_changes(&self) -> anyhow::Result<()> {
+        if !self.changes.is_empty() {
+            anyhow::bail!("commit or rollback the {} staged changes first", self.changes.len())
+        }
+        Ok(())
+    }
+
+    // drop a staged insert, the rows below it move up
+    fn remove_inserted_row(&mut self, row: usize) {
+        self.rows.remove(row);
+        self.changes.retain(|c| !matches!(c, Change::Insert { row: r } if *r == row));
+        let shift = |r: &mut usize| if *r > row { *r -= 1 };
+        for change in self.changes.iter_mut() {
+            match change {
+                Change::Update { row: r, .. } | Change::Insert { row: r } => shift(r),
+                Change::Delete { rows, .. } => rows.iter_mut().for_each(shift),
             }
-        } else {
-            if let Some((index, col)) = self.headers.iter().enumerate().find(|(_, h)| h.name.to_lowercase() == "id" ).or(self.headers.iter().enumerate().next()) {
-                if let Some(value) = self.selected_rows()
-                        .map(|rows| rows.iter().filter_map(|row| row.get(index).map(|s| s.clone()))
-                        .map(|v| v.read().unwrap().clone())
-                        .collect::<Vec<_>>()) {
-                    return Ok((col.name.clone(), value))
-                }
-                // if let Some(id) = self.selected_rows().map(|rows| rows.iter().map(|row| row.get(index).map(|s| s.clone()))).flatten().flatten() {
-                //     return Ok((col.name.clone(), id.read().unwrap().clone()))
-                // }
+        }
+        self.rowids = self.rowids.drain().map(|(mut r, v)| { shift(&mut r); (r, v) }).collect();
+    }
+
+    async fn stage_delete(&mut self, pool: &Box<dyn Pool>, database: &Database, table: &DTable, rows: Vec<usize>) -> anyhow::Result<()> {
+        let (inserted, rows): (Vec<_>, Vec<_>) = rows.into_iter()
+            .filter(|r| !self.is_deleted_row(*r))
+            .partition(|r| self.is_inserted_row(*r));
+        if !rows.is_empty() {
+            let keys = self.row_keys(pool, database, table, &rows).await?;
+            let database_type = pool.database_type();
+            let column_types = database_type.column_types(pool, database, table).await?;
+            let statement = database_type.delete_rows_by_column(database, table, &keys, &column_types);
+            self.changes.push(Change::Delete { rows, statement });
+        }
+        if !inserted.is_empty() {
+            // from the bottom up so the remaining indexes stay valid
+            for row in inserted.into_iter().rev() {
+                self.remove_inserted_row(row);
+            }
+            self.reset_selection();
+            if self.selected_row.selected().is_some_and(|i| i >= self.rows.len()) {
+                self.selected_row.select(self.rows.len().checked_sub(1));
             }
         }
-        anyhow::bail!("primary key not found")
+        Ok(())
+    }
+
+    async fn pending_statements(&self, pool: &Box<dyn Pool>) -> anyhow::Result<Vec<Statement>> {
+        let database_type = pool.database_type();
+        let (database, table) = match &self.table {
+            Some(table) => table,
+            None => return Ok(vec![]),
+        };
+        // only inserts need them, updates and deletes are bound when staged
+        let column_types = if self.changes.iter().any(|change| matches!(change, Change::Insert { .. })) {
+            database_type.column_types(pool, database, table).await?
+        } else {
+            HashMap::new()
+        };
+        Ok(self.changes.iter().flat_map(|change| match change {
+            Change::Update { statement, .. } | Change::Delete { statement, .. } => vec![statement.clone()],
+            Change::Insert { row } => {
+                // leave NULL cells out so column defaults apply
+                let (headers, values): (Vec<_>, Vec<_>) = self.headers.iter().cloned()
+                    .zip(self.rows[*row].iter().cloned())
+                    .filter(|(_, v)| !v.read().unwrap().is_null())
+                    .unzip();
+                database_type.insert_rows(database, table, &headers, &[values], &column_types)
+            },
+        }).collect())
+    }
+
+    async fn commit(&mut self, pool: &Box<dyn Pool>, store: &Store) -> anyhow::Result<()> {
+        let statements = self.pending_statements(pool).await?;
+        pool.execute_transaction(&statements).await?;
+        self.changes = vec![];
+        self.rowids = HashMap::new();
+        store.dispatch(Event::RedrawTable(true)).await?;
+        Ok(())
+    }
+
+    async fn rollback(&mut self, store: &Store) -> anyhow::Result<()> {
+        self.changes = vec![];
+        self.rowids = HashMap::new();
+        store.dispatch(Event::RedrawTable(true)).await?;
+        Ok(())
+    }
+
+    // every row of the table or query, not just the loaded ones
+    async fn export(&self, args: &str, store: &Store) -> anyhow::Result<()> {
+        let (format, path) = export_args(args)?;
+        let source = match (&self.query, &self.table) {
+            (Some(query), _) if is_read_only(query) => ExportSource::Query(query.clone()),
+            (Some(_), _) => anyhow::bail!("only a query that reads can be exported, it runs again"),
+            (None, Some((database, table))) => ExportSource::Table {
+                database: database.clone(),
+                table: table.clone(),
+                orderby: self.page.orderby.clone(),
+            },
+            (None, None) => anyhow::bail!("nothing to export"),
+        };
+        store.dispatch(Event::Export(source, format, path)).await
+    }
+
+    // the popup maps the fields of the file to the columns, enter loads it
+    async fn open_import(&mut self, args: &str, pool: &Box<dyn Pool>) -> anyhow::Result<()> {
+        let (database, table) = match (&self.query, &self.table) {
+            (None, Some(table)) => table.clone(),
+            _ => anyhow::bail!("import into the table of the records tab"),
+        };
+        let (format, path) = export_args(args)?;
+        let file = read_file(format, &path, PREVIEW_ROWS)?;
+        let columns = pool.get_columns(&database, &table).await?.iter()
+            .flat_map(|c| {
+                let mut columns = c.columns().into_iter();
+                columns.next().map(|name| (name, columns.next().unwrap_or_default()))
+            })
+            .collect();
+        self.import.open(database, table, format, path, file, columns)
     }
 
-    async fn dispatch_command(&self, command: &str, store: &Store) -> anyhow::Result<()>  {
-        if command == "tree" {
-            store.dispatch(Event::ToggleTree).await?;
+    async fn dispatch_command(&mut self, command: &str, pool: &Box<dyn Pool>, store: &Store) -> anyhow::Result<()>  {
+        let (command, args) = command.split_once(' ').unwrap_or((command, ""));
+        match command {
+            "tree" => store.dispatch(Event::ToggleTree).await?,
+            "stage" => {
+                self.ensure_no_changes()?;
+                self.staging = !self.staging;
+            },
+            "review" => {
+                let database_type = pool.database_type();
+                self.review.set(self.pending_statements(pool).await?.iter().map(|s| database_type.render(s)).collect())?;
+            },
+            "commit" => self.commit(pool, store).await?,
+            "rollback" => self.rollback(store).await?,
+            "export" => self.export(args, store).await?,
+            "import" => self.open_import(args, pool).await?,
+            _ => {},
         }
         Ok(())
     }
@@ -621,6 +843,7 @@ impl StatefulDrawableComponent for TableComponent {
         self.area_width = block.inner(chunks[1]).width;
         let (selected_column_index, headers, rows, constraints) =
             self.calculate_cell_widths(self.area_width);
+        let column_page_start = self.column_page_start.load(Ordering::Relaxed);
         let header_cells = headers.iter().enumerate().map(|(column_index, h)| {
             Cell::from(h.to_string()).style(if selected_column_index == column_index {
                 Style::default().fg(self.settings.color).add_modifier(Modifier::BOLD)
@@ -638,12 +861,18 @@ impl StatefulDrawableComponent for TableComponent {
                 + 1;
             let cells = item.iter().enumerate().map(|(column_index, c)| {
                 let c = c.read().unwrap();
-                let value = if c.is_null { format!("<{}>", "NULL") } else { c.substr(256) };
+                let value = if c.is_null() { format!("<{}>", "NULL") } else { c.substr(256) };
                 let style = if self.is_selected_cell(row_index, column_index, selected_column_index) {
                     Style::default().bg(self.settings.color)
                 } else if self.is_number_column(row_index, column_index) {
                     Style::default().add_modifier(Modifier::BOLD)
-                } else if c.is_null {
+                } else if self.is_deleted_row(row_index) {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT)
+                } else if self.is_inserted_row(row_index) {
+                    Style::default().fg(Color::Green)
+                } else if column_index > 0 && self.is_updated_cell(row_index, column_page_start + column_index - 1) {
+                    Style::default().fg(Color::Yellow)
+                } else if c.is_null() {
                     Style::default().fg(Color::DarkGray)
                 } else {
                     Style::default()
@@ -685,6 +914,7 @@ impl StatefulDrawableComponent for TableComponent {
                     } else {
                         Some(self.rows.len())
                     },
+                    self.total_rows,
                     if self.headers.is_empty() {
                         None
                     } else {
@@ -712,9 +942,24 @@ impl Component for TableComponent {
         out.push(HelpInfo::new(help_info::extend_selection_by_one_cell(
             &self.key_config,
         )));
+        out.push(HelpInfo::new(help_info::insert_row(&self.key_config)));
+        out.push(HelpInfo::new(help_info::staged_edits(&self.key_config)));
+        out.push(HelpInfo::new(help_info::export(&self.key_config)));
+        out.push(HelpInfo::new(help_info::import(&self.key_config)));
+        out.push(HelpInfo::new(help_info::yank(&self.key_config)));
     }
 
     fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        // commit and rollback need the pool, see async_event
+        if self.review.is_visible() {
+            return self.review.event(key);
+        }
+        if self.import.is_visible() {
+            return self.import.event(key);
+        }
+        if self.yank.is_visible() {
+            return self.yank.event(key);
+        }
         if self.focus == Focus::Editor {
             let state = self.cell_editor.event(key)?;
             if state == EventState::Consumed {
@@ -816,12 +1061,25 @@ impl Component for TableComponent {
         //     return Ok(EventState::Consumed);
         } else if key == self.key_config.edit_cell && self.focus == Focus::Status {
             self.focus = Focus::Editor;
-            let s = self.selected_cell().map(|c| if c.is_null { NULL.to_string() } else { c.to_string() });
+            let s = self.selected_cell().map(|c| if c.is_null() { NULL.to_string() } else { c.to_string() });
             self.cell_editor.update(s.unwrap_or("".to_string()));
             return Ok(EventState::Consumed);
+        } else if key == self.key_config.insert_row && self.focus == Focus::Status && self.table.is_some() {
+            if !self.staging {
+                anyhow::bail!("inserting rows needs staged edits, run {}stage first", self.key_config.edit_command)
+            }
+            self.rows.push(self.headers.iter().map(|_| Arc::new(RwLock::new(Value::default()))).collect());
+            let row = self.rows.len() - 1;
+            self.changes.push(Change::Insert { row });
+            self.reset_selection();
+            self.selected_row.select(Some(row));
+            return Ok(EventState::Consumed);
         } else if key == self.key_config.edit_command && self.focus == Focus::Status {
             self.focus = Focus::Command;
             return Ok(EventState::Consumed);
+        } else if key == self.key_config.advanced_copy && self.focus == Focus::Status && self.selected_row.selected().is_some() {
+            self.yank.show()?;
+            return Ok(EventState::Consumed);
         } else if key == self.key_config.exit_popup {
             self.focus = Focus::Status;
             return Ok(EventState::Consumed);
@@ -836,29 +1094,53 @@ impl Component for TableComponent {
         pool: &Box<dyn Pool>,
         store: &Store
     ) -> Result<EventState> {
+        if self.review.is_visible() {
+            if key == self.key_config.enter {
+                self.review.hide();
+                self.commit(pool, store).await?;
+            } else if key == self.key_config.delete {
+                self.review.hide();
+                self.rollback(store).await?;
+            }
+            return Ok(EventState::Consumed)
+        }
+        if self.import.is_visible() {
+            if key == self.key_config.enter {
+                self.import.hide();
+                if let Some(request) = self.import.request() {
+                    store.dispatch(Event::Import(request)).await?;
+                }
+            }
+            return Ok(EventState::Consumed)
+        }
+        if self.yank.is_visible() {
+            if key == self.key_config.enter {
+                self.yank.hide();
+                let text = self.yank_text(self.yank.format(), pool).await?;
+                copy_to_clipboard(&text)?;
+            }
+            return Ok(EventState::Consumed)
+        }
         // delete by primary_key
         if key == self.key_config.delete {
-            if let Some((database, table)) = &self.table {
-                let (primary_key, values) = self.primary_key_value(pool, database, table).await?;
-                let col_values = values.iter().map(|v| v.data.as_str()).collect::<Vec<_>>();
-                // let sql = pool.database_type().delete_row_by_column(&database, &table, &primary_key, &values[0].data);
-                let sql = pool.database_type().delete_rows_by_column(&database, &table, &primary_key, &col_values);
-                pool.execute(&sql).await?;
+            if let Some((database, table)) = self.table.clone() {
+                let rows = self.selected_row_indexes();
+                if self.staging {
+                    self.stage_delete(pool, &database, &table, rows).await?;
+                    return Ok(EventState::Consumed)
+                }
+                let keys = self.row_keys(pool, &database, &table, &rows).await?;
+                let database_type = pool.database_type();
+                let column_types = database_type.column_types(pool, &database, &table).await?;
+                let statement = database_type.delete_rows_by_column(&database, &table, &keys, &column_types);
+                // rolled back unless it deletes exactly the selected rows
+                pool.execute_transaction(&[statement]).await?;
                 store.dispatch(Event::RedrawTable(true)).await?;
                 return Ok(EventState::Consumed)
             }
         }
-        if key == self.key_config.advanced_copy {
-            if let Some(rows) = self.selected_rows() {
-                if let Some((database, table)) = &self.table {
-                    let sql = pool.database_type().insert_rows(database, table, &self.headers, &rows);
-                    copy_to_clipboard(sql.trim())?;
-                }
-            }
-            return Ok(EventState::Consumed);
-        }
-
         if key == self.key_config.orderby_desc {
+            self.ensure_no_changes()?;
             let header = &self.headers[self.selected_column];
             let mut orderby = format!("{} desc", header.name);
             if self.orderby_status.as_deref() == Some(&orderby) {
@@ -870,6 +1152,7 @@ impl Component for TableComponent {
         }
 
         if key == self.key_config.orderby_asc {
+            self.ensure_no_changes()?;
             let header = &self.headers[self.selected_column];
             let mut orderby = format!("{} asc", header.name);
             if self.orderby_status.as_deref() == Some(&orderby) {
@@ -883,14 +1166,29 @@ impl Component for TableComponent {
         // update cell value
         if key == self.key_config.enter && self.focus == Focus::Editor {
             self.focus = Focus::Status;
-            if let Some((database, table)) = &self.table {
-                let (pkey, pval) = self.primary_key_value(pool, database, table).await?;
-                let header = &self.headers[self.selected_column];
+            if let (Some((database, table)), Some(row)) = (self.table.clone(), self.selected_row.selected()) {
                 let v = self.cell_editor.value();
+                if self.staging && self.is_deleted_row(row) {
+                    anyhow::bail!("row {} is staged for deletion", row + 1)
+                }
+                // a staged insert picks up its values on commit
+                if self.staging && self.is_inserted_row(row) {
+                    self.set_selected_cell(v);
+                    return Ok(EventState::Consumed)
+                }
+                let keys = self.row_keys(pool, &database, &table, &[row]).await?;
+                let header = &self.headers[self.selected_column];
                 let value = if v == NULL { Value::default() } else { Value::new(v.clone()) };
-                let sql = pool.database_type().update_row_by_column(database, table, &pkey, &pval[0].data, &header, &value);
+                let database_type = pool.database_type();
+                let column_types = database_type.column_types(pool, &database, &table).await?;
+                let statement = database_type.update_row_by_column(&database, &table, &keys, header, &value, &column_types);
+                if self.staging {
+                    self.changes.push(Change::Update { row, column: self.selected_column, statement });
+                } else {
+                    // rolled back unless it updates exactly the edited row
+                    pool.execute_transaction(&[statement]).await?;
+                }
                 self.set_selected_cell(v);
-                pool.execute(&sql).await?;
                 return Ok(EventState::Consumed)
             }
         }
@@ -899,7 +1197,7 @@ impl Component for TableComponent {
             self.focus = Focus::Status;
             let command = self.command_editor.value();
             self.command_editor.reset();
-            self.dispatch_command(command.trim(), store).await?;
+            self.dispatch_command(command.trim(), pool, store).await?;
             return Ok(EventState::Consumed)
         }
         Ok(EventState::NotConsumed)
@@ -908,7 +1206,7 @@ impl Component for TableComponent {
 
 #[cfg(test)]
 mod test {
-    use super::{KeyConfig, Settings, TableComponent};
+    use super::{Change, KeyConfig, Page, Param, Settings, Statement, TableComponent};
     use tui::layout::Constraint;
     use std::sync::{Arc, RwLock};
 
@@ -1223,4 +1521,42 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn test_remove_inserted_row() {
+        let mut component = TableComponent::new(KeyConfig::default(), Settings::default());
+        component.headers = vec!["1", "2"].into_iter().map(|h| h.into()).collect();
+        component.rows = vec![
+            ["a", "b"].iter().map(|h| Arc::new(RwLock::new(h.into()))).collect(),
+            ["c", "d"].iter().map(|h| Arc::new(RwLock::new(h.into()))).collect(),
+            ["e", "f"].iter().map(|h| Arc::new(RwLock::new(h.into()))).collect(),
+        ];
+        component.changes = vec![
+            Change::Delete { rows: vec![0, 2], statement: Statement::default() },
+            Change::Insert { row: 1 },
+            Change::Update { row: 2, column: 1, statement: Statement::default() },
+        ];
+        component.remove_inserted_row(1);
+        assert_eq!(component.rows.len(), 2);
+        assert!(!component.is_inserted_row(1));
+        assert!(component.is_deleted_row(0));
+        assert!(component.is_deleted_row(1));
+        assert!(component.is_updated_cell(1, 1));
+    }
+
+    #[test]
+    fn test_next_page() {
+        let mut component = TableComponent::new(KeyConfig::default(), Settings::default());
+        component.headers = vec!["id", "name"].into_iter().map(|h| h.into()).collect();
+        component.rows = vec![
+            ["1", "a"].iter().map(|h| Arc::new(RwLock::new(h.into()))).collect(),
+            ["2", "b"].iter().map(|h| Arc::new(RwLock::new(h.into()))).collect(),
+            ["", ""].iter().map(|h| Arc::new(RwLock::new(h.into()))).collect(),
+        ];
+        component.changes = vec![Change::Insert { row: 2 }];
+        component.page = Page::new(Some("name desc".to_string()), vec!["id".to_string()]);
+        let page = component.next_page();
+        assert_eq!(page.offset, 2);
+        assert_eq!(page.keyset.unwrap().after, Some(vec![Param::Text("b".to_string()), Param::Text("2".to_string())]));
+    }
 }
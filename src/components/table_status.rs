@@ -1,4 +1,4 @@
-use super::{Component, DrawableComponent, EventState};
+use super::{utils::{format_count, format_size}, Component, DrawableComponent, EventState};
 use crate::components::help_info::HelpInfo;
 use crate::event::Key;
 use anyhow::Result;
@@ -15,6 +15,7 @@ use tui::{
 pub struct TableStatusComponent {
     column_count: Option<usize>,
     row_count: Option<usize>,
+    total_rows: Option<u64>, // counted on demand
     table: Option<Table>,
     table_value: Option<String>,
 }
@@ -23,6 +24,7 @@ impl Default for TableStatusComponent {
     fn default() -> Self {
         Self {
             row_count: None,
+            total_rows: None,
             column_count: None,
             table: None,
             table_value: None,
@@ -33,12 +35,14 @@ impl Default for TableStatusComponent {
 impl TableStatusComponent {
     pub fn new(
         row_count: Option<usize>,
+        total_rows: Option<u64>,
         column_count: Option<usize>,
         table_value: Option<String>,
         table: Option<Table>,
     ) -> Self {
         Self {
             row_count,
+            total_rows,
             column_count,
             table,
             table_value,
@@ -49,10 +53,17 @@ impl TableStatusComponent {
 // TODO:
 impl DrawableComponent for TableStatusComponent {
     fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, focused: bool) -> Result<()> {
+        // loaded rows of the exact count, or of the estimate
+        let total = match (self.total_rows, self.table.as_ref().and_then(|t| t.rows)) {
+            (Some(total), _) => format!(" of {}", total),
+            (None, Some(estimate)) => format!(" of ~{}", format_count(estimate)),
+            (None, None) => String::new(),
+        };
         let mut spans = vec![
             Span::from(format!(
-                "rows: {}, ",
-                self.row_count.map_or("-".to_string(), |c| c.to_string())
+                "rows: {}{}, ",
+                self.row_count.map_or("-".to_string(), |c| c.to_string()),
+                total
             )),
             Span::from(format!(
                 "cols: {}, ",
@@ -63,6 +74,9 @@ impl DrawableComponent for TableStatusComponent {
             if let Some(engine) = &table.engine {
                 spans.push(Span::from(format!("engine: {}, ", engine.to_string())))
             }
+            if let Some(size) = table.size {
+                spans.push(Span::from(format!("size: {}, ", format_size(size))))
+            }
         }
         spans.push(Span::from(format!("{}", self.table_value.as_deref().unwrap_or(""))));
         let status = Paragraph::new(Spans::from(spans))
@@ -0,0 +1,38 @@
+// short numbers for the narrow tree and status bar: 950, 1.2k, 34M
+pub fn format_count(n: u64) -> String {
+    scaled(n as f64, 1000.0, &["", "k", "M", "G", "T"])
+}
+
+// bytes: 512B, 64kB, 1.5MB
+pub fn format_size(bytes: u64) -> String {
+    scaled(bytes as f64, 1024.0, &["B", "kB", "MB", "GB", "TB"])
+}
+
+fn scaled(mut n: f64, step: f64, units: &[&str]) -> String {
+    let mut unit = 0;
+    while n >= step && unit + 1 < units.len() {
+        n /= step;
+        unit += 1;
+    }
+    // one decimal while it is a single digit
+    if unit > 0 && n < 10.0 {
+        format!("{:.1}{}", n, units[unit])
+    } else {
+        format!("{:.0}{}", n, units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_count, format_size};
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_count(950), "950");
+        assert_eq!(format_count(1234), "1.2k");
+        assert_eq!(format_count(34_000_000), "34M");
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(64 * 1024), "64kB");
+        assert_eq!(format_size(1536 * 1024), "1.5MB");
+    }
+}
@@ -1,4 +1,5 @@
 use std::convert::TryFrom;
+use std::ops::Range;
 use tui::text::{Text, Spans, Span};
 use tui::style::{Color, Style};
 use crate::config::{Settings, DatabaseType};
@@ -44,9 +45,12 @@ use crate::sql::token::{tokenizer::Tokenizer, token_type::TokenType };
 // }
 
 // TODO: sql formatter
-pub fn highlight_sql<'a>(input: &'a str, settings: &'a Settings, database_type: &DatabaseType) -> Text<'a> {
+// `selected` is a byte range of the input drawn with a background
+pub fn highlight_sql<'a>(input: &'a str, settings: &'a Settings, database_type: &DatabaseType, selected: Option<Range<usize>>) -> Text<'a> {
     let style_hl = Style::default().fg(settings.color);
     let style_normal = Style::default().fg(Color::White);
+    let selected = selected.unwrap_or_default();
+    let style_selected = |style: Style, at: usize| if selected.contains(&at) { style.bg(Color::DarkGray) } else { style };
     let mut spans = vec![];
     let t = Tokenizer::try_from(database_type.clone());
     if t.is_err() {
@@ -55,11 +59,20 @@ pub fn highlight_sql<'a>(input: &'a str, settings: &'a Settings, database_type:
     };
     let t = t.unwrap();
     let tokens = t.tokenize(input);
+    let mut offset = 0;
     for token in tokens {
+        if !token.whitespace_before.is_empty() {
+            // only whitespace between two selected tokens is selected
+            let end = offset + token.whitespace_before.len();
+            let style = if offset > 0 && selected.contains(&(offset - 1)) { style_selected(style_normal, end) } else { style_normal };
+            spans.push(Span::styled(token.whitespace_before.clone(), style));
+            offset = end;
+        }
         let span = match token.typ {
-            TokenType::ReservedTopLevel | TokenType::Reserved => Span::styled(token.to_string(), style_hl),
-            _ => Span::styled(token.to_string(), style_normal)
+            TokenType::ReservedTopLevel | TokenType::Reserved => Span::styled(token.value.clone(), style_selected(style_hl, offset)),
+            _ => Span::styled(token.value.clone(), style_selected(style_normal, offset))
         };
+        offset += token.value.len();
         spans.push(span);
     };
     Text::from(Spans::from(spans))
@@ -1,4 +1,6 @@
 pub mod scroll_vertical;
+mod format;
 mod highlight;
 
+pub use format::{format_count, format_size};
 pub use highlight::highlight_sql;
@@ -0,0 +1,126 @@
+use super::{Component, DrawableComponent, EventState};
+use crate::components::help_info::HelpInfo;
+use crate::config::KeyConfig;
+use crate::database::YankFormat;
+use crate::event::Key;
+use anyhow::Result;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::Span,
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+// picks the format the selected cells are yanked as, the last pick stays selected
+pub struct YankComponent {
+    selected: usize,
+    visible: bool,
+    key_config: KeyConfig,
+}
+
+impl YankComponent {
+    pub fn new(key_config: KeyConfig) -> Self {
+        Self {
+            selected: 0,
+            visible: false,
+            key_config,
+        }
+    }
+
+    pub fn format(&self) -> YankFormat {
+        YankFormat::ALL[self.selected]
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+}
+
+impl DrawableComponent for YankComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame<B>, _area: Rect, _focused: bool) -> Result<()> {
+        if self.visible {
+            const SIZE: (u16, u16) = (32, YankFormat::ALL.len() as u16 + 2);
+            let area = Rect::new(
+                (f.size().width.saturating_sub(SIZE.0)) / 2,
+                (f.size().height.saturating_sub(SIZE.1)) / 2,
+                SIZE.0.min(f.size().width),
+                SIZE.1.min(f.size().height),
+            );
+            let items = YankFormat::ALL.iter().map(|format| ListItem::new(Span::raw(format.to_string()))).collect::<Vec<_>>();
+            let list = List::new(items)
+                .block(Block::default().title("Yank as").borders(Borders::ALL).border_type(BorderType::Thick))
+                .highlight_style(Style::default().bg(Color::Blue));
+            let mut state = ListState::default();
+            state.select(Some(self.selected));
+
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(list, area, &mut state);
+        }
+        Ok(())
+    }
+}
+
+impl Component for YankComponent {
+    fn helps(&self, _out: &mut Vec<HelpInfo>) {}
+
+    // enter needs the pool, see TableComponent::async_event
+    fn event(&mut self, key: &[Key]) -> Result<EventState> {
+        if !self.visible {
+            return Ok(EventState::NotConsumed);
+        }
+        let key = key[0];
+        if key == self.key_config.exit_popup {
+            self.hide();
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_down {
+            self.selected = (self.selected + 1).min(YankFormat::ALL.len() - 1);
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_up {
+            self.selected = self.selected.saturating_sub(1);
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_to_top {
+            self.selected = 0;
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.scroll_to_bottom {
+            self.selected = YankFormat::ALL.len() - 1;
+            return Ok(EventState::Consumed);
+        }
+        Ok(EventState::NotConsumed)
+    }
+
+    fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn show(&mut self) -> Result<()> {
+        self.visible = true;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Component, KeyConfig, YankComponent, YankFormat};
+    use crate::event::Key;
+
+    #[test]
+    fn test_pick_format() {
+        let mut yank = YankComponent::new(KeyConfig::default());
+        yank.show().unwrap();
+        yank.event(&[Key::Char('j')]).unwrap();
+        yank.event(&[Key::Char('j')]).unwrap();
+        assert_eq!(yank.format(), YankFormat::Markdown);
+        yank.event(&[Key::Char('G')]).unwrap();
+        assert_eq!(yank.format(), YankFormat::WhereIn);
+        yank.event(&[Key::Char('j')]).unwrap();
+        assert_eq!(yank.format(), YankFormat::WhereIn);
+        yank.event(&[Key::Esc]).unwrap();
+        assert!(!yank.is_visible());
+        // opened again on the last pick
+        yank.show().unwrap();
+        assert_eq!(yank.format(), YankFormat::WhereIn);
+    }
+}
@@ -0,0 +1,155 @@
+use super::{Param, Pool, Value, INDENT};
+use crate::config::DatabaseType;
+use database_tree::Table;
+
+// the statements that recreate a postgres table, built by the catalog itself so names are quoted
+// and types, defaults and bounds read the way postgres prints them
+impl DatabaseType {
+    pub(super) async fn postgres_ddl(&self, pool: &Box<dyn Pool>, table: &Table) -> anyhow::Result<String> {
+        let rel = format!("{}.{}", self.quote_identifier(&table.pg_schema()), self.quote_identifier(&table.name));
+        let oid = format!("{}::regclass", self.literal(&Param::Text(rel.clone())));
+
+        let info = pool.query(&format!(
+            "SELECT c.relpersistence::text, c.relispartition::text, array_to_string(c.reloptions, ', '), pg_get_partkeydef(c.oid),
+                pg_get_expr(c.relpartbound, c.oid), c.relrowsecurity::text, c.relforcerowsecurity::text,
+                (SELECT string_agg(format('%I.%I', n.nspname, p.relname), ', ' ORDER BY i.inhseqno)
+                    FROM pg_inherits i JOIN pg_class p ON p.oid = i.inhparent JOIN pg_namespace n ON n.oid = p.relnamespace
+                    WHERE i.inhrelid = c.oid)
+            FROM pg_class c WHERE c.oid = {}", oid)).await?.rows;
+        let info = info.first().ok_or_else(|| anyhow::anyhow!("no table {}", rel))?;
+        let unlogged = text(&info[0]) == Some("u");
+        let partition = text(&info[1]) == Some("true");
+        let options = text(&info[2]).filter(|o| !o.is_empty());
+        let partition_key = text(&info[3]);
+        let bound = text(&info[4]);
+        let parents = text(&info[7]);
+
+        // a partition gets its columns from the parent, an inheriting table only declares its own
+        let columns = pool.query(&format!(
+            "SELECT format('%I %s', a.attname, format_type(a.atttypid, a.atttypmod))
+                || CASE WHEN a.attcollation <> t.typcollation THEN (
+                    SELECT format(' COLLATE %I.%I', n.nspname, co.collname)
+                    FROM pg_collation co JOIN pg_namespace n ON n.oid = co.collnamespace WHERE co.oid = a.attcollation) ELSE '' END
+                || CASE WHEN a.attgenerated = 's' THEN format(' GENERATED ALWAYS AS (%s) STORED', pg_get_expr(d.adbin, d.adrelid))
+                    WHEN d.adbin IS NOT NULL THEN ' DEFAULT ' || pg_get_expr(d.adbin, d.adrelid) ELSE '' END
+                || CASE a.attidentity WHEN 'a' THEN ' GENERATED ALWAYS AS IDENTITY' WHEN 'd' THEN ' GENERATED BY DEFAULT AS IDENTITY' ELSE '' END
+                || CASE WHEN a.attnotnull THEN ' NOT NULL' ELSE '' END
+            FROM pg_attribute a
+            JOIN pg_type t ON t.oid = a.atttypid
+            LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = {} AND a.attnum > 0 AND NOT a.attisdropped AND a.attislocal
+            ORDER BY a.attnum", oid)).await?.rows;
+        // foreign keys may point at tables created later, they are added at the end
+        let constraints = pool.query(&format!(
+            "SELECT format('CONSTRAINT %I %s', conname, pg_get_constraintdef(oid)), contype::text
+            FROM pg_constraint
+            WHERE conrelid = {} AND contype IN ('p', 'u', 'c', 'x', 'f') AND conislocal
+            ORDER BY CASE contype WHEN 'p' THEN 0 WHEN 'u' THEN 1 WHEN 'x' THEN 2 WHEN 'c' THEN 3 ELSE 4 END, conname", oid)).await?.rows;
+        let (foreign_keys, constraints): (Vec<_>, Vec<_>) = constraints.iter().partition(|c| text(&c[1]) == Some("f"));
+
+        let mut ddl = vec![];
+        // serial columns own their sequence
+        let sequences = pool.query(&format!(
+            "SELECT format('CREATE SEQUENCE %I.%I AS %s INCREMENT BY %s MINVALUE %s MAXVALUE %s START WITH %s CACHE %s%s;',
+                    n.nspname, s.relname, format_type(q.seqtypid, NULL), q.seqincrement, q.seqmin, q.seqmax, q.seqstart, q.seqcache,
+                    CASE WHEN q.seqcycle THEN ' CYCLE' ELSE '' END),
+                format('ALTER SEQUENCE %I.%I OWNED BY %s.%I;', n.nspname, s.relname, {rel}, a.attname)
+            FROM pg_depend d
+            JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S'
+            JOIN pg_sequence q ON q.seqrelid = s.oid
+            JOIN pg_namespace n ON n.oid = s.relnamespace
+            JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+            WHERE d.classid = 'pg_class'::regclass AND d.refobjid = {oid} AND d.deptype = 'a'
+            ORDER BY s.relname", rel = self.literal(&Param::Text(rel.clone())), oid = oid)).await?.rows;
+        ddl.extend(sequences.iter().flat_map(|s| text(&s[0])).map(str::to_string));
+
+        let elements = columns.iter().chain(constraints)
+            .flat_map(|row| text(&row[0]))
+            .map(|element| format!("{}{}", INDENT, element))
+            .collect::<Vec<_>>();
+        let mut create = format!("CREATE {}TABLE {}", if unlogged { "UNLOGGED " } else { "" }, rel);
+        if partition {
+            create = format!("{} PARTITION OF {}", create, parents.unwrap_or_default());
+        }
+        if !elements.is_empty() || !partition {
+            create = format!("{} (\n{}\n)", create, elements.join(",\n"));
+        }
+        match (partition, parents) {
+            (true, _) => create = format!("{} {}", create, bound.unwrap_or("DEFAULT")),
+            (false, Some(parents)) => create = format!("{} INHERITS ({})", create, parents),
+            _ => {},
+        }
+        if let Some(key) = partition_key {
+            create = format!("{} PARTITION BY {}", create, key);
+        }
+        if let Some(options) = options {
+            create = format!("{} WITH ({})", create, options);
+        }
+        ddl.push(format!("{};", create));
+        ddl.extend(sequences.iter().flat_map(|s| text(&s[1])).map(str::to_string));
+
+        let partitions = pool.query(&format!(
+            "SELECT format('CREATE TABLE %I.%I PARTITION OF %s %s', n.nspname, c.relname, {rel}, pg_get_expr(c.relpartbound, c.oid))
+                || coalesce(' PARTITION BY ' || pg_get_partkeydef(c.oid), '') || ';'
+            FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE i.inhparent = {oid} AND c.relispartition
+            ORDER BY c.relname", rel = self.literal(&Param::Text(rel.clone())), oid = oid)).await?.rows;
+        ddl.extend(partitions.iter().flat_map(|p| text(&p[0])).map(str::to_string));
+
+        // indexes of constraints come with them, the ones attached to a parent index with the partition.
+        // an index of a partitioned table prints as `ON ONLY`, created without it the partitions get it too
+        let indexes = pool.query(&format!(
+            "SELECT CASE WHEN c.relkind = 'I' THEN regexp_replace(pg_get_indexdef(i.indexrelid), ' ON ONLY ', ' ON ')
+                ELSE pg_get_indexdef(i.indexrelid) END || ';'
+            FROM pg_index i JOIN pg_class c ON c.oid = i.indexrelid
+            WHERE i.indrelid = {oid}
+                AND NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = i.indexrelid AND con.conrelid = {oid})
+                AND NOT EXISTS (SELECT 1 FROM pg_inherits h WHERE h.inhrelid = i.indexrelid)
+            ORDER BY c.relname", oid = oid)).await?.rows;
+        ddl.extend(indexes.iter().flat_map(|i| text(&i[0])).map(str::to_string));
+        ddl.extend(foreign_keys.iter().flat_map(|f| text(&f[0])).map(|f| format!("ALTER TABLE {} ADD {};", rel, f)));
+
+        // cloned from the parent for a partition
+        let triggers = pool.query(&format!(
+            "SELECT pg_get_triggerdef(t.oid, true) || ';'
+            FROM pg_trigger t
+            WHERE t.tgrelid = {oid} AND NOT t.tgisinternal
+                AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.objid = t.oid AND d.deptype = 'P')
+            ORDER BY t.tgname", oid = oid)).await?.rows;
+        ddl.extend(triggers.iter().flat_map(|t| text(&t[0])).map(str::to_string));
+
+        if text(&info[5]) == Some("true") {
+            ddl.push(format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY;", rel));
+        }
+        if text(&info[6]) == Some("true") {
+            ddl.push(format!("ALTER TABLE {} FORCE ROW LEVEL SECURITY;", rel));
+        }
+        let policies = pool.query(&format!(
+            "SELECT format('CREATE POLICY %I ON %s AS %s FOR %s TO %s', p.polname, {rel},
+                    CASE WHEN p.polpermissive THEN 'PERMISSIVE' ELSE 'RESTRICTIVE' END,
+                    CASE p.polcmd WHEN 'r' THEN 'SELECT' WHEN 'a' THEN 'INSERT' WHEN 'w' THEN 'UPDATE' WHEN 'd' THEN 'DELETE' ELSE 'ALL' END,
+                    (SELECT string_agg(CASE WHEN r = 0 THEN 'PUBLIC' ELSE quote_ident(pg_get_userbyid(r)) END, ', ') FROM unnest(p.polroles) r))
+                || coalesce(' USING (' || pg_get_expr(p.polqual, p.polrelid) || ')', '')
+                || coalesce(' WITH CHECK (' || pg_get_expr(p.polwithcheck, p.polrelid) || ')', '') || ';'
+            FROM pg_policy p WHERE p.polrelid = {oid} ORDER BY p.polname", rel = self.literal(&Param::Text(rel.clone())), oid = oid)).await?.rows;
+        ddl.extend(policies.iter().flat_map(|p| text(&p[0])).map(str::to_string));
+
+        let comments = pool.query(&format!(
+            "SELECT CASE WHEN d.objsubid = 0 THEN format('COMMENT ON TABLE %s IS %L;', {rel}, d.description)
+                ELSE format('COMMENT ON COLUMN %s.%I IS %L;', {rel}, a.attname, d.description) END
+            FROM pg_description d LEFT JOIN pg_attribute a ON a.attrelid = d.objoid AND a.attnum = d.objsubid
+            WHERE d.objoid = {oid} AND d.classoid = 'pg_class'::regclass
+            ORDER BY d.objsubid", rel = self.literal(&Param::Text(rel.clone())), oid = oid)).await?.rows;
+        ddl.extend(comments.iter().flat_map(|c| text(&c[0])).map(str::to_string));
+
+        Ok(ddl.join("\n"))
+    }
+}
+
+fn text(value: &Value) -> Option<&str> {
+    if value.is_null() {
+        None
+    } else {
+        Some(value.data.as_str())
+    }
+}
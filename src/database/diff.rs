@@ -0,0 +1,537 @@
+use super::{Param, Pool, TableRow, INDENT};
+use crate::config::DatabaseType;
+use database_tree::{Child, Database, Table};
+use std::collections::HashMap;
+use std::fmt;
+
+/// what `get_columns`, `get_indexes` and `get_foreign_keys` tell about a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    pub schema: Option<String>,
+    pub name: String,
+    pub columns: Vec<ColumnSchema>,
+    pub indexes: Vec<IndexSchema>,
+    pub foreign_keys: Vec<ForeignKeySchema>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub r#type: String,
+    pub nullable: bool,
+    pub default: Option<String>, // an sql expression
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSchema {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKeySchema {
+    pub name: Option<String>, // sqlite does not name them
+    pub columns: Vec<String>,
+    pub ref_table: String,
+    pub ref_columns: Vec<String>,
+}
+
+/// a change that brings the target in line with the source
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    CreateSchema(String),
+    CreateTable(TableSchema),
+    DropTable(TableSchema),
+    AddColumn(TableSchema, ColumnSchema),
+    DropColumn(TableSchema, ColumnSchema),
+    AlterColumn(TableSchema, ColumnSchema, ColumnSchema), // from the target to the source column
+    CreateIndex(TableSchema, IndexSchema),
+    DropIndex(TableSchema, IndexSchema),
+    AddForeignKey(TableSchema, ForeignKeySchema),
+    DropForeignKey(TableSchema, ForeignKeySchema),
+}
+
+/// the schemas compared and what the target needs
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub source: String,
+    pub target: String,
+    pub differences: Vec<Difference>,
+    pub script: Vec<String>,
+}
+
+impl TableSchema {
+    fn path(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", schema, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    fn key(&self) -> (Option<&str>, &str) {
+        (self.schema.as_deref(), &self.name)
+    }
+}
+
+impl IndexSchema {
+    // the primary key of postgres or mysql, sqlite creates its own for keys and unique columns
+    fn is_primary(&self) -> bool {
+        self.name == "PRIMARY" || self.name.ends_with("_pkey") || self.is_implicit()
+    }
+
+    fn is_implicit(&self) -> bool {
+        self.name.starts_with("sqlite_autoindex_")
+    }
+}
+
+impl ForeignKeySchema {
+    fn key(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("({}) {}", self.columns.join(", "), self.ref_table))
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::CreateSchema(s) => write!(f, "+ schema {}", s),
+            Difference::CreateTable(t) => write!(f, "+ table {} ({} columns)", t.path(), t.columns.len()),
+            Difference::DropTable(t) => write!(f, "- table {}", t.path()),
+            Difference::AddColumn(t, c) => write!(f, "+ column {}.{} {}", t.path(), c.name, c.r#type),
+            Difference::DropColumn(t, c) => write!(f, "- column {}.{} {}", t.path(), c.name, c.r#type),
+            Difference::AlterColumn(t, from, to) => {
+                let mut changes = vec![];
+                if !from.r#type.eq_ignore_ascii_case(&to.r#type) {
+                    changes.push(format!("{} -> {}", from.r#type, to.r#type));
+                }
+                if from.nullable != to.nullable {
+                    changes.push((if to.nullable { "null" } else { "not null" }).to_string());
+                }
+                if from.default != to.default {
+                    changes.push(format!("default {}", to.default.as_deref().unwrap_or("none")));
+                }
+                write!(f, "~ column {}.{} {}", t.path(), to.name, changes.join(", "))
+            },
+            Difference::CreateIndex(t, i) => write!(f, "+ index {} on {} ({})", i.name, t.path(), i.columns.join(", ")),
+            Difference::DropIndex(t, i) => write!(f, "- index {} on {}", i.name, t.path()),
+            Difference::AddForeignKey(t, k) => write!(f, "+ foreign key {} on {} -> {} ({})", k.key(), t.path(), k.ref_table, k.ref_columns.join(", ")),
+            Difference::DropForeignKey(t, k) => write!(f, "- foreign key {} on {}", k.key(), t.path()),
+        }
+    }
+}
+
+// the fields of a row by name, empty when the driver has no value
+pub(super) fn fields(row: &dyn TableRow) -> HashMap<String, String> {
+    row.fields().into_iter().zip(row.columns()).collect()
+}
+
+/// the tables of `database` with their columns, indexes and foreign keys
+pub async fn load_schema(pool: &Box<dyn Pool>, database: &str) -> anyhow::Result<Vec<TableSchema>> {
+    let database = Database::new(database.to_string(), vec![]);
+    let database_type = pool.database_type();
+    let mut schemas = vec![];
+    for table in list_tables(pool, &database).await? {
+        schemas.push(load_table(pool, &database_type, &database, table).await?);
+    }
+    Ok(schemas)
+}
+
+// the tables of every schema, without the internal ones of sqlite
+pub(super) async fn list_tables(pool: &Box<dyn Pool>, database: &Database) -> anyhow::Result<Vec<Table>> {
+    let tables = pool.get_tables(database.name.clone()).await?.into_iter().flat_map(|child| match child {
+        Child::Table(table) => vec![table],
+        Child::Schema(schema) => schema.tables,
+        Child::Object(_) => vec![],
+    });
+    Ok(tables.filter(|t| !t.name.starts_with("sqlite_")).collect())
+}
+
+async fn load_table(pool: &Box<dyn Pool>, database_type: &DatabaseType, database: &Database, table: Table) -> anyhow::Result<TableSchema> {
+    let columns = pool.get_columns(database, &table).await?.iter().map(|row| {
+        let row = fields(row.as_ref());
+        let null = row.get("null").cloned().unwrap_or_default();
+        let default = row.get("default").filter(|d| !d.is_empty()).map(|d| default_expression(database_type, d));
+        ColumnSchema {
+            name: row.get("name").cloned().unwrap_or_default(),
+            r#type: row.get("type").cloned().unwrap_or_default(),
+            // sqlite marks the not null columns
+            nullable: match database_type {
+                DatabaseType::Sqlite => null.is_empty(),
+                _ => null.eq_ignore_ascii_case("YES"),
+            },
+            default,
+        }
+    }).collect();
+
+    // a row per column of each index and key
+    let mut indexes: Vec<IndexSchema> = vec![];
+    for row in pool.get_indexes(database, &table).await? {
+        let row = fields(row.as_ref());
+        let (name, column) = (row.get("name").cloned().unwrap_or_default(), row.get("column_name").cloned().unwrap_or_default());
+        match indexes.iter_mut().find(|i| i.name == name) {
+            Some(index) => index.columns.push(column),
+            None => indexes.push(IndexSchema { name, columns: vec![column] }),
+        }
+    }
+    let mut foreign_keys: Vec<ForeignKeySchema> = vec![];
+    for row in pool.get_foreign_keys(database, &table).await? {
+        let row = fields(row.as_ref());
+        let name = row.get("name").cloned();
+        let ref_table = row.get("ref_table").cloned().unwrap_or_default();
+        let column = row.get("column_name").cloned().unwrap_or_default();
+        let ref_column = row.get("ref_column").cloned().unwrap_or_default();
+        // unnamed keys are told apart by the table they point at
+        match foreign_keys.iter_mut().find(|k| k.name == name && (name.is_some() || k.ref_table == ref_table)) {
+            Some(key) => {
+                if !key.columns.contains(&column) { key.columns.push(column) }
+                if !key.ref_columns.contains(&ref_column) { key.ref_columns.push(ref_column) }
+            },
+            None => foreign_keys.push(ForeignKeySchema { name, columns: vec![column], ref_table, ref_columns: vec![ref_column] }),
+        }
+    }
+    Ok(TableSchema { schema: table.schema, name: table.name, columns, indexes, foreign_keys })
+}
+
+// mysql lists the value of a default, the others the expression
+fn default_expression(database_type: &DatabaseType, default: &str) -> String {
+    let upper = default.to_uppercase();
+    let expression = default.parse::<f64>().is_ok()
+        || upper == "NULL"
+        || upper.starts_with("CURRENT_")
+        || default.starts_with('(')
+        || default.starts_with("b'");
+    match database_type {
+        DatabaseType::MySql if !expression => database_type.literal(&Param::Text(default.to_string())),
+        _ => default.to_string(),
+    }
+}
+
+/// the differences of the tables of `target` from those of `source`
+pub fn compare(source: &[TableSchema], target: &[TableSchema]) -> Vec<Difference> {
+    let mut differences = vec![];
+    for table in source {
+        let other = match target.iter().find(|t| t.key() == table.key()) {
+            Some(other) => other,
+            None => {
+                let schema = table.schema.as_ref().filter(|s| !target.iter().any(|t| t.schema.as_ref() == Some(s)));
+                if let Some(schema) = schema.filter(|s| !differences.contains(&Difference::CreateSchema(s.to_string()))) {
+                    differences.push(Difference::CreateSchema(schema.clone()));
+                }
+                differences.push(Difference::CreateTable(table.clone()));
+                continue;
+            },
+        };
+        for column in &table.columns {
+            match other.columns.iter().find(|c| c.name == column.name) {
+                None => differences.push(Difference::AddColumn(other.clone(), column.clone())),
+                Some(c) if !c.r#type.eq_ignore_ascii_case(&column.r#type) || c.nullable != column.nullable || c.default != column.default => {
+                    differences.push(Difference::AlterColumn(other.clone(), c.clone(), column.clone()))
+                },
+                Some(_) => {},
+            }
+        }
+        for column in other.columns.iter().filter(|c| !table.columns.iter().any(|t| t.name == c.name)) {
+            differences.push(Difference::DropColumn(other.clone(), column.clone()));
+        }
+        // a changed index or key is dropped and created again
+        for index in &other.indexes {
+            if !table.indexes.contains(index) {
+                differences.push(Difference::DropIndex(other.clone(), index.clone()));
+            }
+        }
+        for index in &table.indexes {
+            if !other.indexes.contains(index) {
+                differences.push(Difference::CreateIndex(other.clone(), index.clone()));
+            }
+        }
+        for key in &other.foreign_keys {
+            if !table.foreign_keys.contains(key) {
+                differences.push(Difference::DropForeignKey(other.clone(), key.clone()));
+            }
+        }
+        for key in &table.foreign_keys {
+            if !other.foreign_keys.contains(key) {
+                differences.push(Difference::AddForeignKey(other.clone(), key.clone()));
+            }
+        }
+    }
+    for table in target.iter().filter(|t| !source.iter().any(|s| s.key() == t.key())) {
+        differences.push(Difference::DropTable(table.clone()));
+    }
+    differences
+}
+
+impl DatabaseType {
+    /// the statements that apply `differences` to `database`: keys and indexes are dropped first,
+    /// foreign keys added once every table exists
+    pub fn migration(&self, database: &str, differences: &[Difference]) -> Vec<String> {
+        let mut statements = differences.iter().flat_map(|d| self.difference_statements(database, d)).collect::<Vec<_>>();
+        statements.sort_by_key(|(order, _)| *order);
+        statements.into_iter().map(|(_, statement)| statement).collect()
+    }
+
+    fn difference_statements(&self, database: &str, difference: &Difference) -> Vec<(u8, String)> {
+        match difference {
+            Difference::DropForeignKey(t, k) => vec![(0, self.drop_foreign_key(database, t, k))],
+            Difference::DropIndex(t, i) => vec![(1, self.drop_index(database, t, i))],
+            Difference::CreateSchema(s) => vec![(2, format!("CREATE SCHEMA {};", self.quote_identifier(s)))],
+            Difference::CreateTable(t) => {
+                let mut statements = vec![(2, self.create_table(database, t))];
+                statements.extend(t.indexes.iter().filter(|i| !i.is_primary()).map(|i| (6, self.create_index(database, t, i))));
+                // sqlite declares them in the table
+                if !matches!(self, DatabaseType::Sqlite) {
+                    statements.extend(t.foreign_keys.iter().map(|k| (7, self.add_foreign_key(database, t, k))));
+                }
+                statements
+            },
+            Difference::AddColumn(t, c) => vec![(3, format!("ALTER TABLE {} ADD {}{};", self.table(database, t), self.column_keyword(), self.column_definition(c)))],
+            Difference::AlterColumn(t, from, to) => self.alter_column(database, t, from, to).into_iter().map(|s| (4, s)).collect(),
+            Difference::DropColumn(t, c) => vec![(5, format!("ALTER TABLE {} DROP COLUMN {};", self.table(database, t), self.quote_identifier(&c.name)))],
+            Difference::CreateIndex(t, i) => vec![(6, self.create_index(database, t, i))],
+            Difference::AddForeignKey(t, k) => vec![(7, self.add_foreign_key(database, t, k))],
+            Difference::DropTable(t) => vec![(8, format!("DROP TABLE {};", self.table(database, t)))],
+        }
+    }
+
+    fn table(&self, database: &str, table: &TableSchema) -> String {
+        self.table_name(database, table, &table.name)
+    }
+
+    // a table next to `table`, the one a foreign key points at
+    fn table_name(&self, database: &str, table: &TableSchema, name: &str) -> String {
+        match (self, &table.schema) {
+            (DatabaseType::MySql, _) => format!("{}.{}", self.quote_identifier(database), self.quote_identifier(name)),
+            (DatabaseType::Postgres | DatabaseType::Mssql, Some(schema)) => format!("{}.{}", self.quote_identifier(schema), self.quote_identifier(name)),
+            _ => self.quote_identifier(name),
+        }
+    }
+
+    fn column_keyword(&self) -> &str {
+        match self {
+            DatabaseType::Mssql => "",
+            _ => "COLUMN ",
+        }
+    }
+
+    fn column_definition(&self, column: &ColumnSchema) -> String {
+        let mut definition = format!("{} {}", self.quote_identifier(&column.name), column.r#type);
+        let default = column.default.as_ref();
+        // a postgres serial column defaults to its own sequence, which the target may not have yet
+        if let (DatabaseType::Postgres, Some(default)) = (self, default.filter(|d| d.starts_with("nextval("))) {
+            let serial = match column.r#type.as_str() {
+                "smallint" => Some("smallserial"),
+                "integer" => Some("serial"),
+                "bigint" => Some("bigserial"),
+                _ => None,
+            };
+            return match serial {
+                Some(serial) => format!("{} {}", self.quote_identifier(&column.name), serial),
+                None => format!("{} DEFAULT {}", definition, default),
+            };
+        }
+        if !column.nullable {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(default) = default {
+            definition = format!("{} DEFAULT {}", definition, default);
+        }
+        definition
+    }
+
+    fn columns(&self, columns: &[String]) -> String {
+        columns.iter().map(|c| self.quote_identifier(c)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn create_table(&self, database: &str, table: &TableSchema) -> String {
+        let mut elements = table.columns.iter().map(|c| self.column_definition(c)).collect::<Vec<_>>();
+        // sqlite names its own
+        if let Some(key) = table.indexes.iter().find(|i| i.is_primary() && !i.is_implicit()) {
+            elements.push(format!("PRIMARY KEY ({})", self.columns(&key.columns)));
+        }
+        if matches!(self, DatabaseType::Sqlite) {
+            elements.extend(table.foreign_keys.iter().map(|k| format!(
+                "FOREIGN KEY ({}) REFERENCES {} ({})",
+                self.columns(&k.columns), self.quote_identifier(&k.ref_table), self.columns(&k.ref_columns),
+            )));
+        }
+        let elements = elements.iter().map(|e| format!("{}{}", INDENT, e)).collect::<Vec<_>>();
+        format!("CREATE TABLE {} (\n{}\n);", self.table(database, table), elements.join(",\n"))
+    }
+
+    fn alter_column(&self, database: &str, table: &TableSchema, from: &ColumnSchema, to: &ColumnSchema) -> Vec<String> {
+        let path = self.table(database, table);
+        let name = self.quote_identifier(&to.name);
+        match self {
+            DatabaseType::Postgres => {
+                let mut statements = vec![];
+                if !from.r#type.eq_ignore_ascii_case(&to.r#type) {
+                    statements.push(format!("ALTER TABLE {} ALTER COLUMN {} TYPE {};", path, name, to.r#type));
+                }
+                if from.nullable != to.nullable {
+                    let action = if to.nullable { "DROP" } else { "SET" };
+                    statements.push(format!("ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;", path, name, action));
+                }
+                if from.default != to.default {
+                    statements.push(match &to.default {
+                        Some(default) => format!("ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};", path, name, default),
+                        None => format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;", path, name),
+                    });
+                }
+                statements
+            },
+            DatabaseType::MySql => vec![format!("ALTER TABLE {} MODIFY COLUMN {};", path, self.column_definition(to))],
+            // defaults are constraints of their own
+            DatabaseType::Mssql => vec![format!(
+                "ALTER TABLE {} ALTER COLUMN {} {} {}NULL;",
+                path, name, to.r#type, if to.nullable { "" } else { "NOT " },
+            )],
+            DatabaseType::Sqlite => vec![format!("-- sqlite can not alter column {}.{}, the table has to be rebuilt", table.path(), to.name)],
+        }
+    }
+
+    fn create_index(&self, database: &str, table: &TableSchema, index: &IndexSchema) -> String {
+        if index.is_implicit() {
+            return format!("-- {} comes from the definition of {}", index.name, table.path());
+        }
+        if index.is_primary() {
+            if matches!(self, DatabaseType::Sqlite) {
+                return format!("-- sqlite can not add a primary key to {}, the table has to be rebuilt", table.path());
+            }
+            return format!("ALTER TABLE {} ADD PRIMARY KEY ({});", self.table(database, table), self.columns(&index.columns));
+        }
+        // postgres puts an index in the schema of its table
+        format!("CREATE INDEX {} ON {} ({});", self.quote_identifier(&index.name), self.table(database, table), self.columns(&index.columns))
+    }
+
+    fn drop_index(&self, database: &str, table: &TableSchema, index: &IndexSchema) -> String {
+        let path = self.table(database, table);
+        match self {
+            _ if index.is_implicit() => format!("-- {} comes from the definition of {}", index.name, table.path()),
+            DatabaseType::MySql if index.is_primary() => format!("ALTER TABLE {} DROP PRIMARY KEY;", path),
+            DatabaseType::Postgres | DatabaseType::Mssql if index.is_primary() => {
+                format!("ALTER TABLE {} DROP CONSTRAINT {};", path, self.quote_identifier(&index.name))
+            },
+            DatabaseType::MySql | DatabaseType::Mssql => format!("DROP INDEX {} ON {};", self.quote_identifier(&index.name), path),
+            DatabaseType::Postgres => format!("DROP INDEX {};", self.table_name(database, table, &index.name)),
+            DatabaseType::Sqlite => format!("DROP INDEX {};", self.quote_identifier(&index.name)),
+        }
+    }
+
+    fn add_foreign_key(&self, database: &str, table: &TableSchema, key: &ForeignKeySchema) -> String {
+        if matches!(self, DatabaseType::Sqlite) {
+            return format!("-- sqlite can not add foreign key {} to {}, the table has to be rebuilt", key.key(), table.path());
+        }
+        let constraint = key.name.as_ref().map_or(String::new(), |name| format!("CONSTRAINT {} ", self.quote_identifier(name)));
+        format!(
+            "ALTER TABLE {} ADD {}FOREIGN KEY ({}) REFERENCES {} ({});",
+            self.table(database, table), constraint, self.columns(&key.columns),
+            self.table_name(database, table, &key.ref_table), self.columns(&key.ref_columns),
+        )
+    }
+
+    fn drop_foreign_key(&self, database: &str, table: &TableSchema, key: &ForeignKeySchema) -> String {
+        let path = self.table(database, table);
+        match (self, &key.name) {
+            (DatabaseType::MySql, Some(name)) => format!("ALTER TABLE {} DROP FOREIGN KEY {};", path, self.quote_identifier(name)),
+            (DatabaseType::Postgres | DatabaseType::Mssql, Some(name)) => format!("ALTER TABLE {} DROP CONSTRAINT {};", path, self.quote_identifier(name)),
+            _ => format!("-- sqlite can not drop foreign key {} of {}, the table has to be rebuilt", key.key(), table.path()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, load_schema, ColumnSchema, Difference, ForeignKeySchema, IndexSchema, TableSchema};
+    use crate::config::DatabaseType;
+    use crate::database::{Pool, SqlitePool};
+
+    fn column(name: &str, r#type: &str, nullable: bool) -> ColumnSchema {
+        ColumnSchema { name: name.to_string(), r#type: r#type.to_string(), nullable, default: None }
+    }
+
+    fn index(name: &str, columns: &[&str]) -> IndexSchema {
+        IndexSchema { name: name.to_string(), columns: columns.iter().map(|c| c.to_string()).collect() }
+    }
+
+    fn table(name: &str, columns: Vec<ColumnSchema>, indexes: Vec<IndexSchema>) -> TableSchema {
+        TableSchema { schema: Some("public".to_string()), name: name.to_string(), columns, indexes, foreign_keys: vec![] }
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut users = table("users", vec![column("id", "integer", false), column("email", "text", false)], vec![index("users_pkey", &["id"])]);
+        let orders = table("orders", vec![column("id", "integer", false), column("user_id", "integer", true)], vec![index("orders_pkey", &["id"])]);
+        let mut orders = orders;
+        orders.foreign_keys.push(ForeignKeySchema {
+            name: Some("orders_user_id_fkey".to_string()),
+            columns: vec!["user_id".to_string()],
+            ref_table: "users".to_string(),
+            ref_columns: vec!["id".to_string()],
+        });
+        let source = vec![users.clone(), orders];
+        users.columns[1] = column("email", "character varying", true);
+        users.columns.push(column("legacy", "text", true));
+        users.indexes.push(index("users_legacy", &["legacy"]));
+        let target = vec![users, table("audit", vec![column("id", "integer", false)], vec![])];
+
+        let differences = compare(&source, &target);
+        let descriptions = differences.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(descriptions, [
+            "~ column public.users.email character varying -> text, not null",
+            "- column public.users.legacy text",
+            "- index users_legacy on public.users",
+            "+ table public.orders (2 columns)",
+            "- table public.audit",
+        ]);
+        assert!(matches!(&differences[3], Difference::CreateTable(t) if t.name == "orders"));
+
+        // keys and indexes go first, foreign keys once their tables exist
+        assert_eq!(DatabaseType::Postgres.migration("app", &differences), [
+            r#"DROP INDEX "public"."users_legacy";"#,
+            "CREATE TABLE \"public\".\"orders\" (\n    \"id\" integer NOT NULL,\n    \"user_id\" integer,\n    PRIMARY KEY (\"id\")\n);",
+            r#"ALTER TABLE "public"."users" ALTER COLUMN "email" TYPE text;"#,
+            r#"ALTER TABLE "public"."users" ALTER COLUMN "email" SET NOT NULL;"#,
+            r#"ALTER TABLE "public"."users" DROP COLUMN "legacy";"#,
+            r#"ALTER TABLE "public"."orders" ADD CONSTRAINT "orders_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "public"."users" ("id");"#,
+            r#"DROP TABLE "public"."audit";"#,
+        ]);
+        assert_eq!(DatabaseType::MySql.migration("app", &differences[..3]), [
+            "DROP INDEX `users_legacy` ON `app`.`users`;",
+            "ALTER TABLE `app`.`users` MODIFY COLUMN `email` text NOT NULL;",
+            "ALTER TABLE `app`.`users` DROP COLUMN `legacy`;",
+        ]);
+        assert!(compare(&source, &source).is_empty());
+
+        // a table in a new schema, its serial column brings the sequence along
+        let mut things = table("things", vec![column("id", "integer", false)], vec![]);
+        things.schema = Some("extra".to_string());
+        things.columns[0].default = Some("nextval('extra.things_id_seq'::regclass)".to_string());
+        let differences = compare(&[things], &[]);
+        assert_eq!(differences[0], Difference::CreateSchema("extra".to_string()));
+        assert_eq!(DatabaseType::Postgres.migration("app", &differences), [
+            r#"CREATE SCHEMA "extra";"#,
+            "CREATE TABLE \"extra\".\"things\" (\n    \"id\" serial\n);",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_migration() {
+        let source: Box<dyn Pool> = Box::new(SqlitePool::new("sqlite::memory:", 100).await.unwrap());
+        let target: Box<dyn Pool> = Box::new(SqlitePool::new("sqlite::memory:", 100).await.unwrap());
+        for sql in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL DEFAULT '', name TEXT)",
+            "CREATE INDEX users_email ON users (email)",
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users (id))",
+        ] {
+            source.execute(sql).await.unwrap();
+        }
+        for sql in ["CREATE TABLE users (id INTEGER PRIMARY KEY, legacy TEXT, name TEXT)", "CREATE INDEX users_legacy ON users (legacy)"] {
+            target.execute(sql).await.unwrap();
+        }
+        let differences = compare(&load_schema(&source, "main").await.unwrap(), &load_schema(&target, "main").await.unwrap());
+        let script = DatabaseType::Sqlite.migration("main", &differences);
+        for statement in script.iter().filter(|s| !s.starts_with("--")) {
+            target.execute(statement).await.unwrap();
+        }
+        assert!(compare(&load_schema(&source, "main").await.unwrap(), &load_schema(&target, "main").await.unwrap()).is_empty());
+    }
+}
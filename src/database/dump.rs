@@ -0,0 +1,363 @@
+use super::diff::{fields, list_tables};
+use super::export::{sql_literal, TablePages};
+use super::import::copy_text;
+use super::{Datum, Header, Param, Pool, Value};
+use crate::config::DatabaseType;
+use crate::event::{Event, Store};
+use crate::sql::token::tokenizer::Tokenizer;
+use database_tree::{Database, Table};
+use regex::Regex;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+// rows per insert statement
+const DUMP_BATCH: usize = 100;
+// bytes of copy rows a restore sends at a time
+const COPY_CHUNK: usize = 1 << 20;
+
+/// a statement of a `:dump` script, or a `COPY ... FROM stdin` with the rows that follow it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptStep {
+    Sql(String),
+    // the statement without its `;` and the rows in the text format, the rows of a large table come in several parts
+    Copy { statement: String, rows: String, part: usize },
+}
+
+/// writes the tables of `database` to `path` as a script `:restore` runs again, the number of tables and rows
+/// comes back. a table comes after the ones its foreign keys point at, postgres adds the keys once the rows are in
+pub async fn dump_database(pool: &Box<dyn Pool>, database: &str, path: &Path, store: &Store) -> anyhow::Result<(usize, u64)> {
+    let database_type = pool.database_type();
+    if matches!(database_type, DatabaseType::Mssql) {
+        anyhow::bail!("dump is not supported for {}", database_type)
+    }
+    let database = Database::new(database.to_string(), vec![]);
+    let mut tables = list_tables(pool, &database).await?;
+    if matches!(database_type, DatabaseType::Postgres) {
+        // created and filled with the partitioned table
+        let partitions = pool.query("SELECT n.nspname, c.relname FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace WHERE c.relispartition").await?.rows;
+        tables.retain(|t| !partitions.iter().any(|p| p[0].data == t.pg_schema() && p[1].data == t.name));
+    }
+    let mut references = vec![];
+    for table in &tables {
+        let rows = pool.get_foreign_keys(&database, table).await?;
+        references.push(rows.iter().flat_map(|row| fields(row.as_ref()).remove("ref_table")).collect::<Vec<_>>());
+    }
+    let order = dependency_order(&tables, &references);
+
+    let file = File::create(path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    writeln!(out, "-- {} database {}, written by gobang", database_type, database.name)?;
+    if matches!(database_type, DatabaseType::Postgres) {
+        let mut schemas = tables.iter().map(|t| t.pg_schema()).collect::<Vec<_>>();
+        schemas.sort();
+        schemas.dedup();
+        for schema in schemas {
+            writeln!(out, "CREATE SCHEMA IF NOT EXISTS {};", database_type.quote_identifier(&schema))?;
+        }
+    }
+    let mut foreign_keys = vec![];
+    for &i in &order {
+        let ddl = database_type.show_schema(pool, &database, &tables[i]).await?;
+        writeln!(out)?;
+        match database_type {
+            DatabaseType::Postgres => {
+                let (keys, ddl): (Vec<_>, Vec<_>) = ddl.lines().partition(|l| l.starts_with("ALTER TABLE ") && l.contains(" FOREIGN KEY "));
+                writeln!(out, "{}", ddl.join("\n"))?;
+                foreign_keys.extend(keys.into_iter().map(str::to_string));
+            },
+            _ => writeln!(out, "{};", ddl.trim_end().trim_end_matches(';'))?,
+        }
+    }
+    let mut rows = 0;
+    for &i in &order {
+        rows = dump_rows(pool, &database, &tables[i], &mut out, rows, store).await?;
+    }
+    if !foreign_keys.is_empty() {
+        writeln!(out, "\n{}", foreign_keys.join("\n"))?;
+    }
+    out.flush()?;
+    Ok((tables.len(), rows))
+}
+
+// the rows of one table, `rows` were written before it and the new total comes back
+async fn dump_rows<W: Write>(pool: &Box<dyn Pool>, database: &Database, table: &Table, out: &mut W, mut rows: u64, store: &Store) -> anyhow::Result<u64> {
+    let database_type = pool.database_type();
+    // a script for postgres names the schema, the other ones go into the database they are run against
+    let path = match database_type {
+        DatabaseType::Postgres => database_type.insert_path(database, table),
+        _ => database_type.quote_identifier(&table.name),
+    };
+    let generated = generated_columns(pool, database, table).await?;
+    let mut pages = TablePages::new(pool, database, table, None, None).await?;
+    let mut copying = false;
+    while let Some((headers, page)) = pages.next().await? {
+        let columns = (0..headers.len()).filter(|&i| !generated.contains(&headers[i].name)).collect::<Vec<_>>();
+        let names = columns.iter().map(|&i| database_type.quote_identifier(&headers[i].name)).collect::<Vec<_>>().join(", ");
+        match database_type {
+            DatabaseType::Postgres => {
+                if !copying {
+                    writeln!(out, "\nCOPY {} ({}) FROM stdin;", path, names)?;
+                    copying = true;
+                }
+                let page = page.iter().map(|row| columns.iter().map(|&i| copy_param(&headers[i], &row[i])).collect()).collect::<Vec<_>>();
+                write!(out, "{}", copy_text(&page))?;
+            },
+            _ => {
+                for batch in page.chunks(DUMP_BATCH) {
+                    let values = batch.iter().map(|row| {
+                        format!("({})", columns.iter().map(|&i| sql_literal(&database_type, &headers[i], &row[i])).collect::<Vec<_>>().join(", "))
+                    });
+                    writeln!(out, "\nINSERT INTO {} ({}) VALUES\n{};", path, names, values.collect::<Vec<_>>().join(",\n"))?;
+                }
+            },
+        }
+        rows += page.len() as u64;
+        store.dispatch(Event::DumpProgress(rows)).await?;
+    }
+    if copying {
+        writeln!(out, "\\.")?;
+    }
+    if matches!(database_type, DatabaseType::Postgres) {
+        for setval in sequence_values(pool, &path).await? {
+            writeln!(out, "{}", setval)?;
+        }
+    }
+    Ok(rows)
+}
+
+// columns computed from the others, they take no values
+async fn generated_columns(pool: &Box<dyn Pool>, database: &Database, table: &Table) -> anyhow::Result<Vec<String>> {
+    let database_type = pool.database_type();
+    let literal = |s: &str| database_type.literal(&Param::Text(s.to_string()));
+    let sql = match database_type {
+        DatabaseType::Postgres => format!(
+            "SELECT attname FROM pg_attribute WHERE attrelid = {}::regclass AND attnum > 0 AND attgenerated <> ''",
+            literal(&database_type.insert_path(database, table)),
+        ),
+        DatabaseType::MySql => format!(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = {} AND table_name = {} AND coalesce(generation_expression, '') <> ''",
+            literal(&database.name), literal(&table.name),
+        ),
+        DatabaseType::Sqlite => format!("SELECT name FROM pragma_table_xinfo({}) WHERE hidden IN (2, 3)", literal(&table.name)),
+        DatabaseType::Mssql => return Ok(vec![]),
+    };
+    Ok(pool.query(&sql).await?.rows.iter().filter_map(|row| row.first()).map(|v| v.data.clone()).collect())
+}
+
+// the serial and identity sequences of a postgres table go on from where they were
+async fn sequence_values(pool: &Box<dyn Pool>, table: &str) -> anyhow::Result<Vec<String>> {
+    let rel = pool.database_type().literal(&Param::Text(table.to_string()));
+    let rows = pool.query(&format!(
+        "SELECT format('SELECT setval(pg_get_serial_sequence(%L, %L), %s, %s);', {rel}, a.attname, coalesce(q.last_value, q.start_value), (q.last_value IS NOT NULL)::text)
+        FROM pg_depend d
+        JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S'
+        JOIN pg_namespace n ON n.oid = s.relnamespace
+        JOIN pg_sequences q ON q.schemaname = n.nspname AND q.sequencename = s.relname
+        JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+        WHERE d.classid = 'pg_class'::regclass AND d.refobjid = {rel}::regclass AND d.deptype IN ('a', 'i')
+        ORDER BY a.attnum", rel = rel)).await?.rows;
+    Ok(rows.iter().filter_map(|row| row.first()).map(|v| v.data.clone()).collect())
+}
+
+fn copy_param(header: &Header, value: &Value) -> Param {
+    match &value.datum {
+        // exact, a float would round it
+        Datum::Decimal(d) => Param::Text(d.to_string()),
+        // the grid shows it in utc without the offset
+        Datum::Timestamp(t) => Param::Text(t.to_rfc3339()),
+        _ => Param::new(header, value),
+    }
+}
+
+// the indexes of `tables` with each after the ones it references. a table of the same schema is
+// preferred for a reference, and a cycle is broken where it is found
+fn dependency_order(tables: &[Table], references: &[Vec<String>]) -> Vec<usize> {
+    fn visit(i: usize, edges: &[Vec<usize>], state: &mut [u8], order: &mut Vec<usize>) {
+        if state[i] != 0 {
+            return
+        }
+        state[i] = 1;
+        for &j in &edges[i] {
+            visit(j, edges, state, order);
+        }
+        state[i] = 2;
+        order.push(i);
+    }
+
+    let edges = tables.iter().zip(references.iter()).enumerate().map(|(i, (table, references))| {
+        references.iter().filter_map(|name| {
+            let same_schema = tables.iter().position(|t| t.name == *name && t.schema == table.schema);
+            same_schema.or_else(|| tables.iter().position(|t| t.name == *name))
+        }).filter(|&j| j != i).collect::<Vec<_>>()
+    }).collect::<Vec<_>>();
+    let mut state = vec![0; tables.len()];
+    let mut order = Vec::with_capacity(tables.len());
+    for i in 0..tables.len() {
+        visit(i, &edges, &mut state, &mut order);
+    }
+    order
+}
+
+/// runs a script of `:dump` against `pool` a statement at a time as it is read, the first error stops it.
+/// the number of statements comes back
+pub async fn restore_database(pool: &Box<dyn Pool>, path: &Path, store: &Store) -> anyhow::Result<usize> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    let size = file.metadata()?.len().max(1);
+    let mut script = ScriptReader::new(&pool.database_type(), BufReader::new(file))?;
+    let mut statements = 0;
+    while let Some(step) = script.next_step().map_err(|e| anyhow::anyhow!("statement {}: {}", statements + 1, e))? {
+        let result = match &step {
+            ScriptStep::Sql(sql) => pool.execute(sql).await.map(|_| ()),
+            ScriptStep::Copy { statement, rows, .. } => pool.copy_in(statement, rows).await.map(|_| ()),
+        };
+        if !matches!(step, ScriptStep::Copy { part, .. } if part > 0) {
+            statements += 1;
+        }
+        result.map_err(|e| anyhow::anyhow!("statement {}: {}", statements, e))?;
+        store.dispatch(Event::RestoreProgress(statements, (script.read * 100 / size).min(100) as u8)).await?;
+    }
+    Ok(statements)
+}
+
+/// reads the steps of a script as they come, only the statement or the part of the copy rows being read is held
+struct ScriptReader<R> {
+    tokenizer: Tokenizer,
+    copy_regex: Regex,
+    reader: R,
+    buffer: String, // the lines after the last step
+    copy: Option<(String, usize)>, // the copy whose rows come next, and the parts sent so far
+    read: u64, // bytes
+}
+
+impl<R: BufRead> ScriptReader<R> {
+    fn new(database_type: &DatabaseType, reader: R) -> anyhow::Result<Self> {
+        Ok(Self {
+            tokenizer: Tokenizer::try_from(database_type.clone())?,
+            copy_regex: Regex::new(r"(?is)^COPY\s.*\sFROM\s+stdin$")?,
+            reader,
+            buffer: String::new(),
+            copy: None,
+            read: 0,
+        })
+    }
+
+    fn next_step(&mut self) -> anyhow::Result<Option<ScriptStep>> {
+        if self.copy.is_some() {
+            return self.copy_rows().map(Some)
+        }
+        // a statement ends on a line with a `;`, the buffer is split again only then
+        let mut ended = true;
+        loop {
+            if ended {
+                if let (statement, Some(end)) = self.tokenizer.next_statement(&self.buffer) {
+                    let sql = statement.map(|r| self.buffer[r].to_string());
+                    self.buffer.drain(..end);
+                    match sql {
+                        Some(sql) if self.copy_regex.is_match(&sql) => {
+                            // the rows start on the next line
+                            let line = self.buffer.find('\n').map_or(self.buffer.len(), |i| i + 1);
+                            if !self.buffer[..line].trim().is_empty() {
+                                anyhow::bail!("the rows of `{}` do not start on a line of their own", sql)
+                            }
+                            self.buffer.drain(..line);
+                            self.copy = Some((sql, 0));
+                            return self.copy_rows().map(Some)
+                        },
+                        Some(sql) => return Ok(Some(ScriptStep::Sql(sql))),
+                        None => continue,
+                    }
+                }
+            }
+            let start = self.buffer.len();
+            let n = self.reader.read_line(&mut self.buffer)?;
+            if n == 0 {
+                // the last statement needs no `;`
+                let sql = self.tokenizer.next_statement(&self.buffer).0.map(|r| self.buffer[r].to_string());
+                self.buffer.clear();
+                return Ok(sql.map(ScriptStep::Sql))
+            }
+            self.read += n as u64;
+            ended = self.buffer[start..].contains(';');
+        }
+    }
+
+    // the rows of the copy up to the `\.` line, or the next part of them
+    fn copy_rows(&mut self) -> anyhow::Result<ScriptStep> {
+        let (statement, part) = self.copy.take().unwrap_or_default();
+        let mut rows = String::new();
+        loop {
+            let start = rows.len();
+            let n = self.reader.read_line(&mut rows)?;
+            if n == 0 {
+                anyhow::bail!("the rows of `{}` have no end", statement)
+            }
+            self.read += n as u64;
+            // a backslash in the rows is escaped, so a line can only start with `\.` at the end
+            if rows[start..].starts_with("\\.") {
+                rows.truncate(start);
+                return Ok(ScriptStep::Copy { statement, rows, part })
+            }
+            if rows.len() >= COPY_CHUNK {
+                self.copy = Some((statement.clone(), part + 1));
+                return Ok(ScriptStep::Copy { statement, rows, part })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dependency_order, DatabaseType, ScriptReader, ScriptStep, COPY_CHUNK};
+    use database_tree::Table;
+
+    #[test]
+    fn test_dependency_order() {
+        let table = |name: &str| Table { name: name.to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let tables = ["comments", "posts", "tags", "users"].iter().map(|n| table(n)).collect::<Vec<_>>();
+        let references = [
+            vec!["posts".to_string(), "users".to_string()],
+            vec!["users".to_string(), "posts".to_string()], // a parent post
+            vec!["missing".to_string()],
+            vec!["comments".to_string()], // a cycle
+        ].to_vec();
+        let order = dependency_order(&tables, &references);
+        let names = order.iter().map(|&i| tables[i].name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["users", "posts", "comments", "tags"]);
+    }
+
+    fn script_steps(script: &str) -> anyhow::Result<Vec<ScriptStep>> {
+        // a small buffer, so statements run over the end of what was read so far
+        let mut script = ScriptReader::new(&DatabaseType::Postgres, std::io::BufReader::with_capacity(4, script.as_bytes()))?;
+        let mut steps = vec![];
+        while let Some(step) = script.next_step()? {
+            steps.push(step);
+        }
+        Ok(steps)
+    }
+
+    #[test]
+    fn test_script_steps() {
+        let copy = |statement: &str, rows: &str, part| ScriptStep::Copy { statement: statement.to_string(), rows: rows.to_string(), part };
+        let script = "-- dump\nCREATE TABLE t (a text);\n\nCOPY \"public\".\"t\" (\"a\") FROM stdin;\nx;y\n\\\\.\n\\N\n\\.\nSELECT setval('s', 1, true);\n\nCOPY t (a) FROM stdin;\n\\.\nALTER TABLE t ADD CONSTRAINT c CHECK (a <> ';\n'); SELECT 1;\nSELECT 2 -- no end\n";
+        let steps = script_steps(script).unwrap();
+        assert_eq!(steps, [
+            ScriptStep::Sql("CREATE TABLE t (a text)".to_string()),
+            copy("COPY \"public\".\"t\" (\"a\") FROM stdin", "x;y\n\\\\.\n\\N\n", 0),
+            ScriptStep::Sql("SELECT setval('s', 1, true)".to_string()),
+            copy("COPY t (a) FROM stdin", "", 0),
+            ScriptStep::Sql("ALTER TABLE t ADD CONSTRAINT c CHECK (a <> ';\n')".to_string()),
+            ScriptStep::Sql("SELECT 1".to_string()),
+            ScriptStep::Sql("SELECT 2".to_string()),
+        ]);
+        assert!(script_steps("COPY t (a) FROM stdin;\nx\n").is_err());
+
+        // the rows of a large copy are sent in parts
+        let line = format!("{}\n", "x".repeat(999));
+        let steps = script_steps(&format!("COPY t (a) FROM stdin;\n{}\\.\n", line.repeat(1500))).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0], copy("COPY t (a) FROM stdin", &line.repeat(COPY_CHUNK / line.len() + 1), 0));
+        assert_eq!(steps[1], copy("COPY t (a) FROM stdin", &line.repeat(1500 - COPY_CHUNK / line.len() - 1), 1));
+    }
+}
@@ -0,0 +1,365 @@
+use super::{Datum, Header, Page, Param, Pool, RowSink, Value};
+use crate::config::DatabaseType;
+use crate::event::{Event, Store};
+use database_tree::{Database, Table};
+use serde_json::Value as Json;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// the file formats of `:export`, `:import` reads csv, tsv, json and ndjson
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Markdown,
+    Sql,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "tsv" => Ok(ExportFormat::Tsv),
+            "json" => Ok(ExportFormat::Json),
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "sql" => Ok(ExportFormat::Sql),
+            _ => anyhow::bail!("unknown export format {}, one of csv, tsv, json, ndjson, markdown, sql", s),
+        }
+    }
+}
+
+/// what `:export` writes out, all of it and not just the loaded rows
+#[derive(Debug, Clone)]
+pub enum ExportSource {
+    Table { database: Database, table: Table, orderby: Option<String> },
+    Query(String), // runs again
+}
+
+impl ExportSource {
+    // the table the sql inserts go into, a query has none so it is named after the file
+    fn insert_path(&self, database_type: &DatabaseType, path: &Path) -> String {
+        match self {
+            ExportSource::Table { database, table, .. } => database_type.insert_path(database, table),
+            ExportSource::Query(_) => {
+                let name = path.file_stem().map_or("export".to_string(), |s| s.to_string_lossy().to_string());
+                database_type.quote_identifier(&name)
+            },
+        }
+    }
+}
+
+// `<format> <path>` of `:export` and `:import`, the path may contain spaces and start with `~`
+pub fn export_args(args: &str) -> anyhow::Result<(ExportFormat, PathBuf)> {
+    let (format, path) = args.trim().split_once(' ').map(|(f, p)| (f, p.trim())).unwrap_or((args.trim(), ""));
+    if path.is_empty() {
+        anyhow::bail!("a format and a path are needed, like csv ~/users.csv")
+    }
+    Ok((format.parse()?, expand_path(path)))
+}
+
+// `~/` is the home directory
+pub fn expand_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs_next::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// the query runs again for the export, so it must not change anything
+pub fn is_read_only(query: &str) -> bool {
+    let word = query.trim_start().split(|c: char| !c.is_alphanumeric()).next().unwrap_or("").to_lowercase();
+    matches!(word.as_str(), "select" | "with" | "values" | "table" | "show" | "explain" | "pragma" | "describe" | "desc")
+}
+
+// write the whole source to `path`, the number of rows comes back
+pub async fn export_rows(
+    pool: &Box<dyn Pool>,
+    source: &ExportSource,
+    format: ExportFormat,
+    path: &Path,
+    filter: Option<String>,
+    limit: usize,
+    store: &Store,
+) -> anyhow::Result<u64> {
+    let database_type = pool.database_type();
+    let table = source.insert_path(&database_type, path);
+    let file = File::create(path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    let mut exporter = Exporter::new(BufWriter::new(file), format, database_type, table);
+    match source {
+        ExportSource::Table { database, table, orderby } => {
+            export_table(pool, database, table, orderby.clone(), filter, &mut exporter, store).await?;
+            exporter.finish()
+        },
+        ExportSource::Query(query) => {
+            let mut sink = RowSink::export(limit, exporter, store.clone());
+            pool.stream(std::slice::from_ref(query), &mut sink).await?;
+            sink.finish()
+        },
+    }
+}
+
+// a page at a time, filtered and sorted like the grid
+async fn export_table<W: Write>(
+    pool: &Box<dyn Pool>,
+    database: &Database,
+    table: &Table,
+    orderby: Option<String>,
+    filter: Option<String>,
+    exporter: &mut Exporter<W>,
+    store: &Store,
+) -> anyhow::Result<()> {
+    let mut pages = TablePages::new(pool, database, table, orderby, filter).await?;
+    while let Some((headers, rows)) = pages.next().await? {
+        exporter.write(&headers, &rows)?;
+        store.dispatch(Event::ExportProgress(exporter.rows)).await?;
+    }
+    // an empty table still gets its header
+    if !exporter.started {
+        exporter.write(&pool.get_headers(database, table).await?, &[])?;
+    }
+    Ok(())
+}
+
+/// the rows of a table a page at a time, after the key of the last row read when it has one
+pub(super) struct TablePages<'a> {
+    pool: &'a Box<dyn Pool>,
+    database: &'a Database,
+    table: &'a Table,
+    filter: Option<String>,
+    page: Page,
+}
+
+impl<'a> TablePages<'a> {
+    pub(super) async fn new(pool: &'a Box<dyn Pool>, database: &'a Database, table: &'a Table, orderby: Option<String>, filter: Option<String>) -> anyhow::Result<TablePages<'a>> {
+        let key = pool.database_type().primary_key_columns(pool, database, table).await?;
+        Ok(Self { pool, database, table, filter, page: Page::new(orderby, key) })
+    }
+
+    // none once every row is read
+    pub(super) async fn next(&mut self) -> anyhow::Result<Option<(Vec<Header>, Vec<Vec<Value>>)>> {
+        let (headers, rows) = self.pool.get_records(self.database, self.table, &self.page, self.filter.clone()).await?;
+        let last = match rows.last() {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+        if let Some(keyset) = self.page.keyset.as_mut() {
+            let after = keyset.columns().into_iter()
+                .map(|column| headers.iter().position(|h| h.name == *column).map(|i| Param::new(&headers[i], &last[i])))
+                .collect::<Option<Vec<_>>>();
+            keyset.after = after;
+        }
+        self.page.offset += rows.len() as u64;
+        Ok(Some((headers, rows)))
+    }
+}
+
+/// writes rows as they come, each format keeps NULL apart from empty text and binary values whole
+pub struct Exporter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+    database_type: DatabaseType,
+    table: String, // quoted, for the sql inserts
+    started: bool,
+    pub rows: u64,
+}
+
+impl<W: Write> Exporter<W> {
+    pub fn new(writer: W, format: ExportFormat, database_type: DatabaseType, table: String) -> Self {
+        Self { writer, format, database_type, table, started: false, rows: 0 }
+    }
+
+    // the header goes out with the first batch
+    fn start(&mut self, headers: &[Header]) -> anyhow::Result<()> {
+        if self.started {
+            return Ok(())
+        }
+        self.started = true;
+        let names = headers.iter().map(|h| h.name.as_str());
+        match self.format {
+            ExportFormat::Csv => writeln!(self.writer, "{}", names.map(csv_field).collect::<Vec<_>>().join(","))?,
+            ExportFormat::Tsv => writeln!(self.writer, "{}", names.map(tsv_field).collect::<Vec<_>>().join("\t"))?,
+            ExportFormat::Json => write!(self.writer, "[")?,
+            ExportFormat::Markdown => {
+                writeln!(self.writer, "| {} |", names.map(markdown_cell).collect::<Vec<_>>().join(" | "))?;
+                writeln!(self.writer, "|{}|", vec![" --- "; headers.len()].join("|"))?;
+            },
+            ExportFormat::Ndjson | ExportFormat::Sql => {},
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, headers: &[Header], rows: &[Vec<Value>]) -> anyhow::Result<()> {
+        self.start(headers)?;
+        for row in rows {
+            match self.format {
+                ExportFormat::Csv => {
+                    // an unquoted empty field is NULL, empty text is quoted
+                    let fields = row.iter().map(|v| if v.is_null() { String::new() } else { csv_field(&text(v)) });
+                    writeln!(self.writer, "{}", fields.collect::<Vec<_>>().join(","))?;
+                },
+                ExportFormat::Tsv => {
+                    let fields = row.iter().map(|v| if v.is_null() { "\\N".to_string() } else { tsv_field(&text(v)) });
+                    writeln!(self.writer, "{}", fields.collect::<Vec<_>>().join("\t"))?;
+                },
+                ExportFormat::Json => {
+                    write!(self.writer, "{}\n  {}", if self.rows == 0 { "" } else { "," }, json_object(headers, row))?;
+                },
+                ExportFormat::Ndjson => writeln!(self.writer, "{}", json_object(headers, row))?,
+                ExportFormat::Markdown => {
+                    let cells = row.iter().map(|v| if v.is_null() { "*NULL*".to_string() } else { markdown_cell(&v.data) });
+                    writeln!(self.writer, "| {} |", cells.collect::<Vec<_>>().join(" | "))?;
+                },
+                ExportFormat::Sql => {
+                    let columns = headers.iter().map(|h| self.database_type.quote_identifier(&h.name)).collect::<Vec<_>>();
+                    let values = headers.iter().zip(row.iter()).map(|(h, v)| sql_literal(&self.database_type, h, v)).collect::<Vec<_>>();
+                    writeln!(self.writer, "INSERT INTO {} ({}) VALUES ({});", self.table, columns.join(", "), values.join(", "))?;
+                },
+            }
+            self.rows += 1;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<u64> {
+        self.start(&[])?;
+        if self.format == ExportFormat::Json {
+            writeln!(self.writer, "{}]", if self.rows == 0 { "" } else { "\n" })?;
+        }
+        self.writer.flush()?;
+        Ok(self.rows)
+    }
+}
+
+// the full text of a cell, the grid cuts long binary values short
+fn text(value: &Value) -> String {
+    match &value.datum {
+        Datum::Bytes(bytes) => format!("\\x{}", hex(bytes)),
+        _ => value.data.clone(),
+    }
+}
+
+pub(super) fn sql_literal(database_type: &DatabaseType, header: &Header, value: &Value) -> String {
+    match &value.datum {
+        // exact, a float would round it
+        Datum::Decimal(d) => d.to_string(),
+        _ => database_type.literal(&Param::new(header, value)),
+    }
+}
+
+pub(super) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn csv_field(s: &str) -> String {
+    if s.is_empty() || s.contains([',', '"', '\n', '\r']) || s.starts_with(' ') || s.ends_with(' ') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// the text format of postgres `COPY` and mysql `LOAD DATA`
+pub(super) fn tsv_field(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+// the keys in column order, a join may repeat a name
+fn json_object(headers: &[Header], row: &[Value]) -> String {
+    let fields = headers.iter().zip(row.iter()).map(|(h, v)| format!("{}:{}", Json::String(h.name.clone()), json(&v.datum)));
+    format!("{{{}}}", fields.collect::<Vec<_>>().join(","))
+}
+
+fn json(datum: &Datum) -> Json {
+    match datum {
+        Datum::Null => Json::Null,
+        Datum::Int(v) => (*v).into(),
+        Datum::UInt(v) => (*v).into(),
+        Datum::Float(v) => serde_json::Number::from_f64(*v).map_or_else(|| Json::String(v.to_string()), Json::Number),
+        Datum::Bool(v) => (*v).into(),
+        Datum::Json(v) => v.clone(),
+        Datum::Array(v) => Json::Array(v.iter().map(json).collect()),
+        Datum::Bytes(v) => Json::String(format!("\\x{}", hex(v))),
+        // decimals stay exact as text
+        d => Json::String(d.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_args, is_read_only, ExportFormat, Exporter};
+    use crate::config::DatabaseType;
+    use crate::database::{ColType, Datum, Header, Value};
+    use rust_decimal::Decimal;
+    use std::path::PathBuf;
+
+    fn export(format: ExportFormat, headers: &[Header], rows: &[Vec<Value>]) -> String {
+        let mut buffer = vec![];
+        let mut exporter = Exporter::new(&mut buffer, format, DatabaseType::Postgres, r#""public"."users""#.to_string());
+        // a batch at a time, the drivers send the headers even without rows
+        exporter.write(headers, &[]).unwrap();
+        for row in rows {
+            exporter.write(headers, std::slice::from_ref(row)).unwrap();
+        }
+        assert_eq!(exporter.finish().unwrap(), rows.len() as u64);
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let headers = vec![
+            Header::new("id".to_string(), ColType::Int),
+            Header::new("name".to_string(), ColType::VarChar),
+            Header::new("data".to_string(), ColType::Unknown),
+        ];
+        let rows = vec![
+            vec![Datum::Int(1).into(), Value::new("a, \"b\"\nc|d".to_string()), Datum::Bytes(vec![0xde, 0xad]).into()],
+            vec![Datum::Decimal(Decimal::new(1050, 2)).into(), Value::new(String::new()), Value::default()],
+        ];
+        assert_eq!(export(ExportFormat::Csv, &headers, &rows), "id,name,data\n1,\"a, \"\"b\"\"\nc|d\",\\xdead\n10.50,\"\",\n");
+        assert_eq!(export(ExportFormat::Tsv, &headers, &rows), "id\tname\tdata\n1\ta, \"b\"\\nc|d\t\\\\xdead\n10.50\t\t\\N\n");
+        assert_eq!(
+            export(ExportFormat::Ndjson, &headers, &rows),
+            "{\"id\":1,\"name\":\"a, \\\"b\\\"\\nc|d\",\"data\":\"\\\\xdead\"}\n{\"id\":\"10.50\",\"name\":\"\",\"data\":null}\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Markdown, &headers, &rows),
+            "| id | name | data |\n| --- | --- | --- |\n| 1 | a, \"b\"<br>c\\|d | \\xdead |\n| 10.50 |  | *NULL* |\n"
+        );
+        assert_eq!(export(ExportFormat::Sql, &headers, &rows), concat!(
+            "INSERT INTO \"public\".\"users\" (\"id\", \"name\", \"data\") VALUES (1, 'a, \"b\"\nc|d', '\\xdead'::bytea);\n",
+            "INSERT INTO \"public\".\"users\" (\"id\", \"name\", \"data\") VALUES (10.50, '', NULL);\n",
+        ));
+    }
+
+    #[test]
+    fn test_export_json() {
+        let headers = vec![Header::new("v".to_string(), ColType::Json), Header::new("v".to_string(), ColType::Float)];
+        let rows = vec![vec![Datum::Json(serde_json::json!({"a": [1]})).into(), Datum::Float(f64::NAN).into()]];
+        assert_eq!(export(ExportFormat::Json, &headers, &rows), "[\n  {\"v\":{\"a\":[1]},\"v\":\"NaN\"}\n]\n");
+        assert_eq!(export(ExportFormat::Json, &headers, &[]), "[]\n");
+        assert_eq!(export(ExportFormat::Csv, &headers, &[]), "v,v\n");
+    }
+
+    #[test]
+    fn test_export_args() {
+        let (format, path) = export_args("csv /tmp/my users.csv").unwrap();
+        assert_eq!((format, path), (ExportFormat::Csv, PathBuf::from("/tmp/my users.csv")));
+        assert_eq!(export_args(" md out.md ").unwrap().0, ExportFormat::Markdown);
+        assert!(export_args("csv").is_err());
+        assert!(export_args("xml out.xml").is_err());
+        assert!(is_read_only("  select 1"));
+        assert!(is_read_only("WITH t AS (SELECT 1) SELECT * FROM t"));
+        assert!(!is_read_only("insert into t values (1) returning id"));
+    }
+}
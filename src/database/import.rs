@@ -0,0 +1,607 @@
+use super::export::{hex, tsv_field};
+use super::{ExportFormat, Param, Pool, Statement, SQLITE_ROWID};
+use crate::config::DatabaseType;
+use crate::event::{Event, Store};
+use database_tree::{Database, Table};
+use serde_json::Value as Json;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+// rows per transaction, a failed batch leaves the ones before it loaded
+const IMPORT_BATCH: usize = 1000;
+
+/// what `:import` does with a row whose key is already in the table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Conflict {
+    Fail,
+    Skip,
+    Upsert,
+}
+
+impl Conflict {
+    pub fn next(self) -> Self {
+        match self {
+            Conflict::Fail => Conflict::Skip,
+            Conflict::Skip => Conflict::Upsert,
+            Conflict::Upsert => Conflict::Fail,
+        }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Fail => write!(f, "fail"),
+            Conflict::Skip => write!(f, "skip"),
+            Conflict::Upsert => write!(f, "upsert"),
+        }
+    }
+}
+
+/// the header and rows of a file, NULL apart from empty text the way `:export` writes them
+#[derive(Debug, Clone, Default)]
+pub struct ImportFile {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Param>>,
+}
+
+/// a table column and the field of the file it is loaded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportColumn {
+    pub name: String,
+    pub col_type: String, // declared, a `\x` hex field goes into a binary column as bytes
+    pub field: usize,
+}
+
+/// a file `:import` loads into a table, with the columns mapped
+#[derive(Debug, Clone)]
+pub struct ImportRequest {
+    pub database: Database,
+    pub table: Table,
+    pub format: ExportFormat,
+    pub path: PathBuf,
+    pub columns: Vec<ImportColumn>,
+    pub conflict: Conflict,
+}
+
+/// the table side of an import, what `Pool::import` needs for each batch
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub table: String, // quoted
+    pub columns: Vec<String>,
+    pub key: Vec<String>, // the conflict target of an upsert
+    pub conflict: Conflict,
+}
+
+impl Import {
+    // the same sql for every row
+    pub fn insert(&self, database_type: &DatabaseType, row: Vec<Param>) -> Statement {
+        let mut statement = Statement::default();
+        let values = row.into_iter().map(|p| statement.bind(database_type, p, None)).collect::<Vec<_>>().join(", ");
+        let verb = match (database_type, self.conflict) {
+            (DatabaseType::MySql, Conflict::Skip) => "INSERT IGNORE",
+            (DatabaseType::MySql, Conflict::Upsert) if self.updates().next().is_none() => "INSERT IGNORE",
+            _ => "INSERT",
+        };
+        statement.sql = format!("{} INTO {} ({}) VALUES ({}){}", verb, self.table, self.column_list(database_type), values, self.on_conflict(database_type));
+        statement
+    }
+
+    pub fn column_list(&self, database_type: &DatabaseType) -> String {
+        self.columns.iter().map(|c| database_type.quote_identifier(c)).collect::<Vec<_>>().join(", ")
+    }
+
+    // the clause after the values, empty when a duplicate fails the batch
+    pub fn on_conflict(&self, database_type: &DatabaseType) -> String {
+        let updates = self.updates().collect::<Vec<_>>();
+        match (database_type, self.conflict) {
+            (_, Conflict::Fail) => String::new(),
+            (DatabaseType::MySql, Conflict::Skip) => String::new(),
+            (DatabaseType::MySql, Conflict::Upsert) if updates.is_empty() => String::new(),
+            (DatabaseType::MySql, Conflict::Upsert) => {
+                let set = updates.iter().map(|c| {
+                    let c = database_type.quote_identifier(c);
+                    format!("{} = VALUES({})", c, c)
+                });
+                format!(" ON DUPLICATE KEY UPDATE {}", set.collect::<Vec<_>>().join(", "))
+            },
+            (_, Conflict::Skip) => " ON CONFLICT DO NOTHING".to_string(),
+            (_, Conflict::Upsert) => {
+                let key = self.key.iter().map(|c| database_type.quote_identifier(c)).collect::<Vec<_>>().join(", ");
+                if updates.is_empty() {
+                    return format!(" ON CONFLICT ({}) DO NOTHING", key)
+                }
+                let set = updates.iter().map(|c| {
+                    let c = database_type.quote_identifier(c);
+                    format!("{} = excluded.{}", c, c)
+                });
+                format!(" ON CONFLICT ({}) DO UPDATE SET {}", key, set.collect::<Vec<_>>().join(", "))
+            },
+        }
+    }
+
+    // an upsert overwrites the columns of the file that are not part of the key
+    fn updates(&self) -> impl Iterator<Item = &String> {
+        self.columns.iter().filter(move |c| !self.key.contains(c))
+    }
+}
+
+// load the whole file in batches, the rows read and the rows the table took come back
+pub async fn import_rows(pool: &Box<dyn Pool>, request: &ImportRequest, store: &Store) -> anyhow::Result<(u64, u64)> {
+    let database_type = pool.database_type();
+    if !matches!(database_type, DatabaseType::Postgres | DatabaseType::MySql | DatabaseType::Sqlite) {
+        anyhow::bail!("import is not supported for {}", database_type)
+    }
+    if request.columns.is_empty() {
+        anyhow::bail!("no column of the file is mapped to the table")
+    }
+    let columns = request.columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+    let key = database_type.primary_key_columns(pool, &request.database, &request.table).await?
+        .into_iter()
+        .filter(|c| c != SQLITE_ROWID)
+        .collect::<Vec<_>>();
+    // mysql finds the duplicate by any unique key itself
+    if request.conflict == Conflict::Upsert && !matches!(database_type, DatabaseType::MySql) {
+        if key.is_empty() {
+            anyhow::bail!("{} has no primary key to upsert on", request.table.name)
+        }
+        if let Some(missing) = key.iter().find(|k| !columns.contains(k)) {
+            anyhow::bail!("map the key column {} to upsert", missing)
+        }
+    }
+    let import = Import {
+        table: database_type.insert_path(&request.database, &request.table),
+        columns,
+        key,
+        conflict: request.conflict,
+    };
+    let mut file = FileReader::open(request.format, &request.path)?;
+    let (mut read, mut written) = (0, 0);
+    loop {
+        let batch = file.next_rows(IMPORT_BATCH)?;
+        if file.columns.is_empty() {
+            anyhow::bail!("{} has no columns", request.path.display())
+        }
+        if batch.is_empty() {
+            break
+        }
+        // a json key first seen after the earlier rows leaves them short
+        let rows = batch.iter()
+            .map(|row| request.columns.iter().map(|c| column_param(row.get(c.field).unwrap_or(&Param::Null), &c.col_type)).collect())
+            .collect();
+        written += pool.import(&import, rows).await
+            .map_err(|e| anyhow::anyhow!("{} rows imported, the next batch failed: {}", read, e))?;
+        read += batch.len() as u64;
+        store.dispatch(Event::ImportProgress(read)).await?;
+    }
+    Ok((read, written))
+}
+
+// `:export` writes binary values as `\x` hex text
+fn column_param(param: &Param, col_type: &str) -> Param {
+    let col_type = col_type.to_lowercase();
+    let binary = ["blob", "binary", "bytea"].iter().any(|t| col_type.contains(t));
+    match param {
+        Param::Text(text) if binary => text.strip_prefix("\\x")
+            .and_then(unhex)
+            .map_or_else(|| param.clone(), Param::Bytes),
+        _ => param.clone(),
+    }
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes().chunks(2)
+        .map(|b| std::str::from_utf8(b).ok().filter(|b| b.len() == 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+// the rows of a postgres `COPY ... FROM STDIN` in text format
+pub fn copy_text(rows: &[Vec<Param>]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let fields = row.iter().map(|p| match p {
+            Param::Null => "\\N".to_string(),
+            Param::Int(v) => v.to_string(),
+            Param::Float(v) => v.to_string(),
+            Param::Bool(v) => (if *v { "t" } else { "f" }).to_string(),
+            Param::Text(v) => tsv_field(v),
+            Param::Bytes(v) => tsv_field(&format!("\\x{}", hex(v))),
+        });
+        text.push_str(&fields.collect::<Vec<_>>().join("\t"));
+        text.push('\n');
+    }
+    text
+}
+
+// the header and up to `limit` rows, the preview of the import popup reads only the first ones
+pub fn read_file(format: ExportFormat, path: &Path, limit: usize) -> anyhow::Result<ImportFile> {
+    let mut file = FileReader::open(format, path)?;
+    let rows = file.next_rows(limit)?;
+    if file.columns.is_empty() {
+        anyhow::bail!("{} has no columns", path.display())
+    }
+    Ok(ImportFile { columns: file.columns, rows })
+}
+
+/// reads the rows of a file a batch at a time, so an import never holds more than one batch
+struct FileReader<R> {
+    format: ExportFormat,
+    reader: R,
+    columns: Vec<String>,
+    read: usize, // rows before the next batch
+    first: bool, // the header, the byte order mark or the opening bracket of a json array is still ahead
+    done: bool, // the closing bracket of a json array was read
+}
+
+impl FileReader<BufReader<File>> {
+    fn open(format: ExportFormat, path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        Self::new(format, BufReader::new(file))
+    }
+}
+
+impl<R: BufRead> FileReader<R> {
+    fn new(format: ExportFormat, reader: R) -> anyhow::Result<Self> {
+        if !matches!(format, ExportFormat::Csv | ExportFormat::Tsv | ExportFormat::Json | ExportFormat::Ndjson) {
+            anyhow::bail!("only csv, tsv, json and ndjson files can be imported")
+        }
+        Ok(Self { format, reader, columns: vec![], read: 0, first: true, done: false })
+    }
+
+    // up to `limit` rows, none once the file is read to the end
+    fn next_rows(&mut self, limit: usize) -> anyhow::Result<Vec<Vec<Param>>> {
+        let mut rows = vec![];
+        while rows.len() < limit {
+            if self.format == ExportFormat::Json {
+                match self.json_element()? {
+                    Some(value) => push_object(&mut self.columns, &mut rows, self.read, value)?,
+                    None => break,
+                }
+                continue
+            }
+            let line = match next_line(&mut self.reader) {
+                Some(line) => line?,
+                None => break,
+            };
+            let line = if self.first { line.trim_start_matches('\u{feff}').to_string() } else { line };
+            if line.trim().is_empty() {
+                continue
+            }
+            match self.format {
+                ExportFormat::Csv | ExportFormat::Tsv => {
+                    let row = if self.format == ExportFormat::Csv {
+                        let reader = &mut self.reader;
+                        csv_record(line, &mut std::iter::from_fn(|| next_line(reader)))?
+                    } else {
+                        tsv_record(&line)
+                    };
+                    if self.first {
+                        self.columns = row.into_iter().map(|p| match p {
+                            Param::Text(name) => name,
+                            _ => String::new(),
+                        }).collect();
+                    } else if row.len() != self.columns.len() {
+                        anyhow::bail!("row {} has {} fields, the header has {}", self.read + rows.len() + 1, row.len(), self.columns.len())
+                    } else {
+                        rows.push(row);
+                    }
+                },
+                _ => push_object(&mut self.columns, &mut rows, self.read, serde_json::from_str(&line)?)?,
+            }
+            self.first = false;
+        }
+        self.read += rows.len();
+        Ok(rows)
+    }
+
+    // the next element of the array, its bytes are collected up to the comma or bracket that ends it
+    fn json_element(&mut self) -> anyhow::Result<Option<Json>> {
+        if self.done {
+            return Ok(None)
+        }
+        if self.first {
+            let mut bytes = vec![];
+            while let Some(byte) = next_byte(&mut self.reader)? {
+                bytes.push(byte);
+                if !byte.is_ascii_whitespace() {
+                    break
+                }
+            }
+            if bytes.last() != Some(&b'[') {
+                anyhow::bail!("a json file holds an array of objects")
+            }
+            self.first = false;
+        }
+        let mut element = vec![];
+        let (mut depth, mut in_string, mut escaped) = (0, false, false);
+        loop {
+            let byte = match next_byte(&mut self.reader)? {
+                Some(byte) => byte,
+                None => anyhow::bail!("the json array is not closed"),
+            };
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if byte == b'\\' {
+                    escaped = true;
+                } else if byte == b'"' {
+                    in_string = false;
+                }
+            } else {
+                match byte {
+                    b'"' => in_string = true,
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' if depth > 0 => depth -= 1,
+                    b',' | b']' if depth == 0 => {
+                        self.done = byte == b']';
+                        if self.done && element.iter().all(u8::is_ascii_whitespace) {
+                            return Ok(None)
+                        }
+                        return Ok(Some(serde_json::from_slice(&element)?))
+                    },
+                    _ => {},
+                }
+            }
+            element.push(byte);
+        }
+    }
+}
+
+// a line without its line break, as `BufRead::lines` gives it
+fn next_line<R: BufRead>(reader: &mut R) -> Option<std::io::Result<String>> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => None,
+        Ok(_) => {
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            Some(Ok(line))
+        },
+        Err(e) => Some(Err(e)),
+    }
+}
+
+fn next_byte<R: BufRead>(reader: &mut R) -> std::io::Result<Option<u8>> {
+    let byte = reader.fill_buf()?.first().copied();
+    if byte.is_some() {
+        reader.consume(1);
+    }
+    Ok(byte)
+}
+
+// the keys of every object become the columns, a key missing from a row is NULL.
+// `read` rows came in the batches before
+fn push_object(columns: &mut Vec<String>, rows: &mut Vec<Vec<Param>>, read: usize, value: Json) -> anyhow::Result<()> {
+    let object = match value {
+        Json::Object(object) => object,
+        _ => anyhow::bail!("row {} is not a json object", read + rows.len() + 1),
+    };
+    let mut row = vec![Param::Null; columns.len()];
+    for (key, value) in object {
+        let index = match columns.iter().position(|c| *c == key) {
+            Some(index) => index,
+            None => {
+                columns.push(key);
+                rows.iter_mut().for_each(|r| r.push(Param::Null));
+                row.push(Param::Null);
+                columns.len() - 1
+            },
+        };
+        row[index] = json_param(value);
+    }
+    rows.push(row);
+    Ok(())
+}
+
+fn json_param(value: Json) -> Param {
+    match value {
+        Json::Null => Param::Null,
+        Json::Bool(v) => Param::Bool(v),
+        Json::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(v), _) => Param::Int(v),
+            // a u64 too large for i64 stays exact
+            _ if n.is_u64() => Param::Text(n.to_string()),
+            (_, Some(v)) => Param::Float(v),
+            _ => Param::Text(n.to_string()),
+        },
+        Json::String(v) => Param::Text(v),
+        v => Param::Text(v.to_string()),
+    }
+}
+
+// a quoted field may go on over the next lines
+fn csv_record<I: Iterator<Item = std::io::Result<String>>>(mut line: String, lines: &mut I) -> anyhow::Result<Vec<Param>> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    loop {
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if c != '"' {
+                    field.push(c);
+                } else if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            } else if c == '"' && field.is_empty() && !quoted {
+                quoted = true;
+                in_quotes = true;
+            } else if c == ',' {
+                fields.push(csv_param(std::mem::take(&mut field), quoted));
+                quoted = false;
+            } else {
+                field.push(c);
+            }
+        }
+        if !in_quotes {
+            break
+        }
+        field.push('\n');
+        line = match lines.next() {
+            Some(line) => line?,
+            None => anyhow::bail!("a quoted csv field is not closed"),
+        };
+    }
+    fields.push(csv_param(field, quoted));
+    Ok(fields)
+}
+
+// an unquoted empty field is NULL
+fn csv_param(field: String, quoted: bool) -> Param {
+    if field.is_empty() && !quoted {
+        Param::Null
+    } else {
+        Param::Text(field)
+    }
+}
+
+fn tsv_record(line: &str) -> Vec<Param> {
+    line.split('\t').map(|field| {
+        if field == "\\N" {
+            return Param::Null
+        }
+        let mut text = String::with_capacity(field.len());
+        let mut chars = field.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue
+            }
+            match chars.next() {
+                Some('t') => text.push('\t'),
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some(c) => text.push(c),
+                None => text.push('\\'),
+            }
+        }
+        Param::Text(text)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{column_param, copy_text, Conflict, FileReader, Import, ImportFile};
+    use crate::config::DatabaseType;
+    use crate::database::{ExportFormat, Param};
+
+    fn text(s: &str) -> Param {
+        Param::Text(s.to_string())
+    }
+
+    fn read_rows(format: ExportFormat, bytes: &[u8], limit: usize) -> anyhow::Result<ImportFile> {
+        let mut file = FileReader::new(format, bytes)?;
+        let rows = file.next_rows(limit)?;
+        Ok(ImportFile { columns: file.columns, rows })
+    }
+
+    #[test]
+    fn test_read_rows() {
+        let csv = "\u{feff}id,name\n1,\"a, \"\"b\"\"\nc\"\n2,\"\"\n\n3,\n";
+        let file = read_rows(ExportFormat::Csv, csv.as_bytes(), usize::MAX).unwrap();
+        assert_eq!(file.columns, vec!["id", "name"]);
+        assert_eq!(file.rows, vec![
+            vec![text("1"), text("a, \"b\"\nc")],
+            vec![text("2"), text("")],
+            vec![text("3"), Param::Null],
+        ]);
+        assert_eq!(read_rows(ExportFormat::Csv, csv.as_bytes(), 1).unwrap().rows.len(), 1);
+        assert!(read_rows(ExportFormat::Csv, "a,b\n1\n".as_bytes(), usize::MAX).is_err());
+        assert!(read_rows(ExportFormat::Csv, "a\n\"open\n".as_bytes(), usize::MAX).is_err());
+
+        let file = read_rows(ExportFormat::Tsv, "id\tname\n1\ta\\tb\\\\x\n2\t\\N\n".as_bytes(), usize::MAX).unwrap();
+        assert_eq!(file.rows, vec![vec![text("1"), text("a\tb\\x")], vec![text("2"), Param::Null]]);
+
+        let ndjson = "{\"id\":1,\"ok\":true}\n{\"id\":2.5,\"tags\":[1],\"name\":null}\n";
+        let file = read_rows(ExportFormat::Ndjson, ndjson.as_bytes(), usize::MAX).unwrap();
+        assert_eq!(file.columns, vec!["id", "ok", "name", "tags"]);
+        assert_eq!(file.rows, vec![
+            vec![Param::Int(1), Param::Bool(true), Param::Null, Param::Null],
+            vec![Param::Float(2.5), Param::Null, Param::Null, text("[1]")],
+        ]);
+        let file = read_rows(ExportFormat::Json, "[{\"a\":\"x\"},{\"a\":18446744073709551615}]".as_bytes(), usize::MAX).unwrap();
+        assert_eq!(file.rows, vec![vec![text("x")], vec![text("18446744073709551615")]]);
+        assert!(read_rows(ExportFormat::Json, "[1]".as_bytes(), usize::MAX).is_err());
+        assert!(read_rows(ExportFormat::Markdown, "| a |".as_bytes(), usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_read_batches() {
+        // a tiny buffer, so fields and elements run over the end of what was read so far
+        let batches = |format, bytes: &'static str| {
+            let mut file = FileReader::new(format, std::io::BufReader::with_capacity(3, bytes.as_bytes())).unwrap();
+            let mut batches = vec![];
+            loop {
+                let rows = file.next_rows(2).unwrap();
+                if rows.is_empty() {
+                    break
+                }
+                batches.push(rows);
+            }
+            (file.columns, batches)
+        };
+        let (columns, rows) = batches(ExportFormat::Csv, "id,note\r\n1,\"x\r\ny\"\r\n2,\n3,\"a\"\"\n\nb\"\r\n");
+        assert_eq!(columns, vec!["id", "note"]);
+        assert_eq!(rows, vec![
+            vec![vec![text("1"), text("x\ny")], vec![text("2"), Param::Null]],
+            vec![vec![text("3"), text("a\"\n\nb")]],
+        ]);
+
+        let (columns, rows) = batches(ExportFormat::Json, " [{\"a\": \"],\\\"{\"}, {\"a\": [1, {\"b\": 2}]},\n{\"c\": 3}] ");
+        assert_eq!(columns, vec!["a", "c"]);
+        assert_eq!(rows, vec![
+            vec![vec![text("],\"{")], vec![text("[1,{\"b\":2}]")]],
+            vec![vec![Param::Null, Param::Int(3)]],
+        ]);
+        assert_eq!(batches(ExportFormat::Json, "[]").1.len(), 0);
+        assert!(read_rows(ExportFormat::Json, "[{\"a\": 1}".as_bytes(), usize::MAX).is_err());
+        assert!(read_rows(ExportFormat::Json, "{\"a\": 1}".as_bytes(), usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_import_statements() {
+        let import = |database_type: &DatabaseType, conflict| Import {
+            table: database_type.quote_identifier("users"),
+            columns: vec!["id".to_string(), "name".to_string()],
+            key: vec!["id".to_string()],
+            conflict,
+        };
+        let row = || vec![Param::Int(1), text("a")];
+        let sql = |database_type: DatabaseType, conflict| import(&database_type, conflict).insert(&database_type, row()).sql;
+        assert_eq!(sql(DatabaseType::Sqlite, Conflict::Fail), "INSERT INTO `users` (`id`, `name`) VALUES (?, ?)");
+        assert_eq!(sql(DatabaseType::Sqlite, Conflict::Skip), "INSERT INTO `users` (`id`, `name`) VALUES (?, ?) ON CONFLICT DO NOTHING");
+        assert_eq!(
+            sql(DatabaseType::Sqlite, Conflict::Upsert),
+            "INSERT INTO `users` (`id`, `name`) VALUES (?, ?) ON CONFLICT (`id`) DO UPDATE SET `name` = excluded.`name`"
+        );
+        assert_eq!(sql(DatabaseType::MySql, Conflict::Skip), "INSERT IGNORE INTO `users` (`id`, `name`) VALUES (?, ?)");
+        assert_eq!(
+            sql(DatabaseType::MySql, Conflict::Upsert),
+            "INSERT INTO `users` (`id`, `name`) VALUES (?, ?) ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"
+        );
+        let only_key = Import { columns: vec!["id".to_string()], ..import(&DatabaseType::Postgres, Conflict::Upsert) };
+        assert_eq!(only_key.on_conflict(&DatabaseType::Postgres), " ON CONFLICT (\"id\") DO NOTHING");
+    }
+
+    #[test]
+    fn test_copy_text() {
+        let rows = vec![
+            vec![Param::Int(1), text("a\tb\\"), Param::Null],
+            vec![Param::Bool(false), Param::Float(1.5), Param::Bytes(vec![0xde, 0xad])],
+        ];
+        assert_eq!(copy_text(&rows), "1\ta\\tb\\\\\t\\N\nf\t1.5\t\\\\xdead\n");
+        assert_eq!(column_param(&text("\\xdead"), "BLOB"), Param::Bytes(vec![0xde, 0xad]));
+        assert_eq!(column_param(&text("\\xdead"), "text"), text("\\xdead"));
+        assert_eq!(column_param(&text("\\xno"), "bytea"), text("\\xno"));
+    }
+}
@@ -1,6 +1,8 @@
 use std::string::ToString;
 use std::fmt;
 use std::convert::{From};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
 use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, PartialEq, Debug)]
@@ -63,16 +65,76 @@ impl From<&str> for Header {
     }
 }
 
+// longer blobs show their size instead of the hex
+const BYTES_SHOWN: usize = 256;
+
+/// a cell as the driver decoded it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Datum {
+    Null,
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Decimal(Decimal),
+    Bool(bool),
+    Text(String),
+    Bytes(Vec<u8>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    DateTime(NaiveDateTime),
+    Timestamp(DateTime<Utc>), // with time zone
+    Json(serde_json::Value),
+    Array(Vec<Datum>),
+}
+
+// how a cell is shown, exports and edits can go by the datum instead
+impl fmt::Display for Datum {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Datum::Null => write!(f, "NULL"),
+            Datum::Int(v) => write!(f, "{}", v),
+            Datum::UInt(v) => write!(f, "{}", v),
+            Datum::Float(v) => write!(f, "{}", v),
+            Datum::Decimal(v) => write!(f, "{}", v),
+            Datum::Bool(v) => write!(f, "{}", v),
+            Datum::Text(v) => write!(f, "{}", v),
+            Datum::Bytes(v) if v.len() > BYTES_SHOWN => write!(f, "<{} bytes>", v.len()),
+            Datum::Bytes(v) => write!(f, "\\x{}", v.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+            Datum::Date(v) => write!(f, "{}", v),
+            Datum::Time(v) => write!(f, "{}", v),
+            Datum::DateTime(v) => write!(f, "{}", v),
+            Datum::Timestamp(v) => write!(f, "{}", v.naive_utc()),
+            Datum::Json(v) => write!(f, "{}", v),
+            // postgres array literal
+            Datum::Array(v) => write!(f, "{{{}}}", v.iter().map(|d| match d {
+                Datum::Null => "NULL".to_string(),
+                Datum::Text(_) | Datum::Json(_) | Datum::Date(_) | Datum::Time(_) | Datum::DateTime(_) | Datum::Timestamp(_) => {
+                    let s = d.to_string();
+                    if s.is_empty() || s.eq_ignore_ascii_case("NULL") || s.contains(|c: char| "{},\"\\".contains(c) || c.is_whitespace()) {
+                        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+                    } else { s }
+                },
+                d => d.to_string(),
+            }).collect::<Vec<_>>().join(",")),
+        }
+    }
+}
+
 // for cell value
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Value {
-    pub data: String,
-    pub is_null: bool,
+    pub data: String, // display form of the datum
+    pub datum: Datum,
 }
 
 impl Value {
     pub fn new(v: String) -> Self {
-        Self { data: v, is_null: false }
+        Self { datum: Datum::Text(v.clone()), data: v }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.datum == Datum::Null
     }
 
     pub fn width(&self) -> usize {
@@ -88,10 +150,10 @@ impl Value {
     }
 }
 
-impl Clone for Value {
+impl From<Datum> for Value {
 
-    fn clone(&self) -> Self {
-        Self { data: self.data.clone(), is_null: self.is_null }
+    fn from(datum: Datum) -> Self {
+        Self { data: datum.to_string(), datum }
     }
 }
 
@@ -112,7 +174,7 @@ impl<S> From<S> for Value where S: AsRef<str> {
 impl<S> PartialEq<S> for Value where S: AsRef<str> {
 
     fn eq(&self, other: &S) -> bool {
-        self.is_null == false && self.data == other.as_ref()
+        !self.is_null() && self.data == other.as_ref()
     }
 }
 
@@ -133,39 +195,38 @@ impl fmt::Display for Value {
 
 impl Default for Value {
     fn default() -> Self {
-        Self { data: "NULL".to_string(), is_null: true }
+        Datum::Null.into()
     }
 }
 
 
 
-pub struct ColumnConstraint {
-	pub(crate) definition: String,
-	pub(crate) name:       String,
-}
-
-
+// the declared columns of a table
 pub struct ColumnMeta {
     pub(crate) name: String,
     pub(crate) data_type: String,
-    pub(crate) length: i32,
-    pub(crate) nullable: bool,
-    pub(crate) default: Option<String>,
-    pub(crate) is_auto_increment: bool,
-    pub(crate) identity_generation: Option<String>,
-    pub(crate) check: Option<ColumnConstraint>,
-}
-
-impl ColumnMeta {
-
-    pub fn get_data_type(&self) -> String {
-        match self.data_type.as_ref() {
-            "smallint" => if self.is_auto_increment { "smallserial".to_string() } else { self.data_type.clone() },
-            "integer" => if self.is_auto_increment { "serial".to_string() } else { self.data_type.clone() },
-            "bigint" => if self.is_auto_increment { "bigserial".to_string() } else { self.data_type.clone() },
-            "timestamp without time zone" => return "timestamp".to_string(),
-            "time without time zone" => "time".to_string(),
-            _ => self.data_type.clone()
-        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datum_display() {
+        let value: Value = Datum::Bytes(vec![0xde, 0xad, 0x01]).into();
+        assert_eq!(value.data, "\\xdead01");
+        assert_eq!(Value::from(Datum::Bytes(vec![0; 1000])).data, "<1000 bytes>");
+        let date = NaiveDate::from_ymd(2022, 1, 2).and_hms(3, 4, 5);
+        assert_eq!(Value::from(Datum::Timestamp(DateTime::from_utc(date, Utc))).data, "2022-01-02 03:04:05");
+        assert_eq!(Value::from(Datum::Json(serde_json::json!({"a": [1]}))).data, r#"{"a":[1]}"#);
+        assert_eq!(Value::from(Datum::Decimal(Decimal::new(1050, 2))).data, "10.50");
+        let array = Datum::Array(vec![Datum::Int(1), Datum::Null, Datum::Text("a \"b\"".to_string()), Datum::Text("null".to_string())]);
+        assert_eq!(Value::from(array).data, r#"{1,NULL,"a \"b\"","null"}"#);
+
+        let null = Value::default();
+        assert!(null.is_null());
+        assert_eq!(null.data, "NULL");
+        assert!(null != "NULL");
+        assert!(!Value::new("NULL".to_string()).is_null());
     }
-}
\ No newline at end of file
+}
@@ -3,26 +3,45 @@ pub mod postgres;
 pub mod sqlite;
 pub mod mssql;
 pub mod meta;
+pub mod statement;
+pub mod page;
+pub mod stream;
+pub mod plan;
+pub mod diff;
+pub mod export;
+pub mod import;
+pub mod dump;
+pub mod yank;
+mod ddl;
 
 pub use mysql::MySqlPool;
 pub use postgres::PostgresPool;
 pub use sqlite::SqlitePool;
 pub use mssql::MssqlPool;
-pub use meta::{ColType, Header, Value, ColumnMeta, ColumnConstraint};
+pub use meta::{ColType, Header, Value, Datum, ColumnMeta};
+pub use statement::{Param, RowKeys, Statement};
+pub use page::{Keyset, Page};
+pub use stream::{statement_error, RowBatch, RowSink};
+pub use plan::PlanNode;
+pub use diff::{compare, load_schema, Comparison};
+pub use export::{expand_path, export_args, export_rows, is_read_only, ExportFormat, ExportSource, Exporter};
+pub use import::{import_rows, read_file, Conflict, Import, ImportColumn, ImportFile, ImportRequest};
+pub use dump::{dump_database, restore_database};
+pub use yank::{query_table, yank, yank_table, YankFormat};
 
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use async_trait::async_trait;
-use database_tree::{Child, Database, Table};
+use database_tree::{Child, Database, Object, Table};
 use crate::config::DatabaseType;
 use sqlparse::Trie;
 
 // pub const RECORDS_LIMIT_PER_PAGE: u8 = 200;
-pub const MYSQL_KEYWORDS: [&str; 1] = ["int"];
-pub const POSTGRES_KEYWORDS: [&str; 1] = ["group"];
 const INDENT: &str = "    ";
+pub const SQLITE_ROWID: &str = "rowid";
 
 // TODO: memo
+
 fn init_trie () -> Trie<ColType> {
     let mut trie = Trie::default();
     let ints = ["int", "smallint", "bigint", "mediumint", "tinyint", "integer"];
@@ -39,6 +58,32 @@ fn init_trie () -> Trie<ColType> {
 #[async_trait]
 pub trait Pool: Send + Sync {
     async fn execute(&self, query: &str) -> anyhow::Result<ExecuteResult>;
+    // `execute` for the sql editor, runs the statements of a script in order and hands each
+    // result to `sink`, rows a batch at a time. drivers use one connection so session state carries over
+    async fn stream(&self, queries: &[String], sink: &mut RowSink) -> anyhow::Result<()> {
+        for (i, query) in queries.iter().enumerate() {
+            sink.start(i, i + 1 == queries.len());
+            match self.execute(query).await.map_err(|e| statement_error(e, i, queries.len()))? {
+                ExecuteResult::Read { headers, rows, .. } => {
+                    sink.send(&headers, rows, false).await?;
+                },
+                ExecuteResult::Write { updated_rows } => sink.affected(updated_rows).await?,
+            }
+        }
+        Ok(())
+    }
+    async fn execute_statement(&self, statement: &Statement) -> anyhow::Result<u64>;
+    async fn execute_transaction(&self, statements: &[Statement]) -> anyhow::Result<u64>;
+    // a batch of `:import` in one transaction. every row has the same sql, so the driver prepares it once
+    async fn import(&self, import: &Import, rows: Vec<Vec<Param>>) -> anyhow::Result<u64> {
+        let database_type = self.database_type();
+        let statements = rows.into_iter().map(|row| import.insert(&database_type, row)).collect::<Vec<_>>();
+        self.execute_transaction(&statements).await
+    }
+    // a `COPY ... FROM STDIN` block of a `:restore` script, the rows in the text format
+    async fn copy_in(&self, _statement: &str, _rows: &str) -> anyhow::Result<u64> {
+        anyhow::bail!("copy is not supported for {}", self.database_type())
+    }
     async fn query(&self, query: &str) -> anyhow::Result<QueryResult>;
     async fn get_databases(&self) -> anyhow::Result<Vec<Database>>;
     async fn get_tables(&self, database: String) -> anyhow::Result<Vec<Child>>;
@@ -46,9 +91,8 @@ pub trait Pool: Send + Sync {
         &self,
         database: &Database,
         table: &Table,
-        page: u16,
+        page: &Page,
         filter: Option<String>,
-        orderby: Option<String>,
     ) -> anyhow::Result<(Vec<Header>, Vec<Vec<Value>>)>;
     async fn get_columns(
         &self,
@@ -101,11 +145,23 @@ pub trait Pool: Send + Sync {
         database: &Database,
         table: &Table,
     ) -> anyhow::Result<Vec<Box<dyn TableRow>>>;
+    // the `CREATE` statement of a view, routine, trigger or sequence
+    async fn get_definition(&self, _database: &Database, object: &Object) -> anyhow::Result<String> {
+        anyhow::bail!("no definition of {} {} for {}", object.kind.label(), object.name, self.database_type())
+    }
+    // the plan of `query`, `analyze` runs it to measure each step
+    async fn explain(&self, _query: &str, _analyze: bool) -> anyhow::Result<PlanNode> {
+        anyhow::bail!("explain is not supported for {}", self.database_type())
+    }
     async fn close(&self);
+    // stop the query running in `execute` or `get_records`, false when there is none
+    async fn cancel(&self) -> anyhow::Result<bool>;
 
     fn database_type(&self) -> DatabaseType;
 }
 
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // short lived, a read is the common case
 pub enum ExecuteResult {
     Read {
         headers: Vec<Header>,
@@ -118,6 +174,34 @@ pub enum ExecuteResult {
     },
 }
 
+// keeps the backend id of the connection a query runs on, so `Pool::cancel` can reach it
+pub struct Running<'a, T: Copy> {
+    slot: &'a Mutex<Option<T>>,
+    id: T,
+}
+
+impl<'a, T: Copy> Running<'a, T> {
+    pub fn new(slot: &'a Mutex<Option<T>>, id: T) -> Self {
+        *slot.lock().unwrap() = Some(id);
+        Self { slot, id }
+    }
+
+    // a streamed query waiting for more rows to be asked for is not running
+    pub fn pause(&self) {
+        *self.slot.lock().unwrap() = None;
+    }
+
+    pub fn resume(&self) {
+        *self.slot.lock().unwrap() = Some(self.id);
+    }
+}
+
+impl<T: Copy> Drop for Running<'_, T> {
+    fn drop(&mut self) {
+        *self.slot.lock().unwrap() = None;
+    }
+}
+
 pub struct QueryResult {
     pub headers: Vec<Header>,
     pub rows: Vec<Vec<Value>>,
@@ -151,91 +235,11 @@ impl DatabaseType {
                 let result = pool.query(&sql).await?;
                 return self.table_ddl(result)
             },
-            DatabaseType::Postgres => {
-                let columns = pool.get_columns2(database, table).await?;
-                let pkey_cols = self.primary_key_columns(pool, database, table).await?;
-                let indexes = self.postgres_index(pool, table).await?;
-                let foreign_defs = self.postgres_foreign(pool, table).await?;
-                let unique_constraints = self.postgres_unique(pool, table).await?;
-                postgres_table_ddl(table, columns, pkey_cols, indexes, foreign_defs, unique_constraints).await
-            },
+            DatabaseType::Postgres => self.postgres_ddl(pool, table).await,
             _ => unimplemented!(),
         }
     }
 
-    async fn postgres_index(&self, pool: &Box<dyn Pool>, table: &Table) -> anyhow::Result<Vec<String>> {
-        let sql = format!(r#"WITH
-        unique_and_pk_constraints AS (
-          SELECT con.conname AS name
-          FROM   pg_constraint con
-          JOIN   pg_namespace nsp ON nsp.oid = con.connamespace
-          JOIN   pg_class cls ON cls.oid = con.conrelid
-          WHERE  con.contype IN ('p', 'u')
-          AND    nsp.nspname = '{schema}'
-          AND    cls.relname = '{table}'
-        )
-      SELECT indexName, indexdef
-      FROM   pg_indexes
-      WHERE  schemaname = '{schema}'
-      AND    tablename = '{table}'
-      AND    indexName NOT IN (SELECT name FROM unique_and_pk_constraints)"#, schema=table.pg_schema(), table=table.name);
-      let result = pool.query(&sql).await?;
-      let indexes = result.rows.iter().map(|v| v[1].to_string()).collect::<Vec<_>>();
-      Ok(indexes)
-    }
-
-    async fn postgres_foreign(&self, pool: &Box<dyn Pool>, table: &Table) -> anyhow::Result<Vec<String>> {
-        let sql = format!(r#"SELECT
-        tc.table_schema, tc.constraint_name, tc.table_name, kcu.column_name,
-        ccu.table_schema AS foreign_table_schema,
-        ccu.table_name AS foreign_table_name,
-        ccu.column_name AS foreign_column_name,
-        rc.update_rule AS foreign_update_rule,
-        rc.delete_rule AS foreign_delete_rule
-    FROM
-        information_schema.table_constraints AS tc
-        JOIN information_schema.key_column_usage AS kcu
-            ON tc.constraint_name = kcu.constraint_name
-        JOIN information_schema.constraint_column_usage AS ccu
-            ON tc.constraint_name = ccu.constraint_name
-        JOIN information_schema.referential_constraints AS rc
-            ON tc.constraint_name = rc.constraint_name
-    WHERE constraint_type = 'FOREIGN KEY' AND tc.table_schema='{schema}' AND tc.table_name='{table}'"#, schema=table.pg_schema(), table=table.name);
-        let result = pool.execute(&sql).await?;
-        match result {
-            ExecuteResult::Read{ rows, .. } => {
-              let indexes = rows.iter().map(|v| {
-                    format!("ALTER TABLE ONLY {}.{} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {}.{}({}) ON UPDATE {} ON DELETE {}",
-                        v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7], v[8])
-              }).collect::<Vec<_>>();
-              Ok(indexes)
-            },
-            _ => Ok(vec![]),
-        }
-    }
-
-    async fn postgres_unique(&self, pool: &Box<dyn Pool>, table: &Table) -> anyhow::Result<HashMap<String, String>> {
-        let sql = format!(r#"SELECT con.conname, pg_get_constraintdef(con.oid)
-            FROM   pg_constraint con
-            JOIN   pg_namespace nsp ON nsp.oid = con.connamespace
-            JOIN   pg_class cls ON cls.oid = con.conrelid
-            WHERE  con.contype = 'u'
-            AND    nsp.nspname = '{}'
-            AND    cls.relname = '{}';"#, table.pg_schema(), table.name);
-        let result = pool.execute(&sql).await?;
-        match result {
-            ExecuteResult::Read{ rows, .. } => {
-                let mut hm = HashMap::new();
-                let _indexes = rows.iter().map(|v| {
-                    let val = format!("ALTER TABLE {} ADD CONSTRAINT {} {};", table.name, v[0], v[1]);
-                    hm.insert(v[0].data.clone(), val)
-                }).collect::<Vec<_>>();
-                Ok(hm)
-            },
-            _ => Ok(HashMap::new()),
-        }
-    }
-
     fn table_ddl(&self, result: QueryResult) -> anyhow::Result<String> {
         match self {
             DatabaseType::MySql | DatabaseType::Sqlite => {
@@ -274,168 +278,160 @@ impl DatabaseType {
                 let cols = result.rows.into_iter().flat_map(|row| row.get(index).filter(|c| c.data == "PRIMARY").map(|_| row.get(index+2).map(|c| c.data.clone())).flatten()).collect();
                 return Ok(cols)
             },
+            DatabaseType::Sqlite => {
+                let name = self.literal(&Param::Text(table.name.clone()));
+                let sql = format!("SELECT name FROM pragma_table_info({}) WHERE pk > 0 ORDER BY pk", name);
+                let result = pool.query(&sql).await?;
+                let cols: Vec<String> = result.rows.into_iter().flat_map(|row| row.into_iter().next().map(|c| c.data)).collect();
+                if !cols.is_empty() {
+                    return Ok(cols)
+                }
+                // a table without a declared key is a rowid table, views have no rowid
+                let sql = format!("SELECT type FROM sqlite_master WHERE name = {}", name);
+                let result = pool.query(&sql).await?;
+                if result.rows.iter().any(|row| row.first().is_some_and(|c| c.data == "table")) {
+                    return Ok(vec![SQLITE_ROWID.to_string()])
+                }
+            },
             _ => {},
         };
         Ok(columns)
     }
 
-    // FIXME: check column type
-    pub fn delete_row_by_column(&self, database: &Database, table: &Table, col: &str, val: &str) -> String {
-        match self {
-            DatabaseType::MySql => format!("delete from `{}`.`{}` where {} = '{}' LIMIT 1", database.name, table.name, col, val),
-            DatabaseType::Sqlite => format!("delete from `{table}` where {col} = (select {col} from `{table}` where {col} = '{val}' LIMIT 1)", table=table.name, col=col, val=val),
-            DatabaseType::Postgres => format!(r#"delete from "{database}"."{schema}"."{table}" where "{col}" = (select "{col}" from "{database}"."{schema}"."{table}" where "{col}" = '{val}' LIMIT 1)"#, database=database.name, schema=table.pg_schema(), table=table.name, col=col, val=val),
-            _ => unimplemented!(),
+    // look up the rowid of each row by matching every column, identical rows get distinct rowids
+    pub async fn sqlite_rowids(&self, pool: &Box<dyn Pool>, database: &Database, table: &Table, rows: &RowKeys, taken: &[Value]) -> anyhow::Result<Vec<Value>> {
+        let path = self.table_path(database, table);
+        let mut rowids: Vec<Value> = vec![];
+        for row in &rows.rows {
+            let mut statement = Statement::default();
+            let keys = RowKeys { headers: rows.headers.clone(), rows: vec![row.clone()], exact: true };
+            let cond = self.where_rows(&mut statement, &keys, &HashMap::new());
+            statement.sql = format!("SELECT {} FROM {} WHERE {}", SQLITE_ROWID, path, cond);
+            let result = pool.query(&self.render(&statement)).await?;
+            let rowid = result.rows.into_iter()
+                .flat_map(|row| row.into_iter().next())
+                .find(|v| !taken.iter().chain(rowids.iter()).any(|r| r.data == v.data))
+                .ok_or_else(|| anyhow::anyhow!("selected row not found in {}", table.name))?;
+            rowids.push(rowid);
         }
+        Ok(rowids)
     }
 
-    // delete multiple rows
-    pub fn delete_rows_by_column(&self, database: &Database, table: &Table, col: &str, val: &[&str]) -> String {
-        let v = format!("'{}'", val.join("','"));
-        let limit = val.len();
+    // postgres does not cast text params implicitly, so bind them as the declared column type
+    pub async fn column_types(&self, pool: &Box<dyn Pool>, database: &Database, table: &Table) -> anyhow::Result<HashMap<String, String>> {
         match self {
-            DatabaseType::MySql => format!("DELETE FROM `{}`.`{}` where {} IN ({}) LIMIT {}", database.name, table.name, col, v, limit),
-            DatabaseType::Sqlite => format!("delete from `{table}` where {col} IN (select {col} from `{table}` where {col} IN ({val}) LIMIT {limit})", table=table.name, col=col, val=v, limit=limit),
-            DatabaseType::Postgres => format!(r#"delete from "{database}"."{schema}"."{table}" where "{col}" IN (select "{col}" from "{database}"."{schema}"."{table}" where "{col}" IN ({val}) LIMIT {limit})"#, database=database.name, schema=table.pg_schema(), table=table.name, col=col, val=v, limit=limit),
-            _ => unimplemented!(),
+            DatabaseType::Postgres => {
+                let columns = pool.get_columns2(database, table).await?;
+                Ok(columns.into_iter().map(|c| (c.name, c.data_type)).collect())
+            },
+            _ => Ok(HashMap::new()),
         }
     }
 
-    // TODO: limit 1
-    pub fn update_row_by_column(&self, database: &Database, table: &Table, pkey: &str, pval: &str, header: &Header, val: &Value) -> String {
-        let mut v = if header.is_no_quote() { val.data.clone() } else { format!("'{}'", val.data) };
-        if val.is_null { v = "NULL".to_string() };
+    fn table_path(&self, database: &Database, table: &Table) -> String {
         match self {
-            DatabaseType::MySql => format!("UPDATE `{database}`.`{table}` SET {col} = {val} where {pkey} = '{pval}'", database=database.name, table=table.name, col=header.name, val=v, pkey=pkey, pval=pval),
-            DatabaseType::Sqlite => format!("UPDATE {table} SET `{col}` = {val} where {pkey} = '{pval}'", table=table.name, col=header.name, val=v, pkey=pkey, pval=pval),
-            DatabaseType::Postgres => format!(r#"UPDATE "{}"."{}"."{}" SET "{}" = {} where "{}" = '{}'"#, database.name, table.pg_schema(), table.name, header.name, v, pkey, pval),
-            _ => unimplemented!(),
+            DatabaseType::MySql => format!("{}.{}", self.quote_identifier(&database.name), self.quote_identifier(&table.name)),
+            DatabaseType::Postgres => format!("{}.{}.{}", self.quote_identifier(&database.name), self.quote_identifier(&table.pg_schema()), self.quote_identifier(&table.name)),
+            _ => self.quote_identifier(&table.name),
         }
     }
 
-
-    // handle null | handle value type
-    pub fn insert_rows(&self, database: &Database, table: &Table, headers: &Vec<Header>, rows: &Vec<Vec<Arc<RwLock<Value>>>>) -> String {
-        let header_str = self.insert_headers(headers);
-        match self {
-            DatabaseType::Postgres => {
-                let mut sqls = vec![];
-                for row in rows {
-                    let row_str = convert_row_str(row, headers);
-                    let sql = format!(r#"INSERT INTO "{}"."{}" ({}) VALUES ({})"#, table.schema.clone().unwrap_or_else(|| "public".to_string()), table.name, header_str, row_str);
-                    sqls.push(sql)
+    // `(a = ? AND b = ?) OR (...)`, one group per row
+    fn where_rows(&self, statement: &mut Statement, keys: &RowKeys, column_types: &HashMap<String, String>) -> String {
+        keys.rows.iter().map(|row| {
+            let cond = keys.headers.iter().zip(row.iter()).filter_map(|(h, v)| {
+                let col = self.quote_identifier(&h.name);
+                let typ = column_types.get(&h.name);
+                if v.is_null() {
+                    return Some(format!("{} IS NULL", col))
                 }
-                sqls.join(";\n")
-            }
-            DatabaseType::MySql => {
-                let mut sqls = vec![];
-                for row in rows {
-                    let row_str = convert_row_str(row, headers);                  
-                    let sql = format!("INSERT INTO {}.{} ({}) VALUES ({})", database.name, table.name, header_str, row_str);
-                    sqls.push(sql)
+                // a row without a key is matched on every column, not all of them compare with `=`
+                match (self, typ.map(String::as_str)) {
+                    // json is shown reformatted, jsonb compares the documents
+                    (DatabaseType::Postgres, Some("json")) if !keys.exact =>
+                        Some(format!("{}::jsonb = {}", col, statement.bind(self, Param::new(h, v), Some(&"jsonb".to_string())))),
+                    (DatabaseType::Postgres, Some("xml")) if !keys.exact =>
+                        Some(format!("{}::text = {}", col, statement.bind(self, Param::new(h, v), None))),
+                    (DatabaseType::Postgres, Some("point" | "line" | "lseg" | "box" | "path" | "polygon" | "circle")) if !keys.exact => None,
+                    // a float read back is rounded and misses the stored value
+                    (DatabaseType::MySql, _) if !keys.exact && h.col_type == ColType::Float => None,
+                    _ => Some(format!("{} = {}", col, statement.bind(self, Param::new(h, v), typ))),
                 }
-                sqls.join(";\n")
-            },
-            DatabaseType::Sqlite => {
-                let mut sqls = vec![];
-                for row in rows {
-                    let row_str = convert_row_str(row, headers);                  
-                    let sql = format!("INSERT INTO {} ({}) VALUES ({})", table.name, header_str, row_str);
-                    sqls.push(sql)
-                }
-                sqls.join(";\n")
-            },
-            _ => unimplemented!(),
-        }
+            }).collect::<Vec<_>>();
+            if cond.is_empty() {
+                return "(1 = 1)".to_string()
+            }
+            format!("({})", cond.join(" AND "))
+        }).collect::<Vec<_>>().join(" OR ")
     }
 
-    fn is_keywords(&self, w: &str) -> bool {
-        match self {
-            DatabaseType::MySql => {
-                MYSQL_KEYWORDS.iter().find(|kw| **kw == w.to_lowercase()).is_some()
-            },
-            DatabaseType::Postgres => {
-                POSTGRES_KEYWORDS.iter().find(|kw| **kw == w.to_lowercase()).is_some()
-            },
-            _ => false,
+    // without a key, duplicate rows match too, so cap the rows touched
+    fn where_clause(&self, path: &str, cond: &str, keys: &RowKeys) -> String {
+        if keys.exact {
+            return format!("WHERE {}", cond)
         }
-    }
-
-    fn insert_headers(&self, headers: &Vec<Header>) -> String {
+        let limit = keys.rows.len();
         match self {
-            DatabaseType::MySql => {
-                headers.iter().map(|h| {
-                    if self.is_keywords(&h.name) { format!("`{}`", h.to_string()) } else { h.to_string() }
-                }).collect::<Vec<String>>().join(", ")
-            },
-            DatabaseType::Postgres => {
-                headers.iter().map(|h| {
-                    if self.is_keywords(&h.name) { format!("\"{}\"", h.to_string()) } else { h.to_string() }
-                }).collect::<Vec<String>>().join(", ")
-            },
-            _ => headers.iter().map(|h| h.to_string()).collect::<Vec<String>>().join(", ")
+            DatabaseType::MySql => format!("WHERE {} LIMIT {}", cond, limit),
+            DatabaseType::Sqlite => format!("WHERE rowid IN (SELECT rowid FROM {} WHERE {} LIMIT {})", path, cond, limit),
+            DatabaseType::Postgres => format!("WHERE ctid IN (SELECT ctid FROM {} WHERE {} LIMIT {})", path, cond, limit),
+            _ => unimplemented!(),
         }
     }
-}
 
-// TODO: getIndexDefs getForeignDefs getPolicyDefs getTableCheckConstraints getUniqueConstraints
-async fn postgres_table_ddl(table: &Table, columns: Vec<ColumnMeta>, pkey_cols: Vec<String>, index_defs: Vec<String>, foreign_defs: Vec<String>, unique_constraints: HashMap<String, String>) -> anyhow::Result<String> {
-    let mut ddl = format!("CREATE TABLE {}.{} (", table.pg_schema(), table.name);
-    for (i, col) in columns.iter().enumerate() {
-        if i > 0 {
-            ddl = format!("{},", ddl);
-        }
-        ddl = format!("{}\n{}", ddl, INDENT);
-        ddl = format!("{} \"{}\" {}", ddl, col.name, col.get_data_type());
-        if col.length > 0 {
-            ddl = format!("{}({})", ddl, col.length);
-        }
-        if !col.nullable {
-            ddl = format!("{} NOT NULL", ddl);
-        }
-        if col.default.is_some() && col.default.as_ref().unwrap().len() > 0 && !col.is_auto_increment {
-            ddl = format!("{} DEFAULT {}", ddl, col.default.as_ref().unwrap());
-        }
-        if col.identity_generation.is_some() && col.identity_generation.as_ref().unwrap().len() > 0{
-            ddl = format!("{} GENERATED {} AS IDENTITY", ddl, col.identity_generation.as_ref().unwrap())
-        }
-        if col.check.is_some() {
-            let check = col.check.as_ref().unwrap();
-            ddl =  format!("{} CONSTRAINT {} {}", ddl, check.name, check.definition);
-        }
-    }
-    if pkey_cols.len() > 0 {
-        ddl = format!("{},\n{}", ddl, INDENT);
-        let pkey = pkey_cols.join("\", \"");
-        ddl = format!("{} PRIMARY KEY (\"{}\")", ddl, pkey);
-    }
-    ddl = format!("{}\n);\n", ddl);
-    for index_def in index_defs {
-        ddl = format!("{}{};\n", ddl, index_def)
+    // delete multiple rows
+    pub fn delete_rows_by_column(&self, database: &Database, table: &Table, keys: &RowKeys, column_types: &HashMap<String, String>) -> Statement {
+        let mut statement = Statement::default();
+        let cond = self.where_rows(&mut statement, keys, column_types);
+        let path = self.table_path(database, table);
+        statement.sql = match self {
+            DatabaseType::MySql | DatabaseType::Sqlite | DatabaseType::Postgres => format!("DELETE FROM {} {}", path, self.where_clause(&path, &cond, keys)),
+            _ => unimplemented!(),
+        };
+        statement.expected_rows = Some(keys.rows.len() as u64);
+        statement
     }
-    for foreign_def in foreign_defs {
-        ddl = format!("{}{};\n", ddl, foreign_def)
+
+    // update one cell of the first row in `keys`
+    pub fn update_row_by_column(&self, database: &Database, table: &Table, keys: &RowKeys, header: &Header, val: &Value, column_types: &HashMap<String, String>) -> Statement {
+        let mut statement = Statement::default();
+        let v = statement.bind(self, Param::new(header, val), column_types.get(&header.name));
+        let keys = RowKeys { rows: keys.rows.iter().take(1).cloned().collect(), ..keys.clone() };
+        let cond = self.where_rows(&mut statement, &keys, column_types);
+        let path = self.table_path(database, table);
+        statement.sql = match self {
+            DatabaseType::MySql | DatabaseType::Sqlite | DatabaseType::Postgres => format!("UPDATE {} SET {} = {} {}",
+                path, self.quote_identifier(&header.name), v, self.where_clause(&path, &cond, &keys)),
+            _ => unimplemented!(),
+        };
+        statement.expected_rows = Some(1);
+        statement
     }
-    for (_, constraint_def) in unique_constraints {
-        ddl = format!("{}{};\n", ddl, constraint_def)
+
+    fn insert_path(&self, database: &Database, table: &Table) -> String {
+        match self {
+            DatabaseType::Postgres => format!("{}.{}", self.quote_identifier(&table.pg_schema()), self.quote_identifier(&table.name)),
+            DatabaseType::MySql | DatabaseType::Sqlite => self.table_path(database, table),
+            _ => unimplemented!(),
+        }
     }
-    Ok(ddl.trim_end().to_string())
-}
 
-fn convert_row_str (row: &Vec<Arc<RwLock<Value>>>, headers: &Vec<Header>) -> String {
-    let mut row_str = String::new();
-    for (i, v) in row.iter().enumerate() {
-        let v = v.read().unwrap();
-        let s = if v.is_null { "NULL".to_string() } else {
-            let mut s = format!("'{}'", v.data);
-            if let Some(header) = headers.get(i) {
-                if header.is_no_quote() { s = v.data.clone() };
+    pub fn insert_rows(&self, database: &Database, table: &Table, headers: &[Header], rows: &[Vec<Arc<RwLock<Value>>>], column_types: &HashMap<String, String>) -> Vec<Statement> {
+        let path = self.insert_path(database, table);
+        let header_str = headers.iter().map(|h| self.quote_identifier(&h.name)).collect::<Vec<_>>().join(", ");
+        rows.iter().map(|row| {
+            let mut statement = Statement::default();
+            let values = row.iter().zip(headers.iter())
+                .map(|(v, h)| statement.bind(self, Param::new(h, &v.read().unwrap()), column_types.get(&h.name)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            statement.sql = match self {
+                DatabaseType::Postgres | DatabaseType::Sqlite if headers.is_empty() => format!("INSERT INTO {} DEFAULT VALUES", path),
+                _ => format!("INSERT INTO {} ({}) VALUES ({})", path, header_str, values),
             };
-            s
-        };
-        row_str = if row_str.len() == 0 { s.to_string() } else { format!("{}, {}", row_str, s) };
-    };
-    row_str
+            statement
+        }).collect()
+    }
 }
 
 #[macro_export]
@@ -443,6 +439,9 @@ macro_rules! get_or_null {
     ($value:expr) => {
         $value.map_or(Value::default(), |v| Value::new(v.to_string()))
     };
+    ($value:expr, $datum:path) => {
+        $value.map_or(Value::default(), |v| Value::from($datum(v.into())))
+    };
 }
 
 
@@ -460,4 +459,124 @@ mod tests {
         let typ = trie.find("timestamp with time zone", false);
         assert_eq!(typ, Some(ColType::Date));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_update_row_by_column() {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "users".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let keys = RowKeys {
+            headers: vec![Header::new("id".to_string(), ColType::Int)],
+            rows: vec![vec![Value::new("1".to_string())]],
+            exact: true,
+        };
+        let header = Header::new("name".to_string(), ColType::VarChar);
+        let val = Value::new("O'Brien".to_string());
+        let statement = DatabaseType::MySql.update_row_by_column(&database, &table, &keys, &header, &val, &HashMap::new());
+        assert_eq!(statement.sql, "UPDATE `db`.`users` SET `name` = ? WHERE (`id` = ?)");
+        assert_eq!(statement.params, vec![Param::Text("O'Brien".to_string()), Param::Int(1)]);
+
+        let mut column_types = HashMap::new();
+        column_types.insert("id".to_string(), "integer".to_string());
+        let statement = DatabaseType::Postgres.update_row_by_column(&database, &table, &keys, &header, &val, &column_types);
+        assert_eq!(statement.sql, r#"UPDATE "db"."public"."users" SET "name" = $1 WHERE ("id" = CAST($2 AS integer))"#);
+    }
+
+    #[test]
+    fn test_insert_rows_cast() {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "events".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let headers = vec![Header::new("at".to_string(), ColType::Date), Header::new("note".to_string(), ColType::VarChar)];
+        let row = vec![Arc::new(RwLock::new(Value::new("2024-01-02".to_string()))), Arc::new(RwLock::new(Value::new("x".to_string())))];
+        let mut column_types = HashMap::new();
+        column_types.insert("at".to_string(), "date".to_string());
+        let statements = DatabaseType::Postgres.insert_rows(&database, &table, &headers, &[row], &column_types);
+        assert_eq!(statements[0].sql, r#"INSERT INTO "public"."events" ("at", "note") VALUES (CAST($1 AS date), $2)"#);
+        assert_eq!(statements[0].params, vec![Param::Text("2024-01-02".to_string()), Param::Text("x".to_string())]);
+    }
+
+    #[test]
+    fn test_update_row_by_column_composite_key() {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "members".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let keys = RowKeys {
+            headers: vec![Header::new("group_id".to_string(), ColType::Int), Header::new("user_id".to_string(), ColType::Int)],
+            rows: vec![vec![Value::new("1".to_string()), Value::new("2".to_string())]],
+            exact: true,
+        };
+        let header = Header::new("role".to_string(), ColType::VarChar);
+        let statement = DatabaseType::MySql.update_row_by_column(&database, &table, &keys, &header, &Value::default(), &HashMap::new());
+        assert_eq!(statement.sql, "UPDATE `db`.`members` SET `role` = ? WHERE (`group_id` = ? AND `user_id` = ?)");
+        assert_eq!(statement.params, vec![Param::Null, Param::Int(1), Param::Int(2)]);
+    }
+
+    #[test]
+    fn test_update_row_by_column_without_key() {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "logs".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let keys = RowKeys {
+            headers: vec![Header::new("level".to_string(), ColType::VarChar), Header::new("message".to_string(), ColType::VarChar)],
+            rows: vec![vec![Value::new("warn".to_string()), Value::default()]],
+            exact: false,
+        };
+        let header = Header::new("message".to_string(), ColType::VarChar);
+        let val = Value::new("disk full".to_string());
+        let statement = DatabaseType::Sqlite.update_row_by_column(&database, &table, &keys, &header, &val, &HashMap::new());
+        assert_eq!(statement.sql, "UPDATE `logs` SET `message` = ? WHERE rowid IN (SELECT rowid FROM `logs` WHERE (`level` = ? AND `message` IS NULL) LIMIT 1)");
+        assert_eq!(statement.params, vec![Param::Text("disk full".to_string()), Param::Text("warn".to_string())]);
+        let statement = DatabaseType::MySql.update_row_by_column(&database, &table, &keys, &header, &val, &HashMap::new());
+        assert_eq!(statement.sql, "UPDATE `db`.`logs` SET `message` = ? WHERE (`level` = ? AND `message` IS NULL) LIMIT 1");
+    }
+
+    #[test]
+    fn test_delete_rows_without_key() {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "events".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let keys = RowKeys {
+            headers: vec![
+                Header::new("doc".to_string(), ColType::Json),
+                Header::new("at".to_string(), ColType::VarChar),
+                Header::new("score".to_string(), ColType::Float),
+            ],
+            rows: vec![vec![Value::new(r#"{"a": 1}"#.to_string()), Value::new("(1,2)".to_string()), Value::new("0.1".to_string())]],
+            exact: false,
+        };
+        let mut column_types = HashMap::new();
+        column_types.insert("doc".to_string(), "json".to_string());
+        column_types.insert("at".to_string(), "point".to_string());
+        column_types.insert("score".to_string(), "real".to_string());
+        let statement = DatabaseType::Postgres.delete_rows_by_column(&database, &table, &keys, &column_types);
+        assert_eq!(
+            statement.sql,
+            r#"DELETE FROM "db"."public"."events" WHERE ctid IN (SELECT ctid FROM "db"."public"."events" WHERE ("doc"::jsonb = CAST($1 AS jsonb) AND "score" = CAST($2 AS real)) LIMIT 1)"#
+        );
+        assert_eq!(statement.params, vec![Param::Text(r#"{"a": 1}"#.to_string()), Param::Float(0.1)]);
+
+        let statement = DatabaseType::MySql.delete_rows_by_column(&database, &table, &keys, &HashMap::new());
+        assert_eq!(statement.sql, "DELETE FROM `db`.`events` WHERE (`doc` = ? AND `at` = ?) LIMIT 1");
+        // nothing left to compare still matches a single row
+        let keys = RowKeys { headers: keys.headers[2..].to_vec(), rows: vec![keys.rows[0][2..].to_vec()], exact: false };
+        let statement = DatabaseType::MySql.delete_rows_by_column(&database, &table, &keys, &HashMap::new());
+        assert_eq!(statement.sql, "DELETE FROM `db`.`events` WHERE (1 = 1) LIMIT 1");
+    }
+
+    #[test]
+    fn test_delete_rows_by_column() {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "members".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        let keys = RowKeys {
+            headers: vec![Header::new("group_id".to_string(), ColType::Int), Header::new("user_id".to_string(), ColType::Int)],
+            rows: vec![
+                vec![Value::new("1".to_string()), Value::new("2".to_string())],
+                vec![Value::new("1".to_string()), Value::new("3".to_string())],
+            ],
+            exact: true,
+        };
+        let statement = DatabaseType::Sqlite.delete_rows_by_column(&database, &table, &keys, &HashMap::new());
+        assert_eq!(statement.sql, "DELETE FROM `members` WHERE (`group_id` = ? AND `user_id` = ?) OR (`group_id` = ? AND `user_id` = ?)");
+        assert_eq!(statement.params, vec![Param::Int(1), Param::Int(2), Param::Int(1), Param::Int(3)]);
+
+        let keys = RowKeys { exact: false, ..keys };
+        let statement = DatabaseType::Postgres.delete_rows_by_column(&database, &table, &keys, &HashMap::new());
+        assert_eq!(statement.sql, r#"DELETE FROM "db"."public"."members" WHERE ctid IN (SELECT ctid FROM "db"."public"."members" WHERE ("group_id" = $1 AND "user_id" = $2) OR ("group_id" = $3 AND "user_id" = $4) LIMIT 2)"#);
+    }
+}
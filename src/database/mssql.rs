@@ -5,7 +5,7 @@ use async_trait::async_trait;
 use database_tree::{Child, Database, Table, Schema};
 use futures::TryStreamExt;
 use itertools::Itertools;
-use super::{ExecuteResult, QueryResult, Pool, TableRow, ColType, Header, Value};
+use super::{ExecuteResult, QueryResult, Pool, TableRow, Statement, Param, Page, ColType, Header, Value, Datum};
 use crate::get_or_null;
 use crate::config::DatabaseType;
 
@@ -181,12 +181,15 @@ impl Pool for MssqlPool {
                 database: Database {
                     name: "-".to_string(),
                     children: Vec::new(),
+                    loaded: true,
                 },
                 table: Table {
                     name: "-".to_string(),
                     create_time: None,
                     update_time: None,
                     engine: None,
+                    rows: None,
+                    size: None,
                     schema: None,
                 },
             });
@@ -198,6 +201,24 @@ impl Pool for MssqlPool {
         })
     }
 
+    async fn execute_statement(&self, statement: &Statement) -> anyhow::Result<u64> {
+        let result = bind_statement(statement).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn execute_transaction(&self, statements: &[Statement]) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut rows_affected = 0;
+        for statement in statements {
+            let result = bind_statement(statement).execute(&mut tx).await?;
+            // dropping tx without commit rolls it back
+            statement.check_rows_affected(result.rows_affected())?;
+            rows_affected += result.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(rows_affected)
+    }
+
     async fn query(&self, _query: &str) -> anyhow::Result<QueryResult> {
         unimplemented!()
     }
@@ -209,14 +230,7 @@ impl Pool for MssqlPool {
             .iter()
             .map(|table| table.get(0))
             .collect::<Vec<String>>();
-        let mut list = vec![];
-        for db in databases {
-            list.push(Database::new(
-                db.clone(),
-                self.get_tables(db.clone()).await?,
-            ))
-        }
-        Ok(list)
+        Ok(databases.into_iter().map(Database::unloaded).collect())
     }
 
     async fn get_tables(&self, database: String) -> anyhow::Result<Vec<Child>> {
@@ -229,6 +243,8 @@ impl Pool for MssqlPool {
                 create_time: None,
                 update_time: None,
                 engine: None,
+                rows: None,
+                size: None,
                 schema: row.try_get("TABLE_SCHEMA")?,
             })
         }
@@ -243,6 +259,7 @@ impl Pool for MssqlPool {
                     Schema {
                         name: key.to_string(),
                         tables: group.cloned().collect(),
+                        objects: vec![],
                     }
                     .into(),
                 )
@@ -255,9 +272,8 @@ impl Pool for MssqlPool {
         &self,
         database: &Database,
         table: &Table,
-        _page: u16,
+        _page: &Page,
         filter: Option<String>,
-        _orderby: Option<String>,
     ) -> anyhow::Result<(Vec<Header>, Vec<Vec<Value>>)> {
         // FIXME
         let query = if let Some(filter) = filter.as_ref() {
@@ -445,46 +461,67 @@ impl Pool for MssqlPool {
         self.pool.close().await;
     }
 
+    // no cancel request here, the app drops the running task instead
+    async fn cancel(&self) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
     fn database_type(&self) -> DatabaseType {
         DatabaseType::Mssql
     }
 }
 
 
+fn bind_statement(statement: &Statement) -> sqlx::query::Query<'_, Mssql, sqlx::mssql::MssqlArguments> {
+    let mut query = sqlx::query(&statement.sql);
+    for param in &statement.params {
+        query = match param {
+            Param::Null => query.bind(None::<String>),
+            Param::Int(v) => query.bind(*v),
+            Param::Float(v) => query.bind(*v),
+            Param::Bool(v) => query.bind(*v),
+            Param::Text(v) => query.bind(v.as_str()),
+            // sqlx has no binary type for mssql
+            Param::Bytes(v) => query.bind(String::from_utf8_lossy(v).to_string()),
+        };
+    }
+    query
+}
+
 fn convert_column_value_to_string(row: &MssqlRow, column: &MssqlColumn) -> anyhow::Result<(Value, Header)> {
     let column_name = column.name();
 
     if let Ok(value) = row.try_get(column_name) {
         let value: Option<String> = value;
         let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Text), header))
     // } else if let Ok(value) = row.try_get(column_name) {
     //     let value: Option<&str> = value;
     //     Ok(get_or_null!(value))
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<i8> = value;
         let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Int), header))
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<i16> = value;
         let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Int), header))
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<i32> = value;
         let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Int), header))
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<i64> = value;
         let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Int), header))
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<f32> = value;
         let header = Header::new(column_name.to_string(), ColType::Float);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Float), header))
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<f64> = value;
         let header = Header::new(column_name.to_string(), ColType::Float);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Float), header))
     // } else if let Ok(value) = row.try_get(column_name) {
     //     let value: Option<u8> = value;
     //     Ok(get_or_null!(value))
@@ -518,7 +555,7 @@ fn convert_column_value_to_string(row: &MssqlRow, column: &MssqlColumn) -> anyho
     } else if let Ok(value) = row.try_get(column_name) {
         let value: Option<bool> = value;
         let header = Header::new(column_name.to_string(), ColType::Boolean);
-        Ok((get_or_null!(value), header))
+        Ok((get_or_null!(value, Datum::Bool), header))
     } else {
         anyhow::bail!(
             "column type not implemented: `{}` {}",
@@ -1,18 +1,22 @@
 use crate::get_or_null;
 use crate::config::DatabaseType;
-use super::{ExecuteResult, QueryResult, Pool, TableRow, Header, ColType, Value};
+use super::{statement_error, ExecuteResult, PlanNode, QueryResult, Pool, RowSink, Running, TableRow, Statement, Param, Page, Header, ColType, Value, Datum};
 use async_trait::async_trait;
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use database_tree::{Child, Database, Table};
+use chrono::{NaiveDate, NaiveTime};
+use database_tree::{Child, Database, Object, ObjectKind, Table};
 use futures::TryStreamExt;
-use sqlx::mysql::{MySqlColumn, MySqlPoolOptions, MySqlRow, MySql};
-use sqlx::{Column as _, Row as _, TypeInfo as _};
+use sqlx::mysql::{MySqlColumn, MySqlConnection, MySqlPoolOptions, MySqlRow, MySqlValueRef, MySql};
+use rust_decimal::Decimal;
+use sqlx::{Column as _, Executor as _, Row as _, Statement as _, TypeInfo as _, ValueRef as _};
 use sqlx::decode::Decode;
+use std::convert::TryInto;
+use std::sync::Mutex;
 use std::time::Duration;
 
 pub struct MySqlPool {
     pool: sqlx::mysql::MySqlPool,
     page_size: u16,
+    running: Mutex<Option<u64>>, // connection id
 }
 
 impl MySqlPool {
@@ -23,8 +27,14 @@ impl MySqlPool {
                 .acquire_timeout(Duration::from_secs(5))
                 .connect(database_url)
                 .await?,
+            running: Mutex::new(None),
         })
     }
+
+    async fn running(&self, conn: &mut MySqlConnection) -> anyhow::Result<Running<'_, u64>> {
+        let id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()").fetch_one(conn).await?;
+        Ok(Running::new(&self.running, id))
+    }
 }
 
 pub struct Constraint {
@@ -151,63 +161,110 @@ impl TableRow for Index {
 impl Pool for MySqlPool {
     async fn execute(&self, query: &str) -> anyhow::Result<ExecuteResult> {
         let query = query.trim();
-
-        if query.to_uppercase().starts_with("SELECT") || query.to_uppercase().starts_with("SHOW") {
-            let result = self.query(query).await?;
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        let result = query_rows(&mut conn, query).await?;
+        // a statement with result columns always has headers
+        if !result.headers.is_empty() {
             return Ok(ExecuteResult::Read {
                 headers: result.headers,
                 rows: result.rows,
                 database: Database {
                     name: "-".to_string(),
                     children: Vec::new(),
+                    loaded: true,
                 },
                 table: Table {
                     name: "-".to_string(),
                     create_time: None,
                     update_time: None,
                     engine: None,
+                    rows: None,
+                    size: None,
                     schema: None,
                 },
             });
         }
-
-        let result = sqlx::query(query).execute(&self.pool).await?;
         Ok(ExecuteResult::Write {
-            updated_rows: result.rows_affected(),
+            updated_rows: result.updated_rows,
         })
     }
 
-    async fn query(&self, query: &str) -> anyhow::Result<QueryResult> {
-        let query = query.trim();
-
-        if query.to_uppercase().starts_with("SELECT") || query.to_uppercase().starts_with("SHOW") {
-            let mut rows = sqlx::query(query).fetch(&self.pool);
-            let mut headers = vec![];
-            let mut records = vec![];
-            while let Some(row) = rows.try_next().await? {
-              
-                let mut new_row = vec![];
-                for column in row.columns() {
-                    let row = convert_column_value_to_string(&row, column)?;
-                    new_row.push(row.0);
-                    if records.len() == 0 { headers.push(row.1); };
+    async fn stream(&self, queries: &[String], sink: &mut RowSink) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let running = self.running(&mut conn).await?;
+        for (i, query) in queries.iter().enumerate() {
+            let last = i + 1 == queries.len();
+            sink.start(i, last);
+            // false once no more rows are wanted
+            let result: anyhow::Result<bool> = async {
+                let columns = (&mut *conn).prepare(query.as_str()).await?
+                    .columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>();
+                if columns.is_empty() {
+                    let result = sqlx::query(query).execute(&mut *conn).await?;
+                    sink.affected(result.rows_affected()).await?;
+                    return Ok(true);
+                }
+                let mut rows = sqlx::query(query).fetch(&mut *conn);
+                let mut headers = vec![];
+                let mut batch = vec![];
+                while let Some(row) = rows.try_next().await? {
+                    let mut new_row = vec![];
+                    for column in row.columns() {
+                        let row = convert_column_value_to_string(&row, column)?;
+                        new_row.push(row.0);
+                        if !sink.sent() && batch.is_empty() { headers.push(row.1); };
+                    }
+                    batch.push(new_row);
+                    if batch.len() == sink.limit {
+                        if !last {
+                            // only the last result set stays open, the statements after it need the connection
+                            sink.send(&headers, std::mem::take(&mut batch), false).await?;
+                            while rows.try_next().await?.is_some() {}
+                            return Ok(true);
+                        }
+                        running.pause();
+                        let more = sink.send(&headers, std::mem::take(&mut batch), true).await?;
+                        running.resume();
+                        if !more { return Ok(false) }
+                    }
+                }
+                if !sink.sent() && headers.is_empty() {
+                    headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
                 }
-                records.push(new_row)
+                sink.send(&headers, batch, false).await?;
+                Ok(true)
+            }.await;
+            if !result.map_err(|e| statement_error(e, i, queries.len()))? {
+                // closing the connection stops the query, reusing it would read all the rows left
+                drop(conn.detach());
+                return Ok(());
             }
+        }
+        Ok(())
+    }
 
-            return Ok(QueryResult {
-                headers,
-                rows: records,
-                updated_rows: 0,
-            });
+    async fn execute_statement(&self, statement: &Statement) -> anyhow::Result<u64> {
+        let result = bind_statement(statement).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn execute_transaction(&self, statements: &[Statement]) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut rows_affected = 0;
+        for statement in statements {
+            let result = bind_statement(statement).execute(&mut tx).await?;
+            // dropping tx without commit rolls it back
+            statement.check_rows_affected(result.rows_affected())?;
+            rows_affected += result.rows_affected();
         }
+        tx.commit().await?;
+        Ok(rows_affected)
+    }
 
-        let result = sqlx::query(query).execute(&self.pool).await?;
-        Ok(QueryResult {
-            headers: vec![],
-            rows: vec![],
-            updated_rows: result.rows_affected(),
-        })
+    async fn query(&self, query: &str) -> anyhow::Result<QueryResult> {
+        let mut conn = self.pool.acquire().await?;
+        query_rows(&mut conn, query).await
     }
 
     async fn get_databases(&self) -> anyhow::Result<Vec<Database>> {
@@ -217,62 +274,79 @@ impl Pool for MySqlPool {
             .iter()
             .map(|table| table.get(0))
             .collect::<Vec<String>>();
-        let mut list = vec![];
-        for db in databases {
-            list.push(Database::new(
-                db.clone(),
-                self.get_tables(db.clone()).await?,
-            ))
-        }
-        Ok(list)
+        Ok(databases.into_iter().map(Database::unloaded).collect())
     }
 
     async fn get_tables(&self, database: String) -> anyhow::Result<Vec<Child>> {
         let query = format!("SHOW TABLE STATUS FROM `{}`", database);
         let mut rows = sqlx::query(query.as_str()).fetch(&self.pool);
         let mut tables = vec![];
+        let mut objects = vec![];
         while let Some(row) = rows.try_next().await? {
+            let comment: Option<String> = row.try_get("Comment")?;
+            if comment.as_deref() == Some("VIEW") {
+                objects.push(Object { name: row.try_get("Name")?, kind: ObjectKind::View, schema: None, table: None });
+                continue;
+            }
             tables.push(Table {
                 name: row.try_get("Name")?,
                 create_time: row.try_get("Create_time")?,
                 update_time: row.try_get("Update_time")?,
                 engine: row.try_get("Engine")?,
+                rows: row.try_get_unchecked("Rows")?,
+                size: Some(row.try_get_unchecked::<Option<u64>, _>("Data_length")?.unwrap_or(0) + row.try_get_unchecked::<Option<u64>, _>("Index_length")?.unwrap_or(0)),
+                schema: None,
+            })
+        }
+        drop(rows);
+        // information_schema columns come back as binary on some versions
+        let query = "SELECT ROUTINE_NAME, ROUTINE_TYPE, NULL FROM information_schema.ROUTINES WHERE ROUTINE_SCHEMA = ?
+            UNION ALL
+            SELECT TRIGGER_NAME, 'TRIGGER', EVENT_OBJECT_TABLE FROM information_schema.TRIGGERS WHERE TRIGGER_SCHEMA = ?
+            ORDER BY 1";
+        let rows = sqlx::query(query).bind(&database).bind(&database).fetch_all(&self.pool).await?;
+        for row in rows {
+            let kind = match decode::<String>(row.try_get_raw(1)?)?.as_deref() {
+                Some("PROCEDURE") => ObjectKind::Procedure,
+                Some("TRIGGER") => ObjectKind::Trigger,
+                _ => ObjectKind::Function,
+            };
+            objects.push(Object {
+                name: decode::<String>(row.try_get_raw(0)?)?.unwrap_or_default(),
+                kind,
                 schema: None,
+                table: decode::<String>(row.try_get_raw(2)?)?,
             })
         }
-        Ok(tables.into_iter().map(|table| table.into()).collect())
+        objects.sort_by_key(|o| o.kind);
+        Ok(tables.into_iter().map(Child::from).chain(objects.into_iter().map(Child::from)).collect())
+    }
+
+    async fn get_definition(&self, database: &Database, object: &Object) -> anyhow::Result<String> {
+        let (keyword, column) = match object.kind {
+            ObjectKind::View => ("VIEW", "Create View"),
+            ObjectKind::Function => ("FUNCTION", "Create Function"),
+            ObjectKind::Procedure => ("PROCEDURE", "Create Procedure"),
+            ObjectKind::Trigger => ("TRIGGER", "SQL Original Statement"),
+            kind => anyhow::bail!("mysql has no {}", kind.label()),
+        };
+        let query = format!("SHOW CREATE {} `{}`.`{}`", keyword, database.name, object.name);
+        let row = sqlx::query(&query).fetch_one(&self.pool).await?;
+        let definition = decode::<String>(row.try_get_raw(column)?)?.unwrap_or_default();
+        Ok(format!("{};", definition))
     }
 
     async fn get_records(
         &self,
         database: &Database,
         table: &Table,
-        page: u16,
+        page: &Page,
         filter: Option<String>,
-        orderby: Option<String>,
     ) -> anyhow::Result<(Vec<Header>, Vec<Vec<Value>>)> {
-        let orderby = if orderby.is_none() { "".to_string() } else { format!("ORDER BY {}", orderby.unwrap()) };
-        let query = if let Some(filter) = filter {
-            format!(
-                "SELECT * FROM `{database}`.`{table}` WHERE {filter} {orderby} LIMIT {page}, {limit}",
-                database = database.name,
-                table = table.name,
-                filter = filter,
-                orderby = orderby,
-                page = page,
-                limit = self.page_size
-            )
-        } else {
-            format!(
-                "SELECT * FROM `{}`.`{}` {orderby} LIMIT {page}, {limit}",
-                database.name,
-                table.name,
-                orderby = orderby,
-                page = page,
-                limit = self.page_size
-            )
-        };
-        let mut rows = sqlx::query(query.as_str()).fetch(&self.pool);
+        let query = self.database_type().records_query(database, table, "*", page, filter.as_deref(), self.page_size);
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        let mut rows = sqlx::query(query.as_str()).fetch(&mut *conn);
         let mut headers = vec![];
         let mut records = vec![];
         while let Some(row) = rows.try_next().await? {
@@ -418,113 +492,272 @@ impl Pool for MySqlPool {
         Ok(foreign_keys)
     }
 
+    async fn explain(&self, query: &str, analyze: bool) -> anyhow::Result<PlanNode> {
+        if analyze {
+            // `EXPLAIN ANALYZE` only prints a text tree
+            anyhow::bail!("EXPLAIN ANALYZE is only supported for postgres");
+        }
+        let row = sqlx::query(&format!("EXPLAIN FORMAT=JSON {}", query)).fetch_one(&self.pool).await?;
+        let plan = decode::<String>(row.try_get_raw(0)?)?.unwrap_or_default();
+        PlanNode::from_mysql(&serde_json::from_str(&plan)?)
+    }
+
     async fn close(&self) {
         self.pool.close().await;
     }
 
+    async fn cancel(&self) -> anyhow::Result<bool> {
+        let id = *self.running.lock().unwrap();
+        if let Some(id) = id {
+            sqlx::query(&format!("KILL QUERY {}", id)).execute(&self.pool).await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     fn database_type(&self) -> DatabaseType {
         DatabaseType::MySql
     }
 }
 
+async fn query_rows(conn: &mut MySqlConnection, query: &str) -> anyhow::Result<QueryResult> {
+    let query = query.trim();
+
+    // WITH, PRAGMA, VALUES, EXPLAIN and the like return rows too, as in stream
+    let columns = (&mut *conn).prepare(query).await?
+        .columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>();
+    if !columns.is_empty() {
+        let mut rows = sqlx::query(query).fetch(&mut *conn);
+        let mut headers = vec![];
+        let mut records = vec![];
+        while let Some(row) = rows.try_next().await? {
+          
+            let mut new_row = vec![];
+            for column in row.columns() {
+                let row = convert_column_value_to_string(&row, column)?;
+                new_row.push(row.0);
+                if records.len() == 0 { headers.push(row.1); };
+            }
+            records.push(new_row)
+        }
+
+        if headers.is_empty() {
+            headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
+        }
+        return Ok(QueryResult {
+            headers,
+            rows: records,
+            updated_rows: 0,
+        });
+    }
+
+    let result = sqlx::query(query).execute(&mut *conn).await?;
+    Ok(QueryResult {
+        headers: vec![],
+        rows: vec![],
+        updated_rows: result.rows_affected(),
+    })
+}
+
+fn bind_statement(statement: &Statement) -> sqlx::query::Query<'_, MySql, sqlx::mysql::MySqlArguments> {
+    let mut query = sqlx::query(&statement.sql);
+    for param in &statement.params {
+        query = match param {
+            Param::Null => query.bind(None::<String>),
+            Param::Int(v) => query.bind(*v),
+            Param::Float(v) => query.bind(*v),
+            Param::Bool(v) => query.bind(*v),
+            Param::Text(v) => query.bind(v.as_str()),
+            Param::Bytes(v) => query.bind(v.as_slice()),
+        };
+    }
+    query
+}
+
 fn convert_column_value_to_string(row: &MySqlRow, column: &MySqlColumn) -> anyhow::Result<(Value, Header)> {
     let column_name = column.name();
+    let header = |typ| Header::new(column_name.to_string(), typ);
+    // decoded by the column type, sqlx's own checks reject unsigned, YEAR, BIT and non-utf8 text
+    let typ = column.type_info().name();
+    let value = row.try_get_raw(column.ordinal())?;
+    match typ {
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "BOOLEAN" => {
+            let value: Option<i64> = decode(value)?;
+            Ok((get_or_null!(value, Datum::Int), header(ColType::Int)))
+        },
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED" | "BIGINT UNSIGNED" | "YEAR" | "BIT" => {
+            let value: Option<u64> = decode(value)?;
+            Ok((get_or_null!(value, Datum::UInt), header(ColType::Int)))
+        },
+        "FLOAT" => {
+            let value: Option<f32> = decode(value)?;
+            Ok((get_or_null!(value, Datum::Float), header(ColType::Float)))
+        },
+        "DOUBLE" => {
+            let value: Option<f64> = decode(value)?;
+            Ok((get_or_null!(value, Datum::Float), header(ColType::Float)))
+        },
+        "DECIMAL" => {
+            let value: Option<Decimal> = decode(value)?;
+            Ok((get_or_null!(value, Datum::Decimal), header(ColType::Float)))
+        },
+        "DATE" | "TIME" | "DATETIME" | "TIMESTAMP" => {
+            let value: Option<&[u8]> = decode(value)?;
+            Ok((value.map_or(Value::default(), |v| decode_temporal(typ, v).into()), header(ColType::Date)))
+        },
+        "JSON" => {
+            let value: Option<&[u8]> = decode(value)?;
+            let json = |v: &[u8]| serde_json::from_slice(v).map_or_else(|_| Datum::Text(String::from_utf8_lossy(v).to_string()), Datum::Json);
+            Ok((value.map_or(Value::default(), |v| json(v).into()), header(ColType::Json)))
+        },
+        "GEOMETRY" => {
+            let value: Option<&[u8]> = decode(value)?;
+            let wkt = |v: &[u8]| geometry_to_wkt(v).map_or_else(|| Datum::Bytes(v.to_vec()), Datum::Text);
+            Ok((value.map_or(Value::default(), |v| wkt(v).into()), header(ColType::VarChar)))
+        },
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+            let value: Option<&[u8]> = decode(value)?;
+            Ok((value.map_or(Value::default(), |v| Datum::Bytes(v.to_vec()).into()), header(ColType::Unknown)))
+        },
+        // CHAR, VARCHAR, TEXT, ENUM, SET and whatever a newer server sends
+        _ => {
+            let value: Option<&[u8]> = decode(value)?;
+            Ok((value.map_or(Value::default(), |v| Datum::Text(String::from_utf8_lossy(v).to_string()).into()), header(ColType::VarChar)))
+        },
+    }
+}
 
-    if let Ok(value) = row.try_get(column_name) {
-        let value: Option<String> = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<&str> = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i8> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i16> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i32> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i64> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<f32> = value;
-        let header = Header::new(column_name.to_string(), ColType::Float);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<f64> = value;
-        let header = Header::new(column_name.to_string(), ColType::Float);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<u8> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<u16> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<u32> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<u64> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<rust_decimal::Decimal> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<NaiveDate> = value;
-        let header = Header::new(column_name.to_string(), ColType::Date);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<NaiveTime> = value;
-        let header = Header::new(column_name.to_string(), ColType::Date);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<NaiveDateTime> = value;
-        let header = Header::new(column_name.to_string(), ColType::Date);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<chrono::DateTime<chrono::Utc>> = value;
-        let header = Header::new(column_name.to_string(), ColType::Date);
-        let t = value.map(|t| t.to_string().strip_suffix(" UTC").unwrap().to_string());
-        Ok((get_or_null!(t), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<serde_json::Value> = value;
-        let header = Header::new(column_name.to_string(), ColType::Date);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<bool> = value;
-        let header = Header::new(column_name.to_string(), ColType::Boolean);
-        Ok((get_or_null!(value), header))
-    } else {
-        let index = column.ordinal();
-        if let Ok(val) = row.try_get_raw(index) {
-            // https://docs.rs/sqlx-core/0.5.11/src/sqlx_core/mysql/types/str.rs.html
-            // match val.format() {
-            //     MySqlValueFormat::Binary => {},
-            //     MySqlValueFormat::Text => {},
-            // }
-            if let Ok(value) = Decode::<MySql>::decode(val) {
-                let value: &str = value;
-                let header = Header::new(column_name.to_string(), ColType::VarChar);
-                return Ok((Value::new(value.to_string()), header))
-            }
+fn decode<'r, T: Decode<'r, MySql>>(value: MySqlValueRef<'r>) -> anyhow::Result<Option<T>> {
+    if value.is_null() {
+        return Ok(None)
+    }
+    T::decode(value).map(Some).map_err(|e| anyhow::anyhow!(e))
+}
+
+// DATE, DATETIME and TIMESTAMP are length, year (2 bytes), month, day, hour, minute, second, microseconds (4 bytes),
+// TIME is length, is negative, days (4 bytes), hour, minute, second, microseconds (4 bytes), trailing zeros are left out.
+// zero dates and times past a day have no chrono type and stay text.
+fn decode_temporal(typ: &str, bytes: &[u8]) -> Datum {
+    // the text protocol sends them formatted already
+    if bytes.first().map(|len| *len as usize + 1) != Some(bytes.len()) {
+        return Datum::Text(String::from_utf8_lossy(bytes).to_string())
+    }
+    let int = |from: usize, len: usize| bytes[1..].iter().skip(from).take(len).rev().fold(0u32, |acc, b| acc << 8 | u32::from(*b));
+    let micros = |from: usize| match int(from, 4) {
+        0 => String::new(),
+        micros => format!(".{:06}", micros),
+    };
+    if typ == "TIME" {
+        let (negative, hours, minutes, seconds) = (int(0, 1) == 1, int(1, 4) * 24 + int(5, 1), int(6, 1), int(7, 1));
+        return match NaiveTime::from_hms_micro_opt(hours, minutes, seconds, int(8, 4)) {
+            Some(time) if !negative => Datum::Time(time),
+            _ => Datum::Text(format!("{}{:02}:{:02}:{:02}{}", if negative { "-" } else { "" }, hours, minutes, seconds, micros(8))),
         }
-        anyhow::bail!(
-            "column type not implemented: `{}` {}",
-            column_name,
-            column.type_info().clone().name()
-        )
+    }
+    let (year, month, day) = (int(0, 2), int(2, 1), int(3, 1));
+    let date = NaiveDate::from_ymd_opt(year as i32, month, day);
+    if typ == "DATE" {
+        return date.map_or_else(|| Datum::Text(format!("{:04}-{:02}-{:02}", year, month, day)), Datum::Date)
+    }
+    let (hour, minute, second) = (int(4, 1), int(5, 1), int(6, 1));
+    match date.and_then(|date| date.and_hms_micro_opt(hour, minute, second, int(7, 4))) {
+        Some(datetime) => Datum::DateTime(datetime),
+        None => Datum::Text(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}", year, month, day, hour, minute, second, micros(7))),
+    }
+}
+
+// well-known text of a geometry, mysql stores the srid in front of the well-known binary
+fn geometry_to_wkt(bytes: &[u8]) -> Option<String> {
+    let mut wkb = Wkb { bytes: bytes.get(4..)?, little: true };
+    let wkt = wkb.geometry()?;
+    wkb.bytes.is_empty().then_some(wkt)
+}
+
+struct Wkb<'a> {
+    bytes: &'a [u8],
+    little: bool,
+}
+
+impl Wkb<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let head = self.bytes.get(..N)?.try_into().ok()?;
+        self.bytes = &self.bytes[N..];
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let b = self.take()?;
+        Some(if self.little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        let b = self.take()?;
+        Some(if self.little { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) })
+    }
+
+    fn list(&mut self, item: fn(&mut Self) -> Option<String>) -> Option<String> {
+        let n = self.u32()?;
+        Some(format!("({})", (0..n).map(|_| item(self)).collect::<Option<Vec<_>>>()?.join(",")))
+    }
+
+    fn point(&mut self) -> Option<String> {
+        Some(format!("{} {}", self.f64()?, self.f64()?))
+    }
+
+    fn points(&mut self) -> Option<String> {
+        self.list(Self::point)
+    }
+
+    fn rings(&mut self) -> Option<String> {
+        self.list(Self::points)
+    }
+
+    // the parts of a multi geometry are whole geometries, their type name is left out
+    fn parts(&mut self) -> Option<String> {
+        self.list(|wkb| wkb.geometry().map(|g| g.trim_start_matches(char::is_alphabetic).to_string()))
+    }
+
+    fn geometry(&mut self) -> Option<String> {
+        self.little = self.take::<1>()?[0] == 1;
+        match self.u32()? {
+            1 => Some(format!("POINT({})", self.point()?)),
+            2 => Some(format!("LINESTRING{}", self.points()?)),
+            3 => Some(format!("POLYGON{}", self.rings()?)),
+            4 => Some(format!("MULTIPOINT{}", self.parts()?)),
+            5 => Some(format!("MULTILINESTRING{}", self.parts()?)),
+            6 => Some(format!("MULTIPOLYGON{}", self.parts()?)),
+            7 => Some(format!("GEOMETRYCOLLECTION{}", self.list(Self::geometry)?)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_temporal() {
+        let date = NaiveDate::from_ymd(2024, 2, 29);
+        assert_eq!(decode_temporal("DATE", &[4, 0xe8, 0x07, 2, 29]), Datum::Date(date));
+        assert_eq!(decode_temporal("DATE", &[0]), Datum::Text("0000-00-00".to_string()));
+        assert_eq!(decode_temporal("DATETIME", &[7, 0xe8, 0x07, 2, 29, 13, 5, 9]), Datum::DateTime(date.and_hms(13, 5, 9)));
+        assert_eq!(decode_temporal("TIMESTAMP", &[4, 0xe8, 0x07, 2, 0]), Datum::Text("2024-02-00 00:00:00".to_string()));
+        assert_eq!(decode_temporal("TIME", &[0]), Datum::Time(NaiveTime::from_hms(0, 0, 0)));
+        assert_eq!(decode_temporal("TIME", &[12, 0, 0, 0, 0, 0, 1, 2, 3, 0x40, 0xe2, 0x01, 0]), Datum::Time(NaiveTime::from_hms_micro(1, 2, 3, 123_456)));
+        assert_eq!(decode_temporal("TIME", &[8, 1, 34, 0, 0, 0, 22, 59, 59]), Datum::Text("-838:59:59".to_string()));
+        assert_eq!(decode_temporal("DATE", b"2024-02-29"), Datum::Text("2024-02-29".to_string()));
+    }
+
+    #[test]
+    fn test_geometry_to_wkt() {
+        let point = |x: f64, y: f64| [[1u8].as_slice(), &1u32.to_le_bytes(), &x.to_le_bytes(), &y.to_le_bytes()].concat();
+        let srid = 4326u32.to_le_bytes();
+        assert_eq!(geometry_to_wkt(&[srid.as_slice(), &point(1.0, 2.5)].concat()).as_deref(), Some("POINT(1 2.5)"));
+        let multi = [srid.as_slice(), &[1], &4u32.to_le_bytes(), &2u32.to_le_bytes(), &point(1.0, 2.0), &point(3.0, 4.0)].concat();
+        assert_eq!(geometry_to_wkt(&multi).as_deref(), Some("MULTIPOINT((1 2),(3 4))"));
+        let line = [srid.as_slice(), &[0], &2u32.to_be_bytes(), &2u32.to_be_bytes(), &0f64.to_be_bytes(), &0f64.to_be_bytes(), &1f64.to_be_bytes(), &1f64.to_be_bytes()].concat();
+        assert_eq!(geometry_to_wkt(&line).as_deref(), Some("LINESTRING(0 0,1 1)"));
+        assert_eq!(geometry_to_wkt(&multi[..multi.len() - 1]), None);
     }
 }
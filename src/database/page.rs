@@ -0,0 +1,182 @@
+use super::{Param, Statement, SQLITE_ROWID};
+use crate::config::DatabaseType;
+use database_tree::{Database, Table};
+
+/// which rows of a table `get_records` loads
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    pub offset: u64,
+    pub orderby: Option<String>, // `column asc|desc`, as used without a keyset
+    pub keyset: Option<Keyset>,
+}
+
+/// sort by a unique key and continue after the last loaded row instead of scanning an offset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyset {
+    pub sort: Option<String>, // may contain nulls, the key breaks ties
+    pub key: Vec<String>,
+    pub descending: bool,
+    pub after: Option<Vec<Param>>, // sort and key values of the last loaded row
+}
+
+impl Page {
+    // first page, with a keyset when the table has a primary key
+    pub fn new(orderby: Option<String>, key: Vec<String>) -> Self {
+        // sqlite rowid is not part of the records, so there is nothing to continue after
+        let keyset = if key.is_empty() || key == [SQLITE_ROWID] {
+            None
+        } else {
+            let (sort, descending) = match orderby.as_deref().and_then(|o| o.rsplit_once(' ')) {
+                Some((column, direction)) => (Some(column.to_string()), direction.eq_ignore_ascii_case("desc")),
+                None => (None, false),
+            };
+            // sorting by a key column is the same as sorting by the key
+            let sort = sort.filter(|s| !key.contains(s));
+            Some(Keyset { sort, key, descending, after: None })
+        };
+        Self { offset: 0, orderby, keyset }
+    }
+}
+
+impl Keyset {
+    pub fn columns(&self) -> Vec<&String> {
+        self.sort.iter().chain(self.key.iter()).collect()
+    }
+}
+
+impl DatabaseType {
+
+    // nulls sort as the smallest value in mysql and sqlite, and as the largest in postgres
+    fn nulls_after(&self, descending: bool) -> bool {
+        match self {
+            DatabaseType::Postgres => !descending,
+            _ => descending,
+        }
+    }
+
+    pub fn count_query(&self, database: &Database, table: &Table, filter: Option<&str>) -> String {
+        let query = format!("SELECT COUNT(*) FROM {}", self.table_path(database, table));
+        match filter {
+            Some(filter) => format!("{} WHERE ({})", query, filter),
+            None => query,
+        }
+    }
+
+    pub fn records_query(&self, database: &Database, table: &Table, select: &str, page: &Page, filter: Option<&str>, limit: u16) -> String {
+        let mut conditions = filter.map(|f| vec![format!("({})", f)]).unwrap_or_default();
+        let mut offset = page.offset;
+        let orderby = match &page.keyset {
+            Some(keyset) => {
+                let columns = keyset.columns().into_iter().map(|c| self.quote_identifier(c)).collect::<Vec<_>>();
+                match &keyset.after {
+                    // a null sort value can't be compared, continue with the offset
+                    Some(after) if !after.contains(&Param::Null) => {
+                        let mut statement = Statement::default();
+                        let values = after.iter()
+                            .map(|p| statement.bind(self, p.clone(), None))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        let mut condition = format!("({}) {} ({})", columns.join(", "), if keyset.descending { "<" } else { ">" }, values);
+                        // rows with a null sort value are still ahead
+                        if keyset.sort.is_some() && self.nulls_after(keyset.descending) {
+                            condition = format!("{} OR {} IS NULL", condition, columns[0]);
+                        }
+                        statement.sql = format!("({})", condition);
+                        conditions.push(self.render(&statement));
+                        offset = 0;
+                    },
+                    _ => {},
+                }
+                let direction = if keyset.descending { " DESC" } else { "" };
+                Some(columns.iter().map(|c| format!("{}{}", c, direction)).collect::<Vec<_>>().join(", "))
+            },
+            None => page.orderby.clone(),
+        };
+        let mut query = format!("SELECT {} FROM {}", select, self.table_path(database, table));
+        if !conditions.is_empty() {
+            query = format!("{} WHERE {}", query, conditions.join(" AND "));
+        }
+        if let Some(orderby) = orderby {
+            query = format!("{} ORDER BY {}", query, orderby);
+        }
+        query = format!("{} LIMIT {}", query, limit);
+        if offset > 0 {
+            query = format!("{} OFFSET {}", query, offset);
+        }
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> (Database, Table) {
+        let database = Database::new("db".to_string(), vec![]);
+        let table = Table { name: "users".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+        (database, table)
+    }
+
+    #[test]
+    fn test_page_new() {
+        let page = Page::new(Some("name desc".to_string()), vec!["id".to_string()]);
+        assert_eq!(page.keyset, Some(Keyset { sort: Some("name".to_string()), key: vec!["id".to_string()], descending: true, after: None }));
+        let page = Page::new(Some("id asc".to_string()), vec!["id".to_string()]);
+        assert_eq!(page.keyset.unwrap().sort, None);
+        assert_eq!(Page::new(None, vec![]).keyset, None);
+        assert_eq!(Page::new(None, vec![SQLITE_ROWID.to_string()]).keyset, None);
+    }
+
+    #[test]
+    fn test_count_query() {
+        let (database, table) = table();
+        assert_eq!(DatabaseType::Postgres.count_query(&database, &table, None), r#"SELECT COUNT(*) FROM "db"."public"."users""#);
+        assert_eq!(
+            DatabaseType::MySql.count_query(&database, &table, Some("age > 1")),
+            "SELECT COUNT(*) FROM `db`.`users` WHERE (age > 1)"
+        );
+    }
+
+    #[test]
+    fn test_records_query_offset() {
+        let (database, table) = table();
+        let page = Page { offset: 70000, orderby: Some("name desc".to_string()), keyset: None };
+        assert_eq!(
+            DatabaseType::MySql.records_query(&database, &table, "*", &page, Some("age > 1"), 200),
+            "SELECT * FROM `db`.`users` WHERE (age > 1) ORDER BY name desc LIMIT 200 OFFSET 70000"
+        );
+    }
+
+    #[test]
+    fn test_records_query_keyset() {
+        let (database, table) = table();
+        let mut page = Page::new(None, vec!["group_id".to_string(), "user_id".to_string()]);
+        assert_eq!(
+            DatabaseType::Sqlite.records_query(&database, &table, "*", &page, None, 200),
+            "SELECT * FROM `users` ORDER BY `group_id`, `user_id` LIMIT 200"
+        );
+        page.offset = 200;
+        page.keyset.as_mut().unwrap().after = Some(vec![Param::Int(1), Param::Text("a'b".to_string())]);
+        assert_eq!(
+            DatabaseType::Postgres.records_query(&database, &table, "*", &page, Some("age > 1"), 200),
+            r#"SELECT * FROM "db"."public"."users" WHERE (age > 1) AND (("group_id", "user_id") > (1, 'a''b')) ORDER BY "group_id", "user_id" LIMIT 200"#
+        );
+    }
+
+    #[test]
+    fn test_records_query_keyset_with_sort() {
+        let (database, table) = table();
+        let mut page = Page::new(Some("name desc".to_string()), vec!["id".to_string()]);
+        page.offset = 200;
+        page.keyset.as_mut().unwrap().after = Some(vec![Param::Text("bob".to_string()), Param::Int(7)]);
+        assert_eq!(
+            DatabaseType::MySql.records_query(&database, &table, "*", &page, None, 200),
+            "SELECT * FROM `db`.`users` WHERE ((`name`, `id`) < ('bob', 7) OR `name` IS NULL) ORDER BY `name` DESC, `id` DESC LIMIT 200"
+        );
+        page.keyset.as_mut().unwrap().after = Some(vec![Param::Null, Param::Int(7)]);
+        assert_eq!(
+            DatabaseType::MySql.records_query(&database, &table, "*", &page, None, 200),
+            "SELECT * FROM `db`.`users` ORDER BY `name` DESC, `id` DESC LIMIT 200 OFFSET 200"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use serde_json::Value as Json;
+
+// mysql keys that hold a step of the plan, the others describe the step they are in
+const MYSQL_NODES: [&str; 19] = [
+    "query_block",
+    "table",
+    "nested_loop",
+    "ordering_operation",
+    "grouping_operation",
+    "duplicates_removal",
+    "windowing",
+    "buffer_result",
+    "union_result",
+    "query_specifications",
+    "materialized_from_subquery",
+    "attached_subqueries",
+    "optimized_away_subqueries",
+    "select_list_subqueries",
+    "order_by_subqueries",
+    "group_by_subqueries",
+    "having_subqueries",
+    "update_value_subqueries",
+    "subqueries",
+];
+
+/// one step of a query plan. like the databases report them, cost and time include the children
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    pub label: String,
+    pub cost: Option<f64>,
+    pub rows: Option<f64>,
+    pub time: Option<f64>, // milliseconds, only when the query was analyzed
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    pub fn new(label: String) -> Self {
+        Self { label, cost: None, rows: None, time: None, children: vec![] }
+    }
+
+    // what the step takes without its children, by time when the query was analyzed
+    pub fn own_weight(&self) -> Option<f64> {
+        let total = self.time.or(self.cost)?;
+        let children = self.children.iter().map(PlanNode::weight).sum::<f64>();
+        Some((total - children).max(0.0))
+    }
+
+    // a step without numbers weighs what its children do
+    fn weight(&self) -> f64 {
+        self.time.or(self.cost).unwrap_or_else(|| self.children.iter().map(PlanNode::weight).sum())
+    }
+
+    // output of `EXPLAIN (FORMAT JSON)`
+    pub fn from_postgres(json: &Json) -> anyhow::Result<Self> {
+        let plan = json.get(0).and_then(|p| p.get("Plan")).ok_or_else(|| anyhow::anyhow!("no plan in {}", json))?;
+        Ok(postgres_node(plan))
+    }
+
+    // output of `EXPLAIN FORMAT=JSON`
+    pub fn from_mysql(json: &Json) -> anyhow::Result<Self> {
+        let block = json.get("query_block").ok_or_else(|| anyhow::anyhow!("no plan in {}", json))?;
+        Ok(mysql_node("query_block", block))
+    }
+
+    // `id`, `parent` and `detail` of the rows of `EXPLAIN QUERY PLAN`
+    pub fn from_sqlite(rows: &[(i64, i64, String)]) -> Self {
+        let mut root = Self::new("QUERY PLAN".to_string());
+        root.children = sqlite_children(rows, 0);
+        root
+    }
+}
+
+fn postgres_node(plan: &Json) -> PlanNode {
+    let text = |key: &str| plan.get(key).and_then(Json::as_str);
+    let number = |key: &str| plan.get(key).and_then(Json::as_f64);
+    // the way `EXPLAIN` prints it: Hash Left Join, Index Scan using pkey on users u
+    let mut label = text("Node Type").unwrap_or("?").to_string();
+    if let Some(join) = text("Join Type").filter(|j| *j != "Inner") {
+        label = match label.strip_suffix(" Join") {
+            Some(node) => format!("{} {} Join", node, join),
+            None => format!("{} {} Join", label, join),
+        };
+    }
+    if let Some(index) = text("Index Name") {
+        // a bitmap index scan has no relation
+        let on = if label == "Bitmap Index Scan" { "on" } else { "using" };
+        label = format!("{} {} {}", label, on, index);
+    }
+    if let Some(relation) = text("Relation Name").or_else(|| text("CTE Name")).or_else(|| text("Function Name")) {
+        label = format!("{} on {}", label, relation);
+        if let Some(alias) = text("Alias").filter(|a| *a != relation) {
+            label = format!("{} {}", label, alias);
+        }
+    }
+    PlanNode {
+        label,
+        cost: number("Total Cost"),
+        rows: number("Actual Rows").or_else(|| number("Plan Rows")),
+        time: number("Actual Total Time").map(|t| t * number("Actual Loops").unwrap_or(1.0)),
+        children: plan.get("Plans").and_then(Json::as_array).map_or(vec![], |plans| plans.iter().map(postgres_node).collect()),
+    }
+}
+
+fn mysql_node(key: &str, value: &Json) -> PlanNode {
+    let mut node = PlanNode::new(key.replace('_', " "));
+    let value = match value {
+        Json::Array(items) => {
+            node.children = items.iter().flat_map(mysql_children).collect();
+            return node;
+        },
+        value => value,
+    };
+    let text = |key: &str| value.get(key).and_then(Json::as_str);
+    if let Some(table) = text("table_name") {
+        node.label = format!("{} {}", node.label, table);
+    }
+    if let Some(access) = text("access_type") {
+        node.label = format!("{} ({})", node.label, access);
+    }
+    if let Some(index) = text("key") {
+        node.label = format!("{} using {}", node.label, index);
+    }
+    // costs are strings like "1.25"
+    let cost = |key: &str| value.get("cost_info")?.get(key)?.as_str()?.parse::<f64>().ok();
+    node.cost = cost("query_cost")
+        .or_else(|| Some(cost("read_cost")? + cost("eval_cost").unwrap_or(0.0)))
+        .or_else(|| cost("sort_cost"));
+    node.rows = value.get("rows_produced_per_join").or_else(|| value.get("rows_examined_per_scan")).and_then(Json::as_f64);
+    node.children = mysql_children(value);
+    node
+}
+
+fn mysql_children(value: &Json) -> Vec<PlanNode> {
+    match value.as_object() {
+        Some(object) => object.iter()
+            .filter(|(key, _)| MYSQL_NODES.contains(&key.as_str()))
+            .map(|(key, value)| mysql_node(key, value))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn sqlite_children(rows: &[(i64, i64, String)], parent: i64) -> Vec<PlanNode> {
+    rows.iter()
+        .filter(|(_, p, _)| *p == parent)
+        .map(|(id, _, detail)| {
+            let mut node = PlanNode::new(detail.clone());
+            node.children = sqlite_children(rows, *id);
+            node
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlanNode;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_postgres() {
+        let plan = PlanNode::from_postgres(&json!([{"Plan": {
+            "Node Type": "Hash Join", "Join Type": "Left", "Total Cost": 70.0, "Plan Rows": 100.0,
+            "Actual Total Time": 2.5, "Actual Rows": 90.0, "Actual Loops": 1.0,
+            "Plans": [
+                {"Node Type": "Seq Scan", "Relation Name": "users", "Alias": "u", "Total Cost": 20.0, "Plan Rows": 100.0,
+                 "Actual Total Time": 0.5, "Actual Rows": 90.0, "Actual Loops": 1.0},
+                {"Node Type": "Index Scan", "Index Name": "orders_pkey", "Relation Name": "orders", "Alias": "orders",
+                 "Total Cost": 8.0, "Plan Rows": 1.0, "Actual Total Time": 0.01, "Actual Rows": 1.0, "Actual Loops": 90.0},
+            ],
+        }, "Planning Time": 0.1}])).unwrap();
+        assert_eq!(plan.label, "Hash Left Join");
+        assert_eq!((plan.cost, plan.rows), (Some(70.0), Some(90.0)));
+        assert_eq!(plan.children[0].label, "Seq Scan on users u");
+        assert_eq!(plan.children[1].label, "Index Scan using orders_pkey on orders");
+        // time is per loop
+        assert!((plan.children[1].time.unwrap() - 0.9).abs() < 1e-9);
+        assert!((plan.own_weight().unwrap() - 1.1).abs() < 1e-9);
+        assert!(PlanNode::from_postgres(&json!([])).is_err());
+    }
+
+    #[test]
+    fn test_from_mysql() {
+        let plan = PlanNode::from_mysql(&json!({"query_block": {
+            "select_id": 1,
+            "cost_info": {"query_cost": "4.50"},
+            "ordering_operation": {
+                "using_filesort": true,
+                "nested_loop": [
+                    {"table": {"table_name": "u", "access_type": "ALL", "rows_examined_per_scan": 10,
+                               "rows_produced_per_join": 10, "cost_info": {"read_cost": "0.75", "eval_cost": "1.00"}}},
+                    {"table": {"table_name": "o", "access_type": "ref", "key": "user_id", "rows_produced_per_join": 10,
+                               "cost_info": {"read_cost": "1.50", "eval_cost": "1.00"}}},
+                ],
+            },
+        }})).unwrap();
+        assert_eq!((plan.label.as_str(), plan.cost), ("query block", Some(4.5)));
+        let ordering = &plan.children[0];
+        assert_eq!(ordering.label, "ordering operation");
+        let tables = &ordering.children[0];
+        assert_eq!(tables.label, "nested loop");
+        assert_eq!(tables.children.iter().map(|t| t.label.as_str()).collect::<Vec<_>>(), ["table u (ALL)", "table o (ref) using user_id"]);
+        assert_eq!((tables.children[1].cost, tables.children[1].rows), (Some(2.5), Some(10.0)));
+        assert_eq!(plan.own_weight(), Some(0.25));
+        assert_eq!(ordering.own_weight(), None);
+    }
+
+    #[test]
+    fn test_from_sqlite() {
+        let plan = PlanNode::from_sqlite(&[
+            (2, 0, "SCAN u".to_string()),
+            (5, 0, "CORRELATED SCALAR SUBQUERY 1".to_string()),
+            (9, 5, "SEARCH o USING INDEX o_user (user_id=?)".to_string()),
+        ]);
+        assert_eq!(plan.label, "QUERY PLAN");
+        assert_eq!(plan.children.len(), 2);
+        assert_eq!(plan.children[1].children[0].label, "SEARCH o USING INDEX o_user (user_id=?)");
+        assert_eq!(plan.own_weight(), None);
+    }
+}
@@ -1,18 +1,26 @@
 use crate::get_or_null;
 use crate::config::DatabaseType;
-use super::{ExecuteResult, QueryResult, Pool, TableRow, Header, ColType, Value, ColumnMeta, ColumnConstraint};
+use super::import::copy_text;
+use super::{statement_error, Conflict, ExecuteResult, Import, PlanNode, QueryResult, Pool, RowSink, Running, TableRow, Statement, Param, Page, Header, ColType, Value, Datum, ColumnMeta};
 use async_trait::async_trait;
-// use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use database_tree::{Child, Database, Schema, Table};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use database_tree::{Child, Database, Object, ObjectKind, Schema, Table};
 use futures::TryStreamExt;
 use itertools::Itertools;
-use sqlx::postgres::{PgColumn, PgPool, PgPoolOptions, PgRow};
-use sqlx::{Column as _, Row as _, TypeInfo as _};
+use rust_decimal::Decimal;
+use sqlx::postgres::types::{Oid, PgInterval, PgLTree, PgMoney, PgRange, PgTimeTz};
+use sqlx::postgres::{PgColumn, PgConnection, PgPool, PgPoolOptions, PgRow, PgValueFormat, Postgres};
+use sqlx::types::Uuid;
+use sqlx::{Column as _, Connection as _, Executor as _, Row as _, Statement as _, TypeInfo as _, ValueRef as _};
+use std::convert::{TryFrom, TryInto};
+use std::net::IpAddr;
+use std::sync::Mutex;
 use std::time::Duration;
 
 pub struct PostgresPool {
     pool: PgPool,
     page_size: u16,
+    running: Mutex<Option<i32>>, // backend pid
 }
 
 impl PostgresPool {
@@ -23,8 +31,52 @@ impl PostgresPool {
                 .acquire_timeout(Duration::from_secs(5))
                 .connect(database_url)
                 .await?,
+            running: Mutex::new(None),
         })
     }
+
+    // views, routines, triggers and sequences, leaving out what extensions bring
+    async fn get_objects(&self, database: &str) -> anyhow::Result<Vec<Object>> {
+        let query = "SELECT * FROM (
+                SELECT n.nspname AS schema, c.relname AS name, c.relkind::text AS kind, NULL AS table_name, c.oid
+                FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace WHERE c.relkind IN ('v', 'm', 'S')
+                UNION ALL
+                SELECT DISTINCT ON (n.nspname, p.proname) n.nspname, p.proname, p.prokind::text, NULL, p.oid
+                FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace WHERE p.prokind IN ('f', 'p')
+                UNION ALL
+                SELECT n.nspname, t.tgname, 't', c.relname, t.oid
+                FROM pg_trigger t JOIN pg_class c ON c.oid = t.tgrelid JOIN pg_namespace n ON n.oid = c.relnamespace WHERE NOT t.tgisinternal
+            ) o
+            WHERE current_database() = $1
+                AND schema NOT IN ('information_schema', 'pg_catalog') AND schema NOT LIKE 'pg\\_%'
+                AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.objid = o.oid AND d.deptype = 'e')
+            ORDER BY name";
+        let mut rows = sqlx::query(query).bind(database).fetch(&self.pool);
+        let mut objects = vec![];
+        while let Some(row) = rows.try_next().await? {
+            let kind = match row.try_get::<String, _>("kind")?.as_str() {
+                "v" => ObjectKind::View,
+                "m" => ObjectKind::MaterializedView,
+                "S" => ObjectKind::Sequence,
+                "p" => ObjectKind::Procedure,
+                "t" => ObjectKind::Trigger,
+                _ => ObjectKind::Function,
+            };
+            objects.push(Object {
+                name: row.try_get("name")?,
+                kind,
+                schema: row.try_get("schema")?,
+                table: row.try_get("table_name")?,
+            })
+        }
+        objects.sort_by_key(|o| o.kind);
+        Ok(objects)
+    }
+
+    async fn running(&self, conn: &mut PgConnection) -> anyhow::Result<Running<'_, i32>> {
+        let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(conn).await?;
+        Ok(Running::new(&self.running, pid))
+    }
 }
 
 pub struct Constraint {
@@ -151,61 +203,139 @@ impl TableRow for Index {
 impl Pool for PostgresPool {
     async fn execute(&self, query: &str) -> anyhow::Result<ExecuteResult> {
         let query = query.trim();
-        if query.to_uppercase().starts_with("SELECT") ||
-            query.to_uppercase().starts_with("WITH") {
-            let result = self.query(query).await?;
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        let result = query_rows(&mut conn, query).await?;
+        // a statement with result columns always has headers
+        if !result.headers.is_empty() {
             return Ok(ExecuteResult::Read {
                 headers: result.headers,
                 rows: result.rows,
                 database: Database {
                     name: "-".to_string(),
                     children: Vec::new(),
+                    loaded: true,
                 },
                 table: Table {
                     name: "-".to_string(),
                     create_time: None,
                     update_time: None,
                     engine: None,
+                    rows: None,
+                    size: None,
                     schema: None,
                 },
             });
         }
-
-        let result = sqlx::query(query).execute(&self.pool).await?;
         Ok(ExecuteResult::Write {
-            updated_rows: result.rows_affected(),
+            updated_rows: result.updated_rows,
         })
     }
 
-    async fn query(&self, query: &str) -> anyhow::Result<QueryResult> {
-        let query = query.trim();
-        if query.to_uppercase().starts_with("SELECT") ||
-            query.to_uppercase().starts_with("WITH") {
-            let mut rows = sqlx::query(query).fetch(&self.pool);
-            let mut headers = vec![];
-            let mut records = vec![];
-            while let Some(row) = rows.try_next().await? {
-                let mut new_row = vec![];
-                for column in row.columns() {
-                    let row = convert_column_value_to_string(&row, column)?;                 
-                    new_row.push(row.0);
-                    if records.len() == 0 { headers.push(row.1); };
+    async fn stream(&self, queries: &[String], sink: &mut RowSink) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let running = self.running(&mut conn).await?;
+        for (i, query) in queries.iter().enumerate() {
+            let last = i + 1 == queries.len();
+            sink.start(i, last);
+            // false once no more rows are wanted
+            let result: anyhow::Result<bool> = async {
+                let columns = (&mut *conn).prepare(query.as_str()).await?
+                    .columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>();
+                if columns.is_empty() {
+                    let result = sqlx::query(query).execute(&mut *conn).await?;
+                    sink.affected(result.rows_affected()).await?;
+                    return Ok(true);
+                }
+                if !last && is_select(query) {
+                    first_batch(&mut conn, query, columns, sink).await?;
+                    return Ok(true);
                 }
-                records.push(new_row)
+                let mut rows = sqlx::query(query).fetch(&mut *conn);
+                let mut headers = vec![];
+                let mut batch = vec![];
+                while let Some(row) = rows.try_next().await? {
+                    let mut new_row = vec![];
+                    for column in row.columns() {
+                        let row = convert_column_value_to_string(&row, column)?;
+                        new_row.push(row.0);
+                        if !sink.sent() && batch.is_empty() { headers.push(row.1); };
+                    }
+                    batch.push(new_row);
+                    if batch.len() == sink.limit {
+                        if !last {
+                            // only the last result set stays open, the statements after it need the connection.
+                            // anything but a select has to run to the end, so the rest of its rows are read
+                            sink.send(&headers, std::mem::take(&mut batch), false).await?;
+                            while rows.try_next().await?.is_some() {}
+                            return Ok(true);
+                        }
+                        running.pause();
+                        let more = sink.send(&headers, std::mem::take(&mut batch), true).await?;
+                        running.resume();
+                        if !more { return Ok(false) }
+                    }
+                }
+                if !sink.sent() && headers.is_empty() {
+                    headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
+                }
+                sink.send(&headers, batch, false).await?;
+                Ok(true)
+            }.await;
+            if !result.map_err(|e| statement_error(e, i, queries.len()))? {
+                // closing the connection stops the query, reusing it would read all the rows left
+                drop(conn.detach());
+                return Ok(());
             }
-            return Ok(QueryResult {
-                headers,
-                rows: records,
-                updated_rows: 0,
-            });
         }
+        Ok(())
+    }
 
-        let result = sqlx::query(query).execute(&self.pool).await?;
-        Ok(QueryResult {
-            headers: vec![],
-            rows: vec![],
-            updated_rows: result.rows_affected(),
-        })
+    async fn execute_statement(&self, statement: &Statement) -> anyhow::Result<u64> {
+        let result = bind_statement(statement).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn execute_transaction(&self, statements: &[Statement]) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut rows_affected = 0;
+        for statement in statements {
+            let result = bind_statement(statement).execute(&mut tx).await?;
+            // dropping tx without commit rolls it back
+            statement.check_rows_affected(result.rows_affected())?;
+            rows_affected += result.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(rows_affected)
+    }
+
+    // `COPY FROM STDIN` is the fast path
+    async fn import(&self, import: &Import, rows: Vec<Vec<Param>>) -> anyhow::Result<u64> {
+        let mut conn = self.pool.acquire().await?;
+        let result = copy_rows(&mut conn, import, &rows).await;
+        if result.is_err() {
+            // a failed copy leaves messages unread on the connection, it is not reused
+            drop(conn.detach());
+        }
+        result
+    }
+
+    async fn copy_in(&self, statement: &str, rows: &str) -> anyhow::Result<u64> {
+        let mut conn = self.pool.acquire().await?;
+        let result = async {
+            let mut copy = conn.copy_in_raw(statement).await?;
+            copy.send(rows.as_bytes()).await?;
+            Ok(copy.finish().await?)
+        }.await;
+        if result.is_err() {
+            drop(conn.detach());
+        }
+        result
+    }
+
+    async fn query(&self, query: &str) -> anyhow::Result<QueryResult> {
+        let mut conn = self.pool.acquire().await?;
+        query_rows(&mut conn, query).await
     }
 
     async fn get_databases(&self) -> anyhow::Result<Vec<Database>> {
@@ -215,133 +345,105 @@ impl Pool for PostgresPool {
             .iter()
             .map(|table| table.get(0))
             .collect::<Vec<String>>();
-        let mut list = vec![];
-        for db in databases {
-            list.push(Database::new(
-                db.clone(),
-                self.get_tables(db.clone()).await?,
-            ))
-        }
-        Ok(list)
+        Ok(databases.into_iter().map(Database::unloaded).collect())
     }
 
     async fn get_tables(&self, database: String) -> anyhow::Result<Vec<Child>> {
         let mut rows =
-            sqlx::query("SELECT * FROM information_schema.tables WHERE table_catalog = $1 AND table_type = 'BASE TABLE' and table_schema not in ('information_schema', 'pg_catalog') ORDER BY table_name")
-                .bind(database)
+            sqlx::query("SELECT t.table_schema, t.table_name, c.reltuples::bigint AS estimate, pg_total_relation_size(c.oid) AS size
+                FROM information_schema.tables t
+                LEFT JOIN pg_class c ON c.oid = to_regclass(quote_ident(t.table_schema) || '.' || quote_ident(t.table_name))
+                WHERE table_catalog = $1 AND table_type = 'BASE TABLE' and table_schema not in ('information_schema', 'pg_catalog') ORDER BY table_name")
+                .bind(&database)
                 .fetch(&self.pool);
         let mut tables = Vec::new();
         while let Some(row) = rows.try_next().await? {
+            // -1 until the table is analyzed
+            let estimate: Option<i64> = row.try_get("estimate")?;
+            let size: Option<i64> = row.try_get("size")?;
             tables.push(Table {
                 name: row.try_get("table_name")?,
                 create_time: None,
                 update_time: None,
                 engine: None,
+                rows: estimate.and_then(|n| u64::try_from(n).ok()),
+                size: size.and_then(|n| u64::try_from(n).ok()),
                 schema: row.try_get("table_schema")?,
             })
         }
+        let objects = self.get_objects(&database).await?;
         let mut schemas = vec![];
-        for (key, group) in &tables
+        for key in tables
             .iter()
-            .sorted_by(|a, b| Ord::cmp(&b.schema, &a.schema))
-            .group_by(|t| t.schema.as_ref())
+            .map(|t| &t.schema)
+            .chain(objects.iter().map(|o| &o.schema))
+            .flatten()
+            .unique()
+            .sorted_by(|a, b| Ord::cmp(b, a))
         {
-            if let Some(key) = key {
-                schemas.push(
-                    Schema {
-                        name: key.to_string(),
-                        tables: group.cloned().collect(),
-                    }
-                    .into(),
-                )
-            }
+            schemas.push(
+                Schema {
+                    name: key.to_string(),
+                    tables: tables.iter().filter(|t| t.schema.as_ref() == Some(key)).cloned().collect(),
+                    objects: objects.iter().filter(|o| o.schema.as_ref() == Some(key)).cloned().collect(),
+                }
+                .into(),
+            )
         }
         Ok(schemas)
     }
 
+    async fn get_definition(&self, _database: &Database, object: &Object) -> anyhow::Result<String> {
+        let schema = object.schema.clone().unwrap_or_else(|| "public".to_string());
+        let name = format!("{}.{}", DatabaseType::Postgres.quote_identifier(&schema), DatabaseType::Postgres.quote_identifier(&object.name));
+        let definition = match object.kind {
+            ObjectKind::View | ObjectKind::MaterializedView => {
+                let query = "SELECT pg_get_viewdef(c.oid, true) FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace WHERE n.nspname = $1 AND c.relname = $2";
+                let body: String = sqlx::query_scalar(query).bind(&schema).bind(&object.name).fetch_one(&self.pool).await?;
+                let create = if object.kind == ObjectKind::View { "CREATE OR REPLACE VIEW" } else { "CREATE MATERIALIZED VIEW" };
+                format!("{} {} AS\n{}", create, name, body)
+            },
+            ObjectKind::Function | ObjectKind::Procedure => {
+                // every overload of the name
+                let query = "SELECT string_agg(pg_get_functiondef(p.oid) || ';', E'\\n\\n' ORDER BY p.oid) FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace WHERE n.nspname = $1 AND p.proname = $2";
+                sqlx::query_scalar(query).bind(&schema).bind(&object.name).fetch_one(&self.pool).await?
+            },
+            ObjectKind::Trigger => {
+                let query = "SELECT pg_get_triggerdef(t.oid, true) || ';' FROM pg_trigger t JOIN pg_class c ON c.oid = t.tgrelid JOIN pg_namespace n ON n.oid = c.relnamespace WHERE n.nspname = $1 AND t.tgname = $2 AND c.relname = $3";
+                sqlx::query_scalar(query).bind(&schema).bind(&object.name).bind(&object.table).fetch_one(&self.pool).await?
+            },
+            ObjectKind::Sequence => {
+                let query = "SELECT data_type::text, increment_by, min_value, max_value, start_value, cache_size, cycle FROM pg_sequences WHERE schemaname = $1 AND sequencename = $2";
+                let (data_type, increment, min, max, start, cache, cycle): (String, i64, i64, i64, i64, i64, bool) =
+                    sqlx::query_as(query).bind(&schema).bind(&object.name).fetch_one(&self.pool).await?;
+                format!(
+                    "CREATE SEQUENCE {} AS {} INCREMENT BY {} MINVALUE {} MAXVALUE {} START WITH {} CACHE {}{};",
+                    name, data_type, increment, min, max, start, cache, if cycle { " CYCLE" } else { "" }
+                )
+            },
+        };
+        Ok(definition)
+    }
+
     async fn get_records(
         &self,
         database: &Database,
         table: &Table,
-        page: u16,
+        page: &Page,
         filter: Option<String>,
-        orderby: Option<String>,
     ) -> anyhow::Result<(Vec<Header>, Vec<Vec<Value>>)> {
-        let orderby = if orderby.is_none() { "".to_string() } else { format!("ORDER BY {}", orderby.unwrap()) };
-        let query = if let Some(filter) = filter.as_ref() {
-            format!(
-                r#"SELECT * FROM "{database}"."{table_schema}"."{table}" WHERE {filter} {orderby} LIMIT {limit} OFFSET {page}"#,
-                database = database.name,
-                table = table.name,
-                filter = filter,
-                orderby = orderby,
-                table_schema = table.schema.clone().unwrap_or_else(|| "public".to_string()),
-                page = page,
-                limit = self.page_size
-            )
-        } else {
-            format!(
-                r#"SELECT * FROM "{database}"."{table_schema}"."{table}" {orderby} LIMIT {limit} OFFSET {page}"#,
-                database = database.name,
-                table = table.name,
-                orderby = orderby,
-                table_schema = table.schema.clone().unwrap_or_else(|| "public".to_string()),
-                page = page,
-                limit = self.page_size
-            )
-        };
-        let mut rows = sqlx::query(query.as_str()).fetch(&self.pool);
+        let query = self.database_type().records_query(database, table, "*", page, filter.as_deref(), self.page_size);
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        let mut rows = sqlx::query(query.as_str()).fetch(&mut *conn);
         let mut headers: Vec<Header> = vec![];
         let mut records = vec![];
-        let mut json_records = None;
         while let Some(row) = rows.try_next().await? {
-            // if records.len() == 0 {
-            //     headers = row
-            //         .columns()
-            //         .iter()
-            //         .map(|column| column.name().into())
-            //         .collect();
-            // }
             let mut new_row = vec![];
             for column in row.columns() {
-                match convert_column_value_to_string(&row, column) {
-                    Ok(v) => {
-                        if records.len() == 0 { headers.push(v.1); };
-                        new_row.push(v.0)
-                    },
-                    Err(_) => {
-                        if json_records.is_none() {
-                            json_records = Some(
-                                self.get_json_records(database, table, page, filter.clone())
-                                    .await?,
-                            );
-                        }
-                        if let Some(json_records) = &json_records {
-                            if records.len() == 0 { headers.push(Header::new(column.name().to_string(), ColType::Json)); }
-                            match json_records
-                                .get(records.len())
-                                .unwrap()
-                                .get(column.name())
-                                .unwrap()
-                            {
-                                serde_json::Value::String(v) => new_row.push(Value::new(v.to_string())),
-                                serde_json::Value::Null => new_row.push(Value::default()),
-                                serde_json::Value::Array(v) => {
-                                    new_row.push(v.iter().map(|v| v.to_string()).join(",").into())
-                                }
-                                serde_json::Value::Number(v) => new_row.push(Value::new(v.to_string())),
-                                serde_json::Value::Bool(v) => new_row.push(v.to_string().into()),
-                                others => {
-                                    panic!(
-                                        "column type not implemented: `{}` {}",
-                                        column.name(),
-                                        others
-                                    )
-                                }
-                            }
-                        }
-                    }
-                }
+                let (value, header) = convert_column_value_to_string(&row, column)?;
+                if records.is_empty() { headers.push(header); };
+                new_row.push(value)
             }
             records.push(new_row)
         }
@@ -357,8 +459,12 @@ impl Pool for PostgresPool {
             .schema
             .as_ref()
             .map_or("public", |schema| schema.as_str());
+        // the declared type, with its length and element type
         let mut rows = sqlx::query(
-            "SELECT * FROM information_schema.columns WHERE table_catalog = $1 AND table_schema = $2 AND table_name = $3 ORDER BY ordinal_position"
+            "SELECT c.column_name, format_type(a.atttypid, a.atttypmod) AS data_type, c.is_nullable, c.column_default
+            FROM information_schema.columns c
+            JOIN pg_attribute a ON a.attrelid = to_regclass(quote_ident(c.table_schema) || '.' || quote_ident(c.table_name)) AND a.attname = c.column_name
+            WHERE c.table_catalog = $1 AND c.table_schema = $2 AND c.table_name = $3 ORDER BY c.ordinal_position"
         )
         .bind(&database.name).bind(table_schema).bind(&table.name)
         .fetch(&self.pool);
@@ -437,9 +543,11 @@ impl Pool for PostgresPool {
         WHERE
             tc.constraint_type = 'FOREIGN KEY'
             AND tc.table_name = $1
+            AND tc.table_schema = $2
         ",
         )
         .bind(&table.name)
+        .bind(table.pg_schema())
         .fetch(&self.pool);
         let mut constraints: Vec<Box<dyn TableRow>> = vec![];
         while let Some(row) = rows.try_next().await? {
@@ -479,12 +587,15 @@ impl Pool for PostgresPool {
             and t.relkind = 'r'
             and am.oid = i.relam
             and t.relname = $1
+            and t.relnamespace = to_regnamespace($2)
         ORDER BY
             t.relname,
-            i.relname
+            i.relname,
+            array_position(ix.indkey::int2[], a.attnum)
         ",
         )
         .bind(&table.name)
+        .bind(table.pg_schema())
         .fetch(&self.pool);
         let mut foreign_keys: Vec<Box<dyn TableRow>> = vec![];
         while let Some(row) = rows.try_next().await? {
@@ -497,10 +608,30 @@ impl Pool for PostgresPool {
         Ok(foreign_keys)
     }
 
+    async fn explain(&self, query: &str, analyze: bool) -> anyhow::Result<PlanNode> {
+        let query = format!("EXPLAIN (FORMAT JSON{}) {}", if analyze { ", ANALYZE" } else { "" }, query);
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        // analyze runs the statement, rolling back keeps what it writes out of the database
+        let mut tx = conn.begin().await?;
+        let plan: serde_json::Value = sqlx::query_scalar(&query).fetch_one(&mut tx).await?;
+        tx.rollback().await?;
+        PlanNode::from_postgres(&plan)
+    }
+
     async fn close(&self) {
         self.pool.close().await;
     }
 
+    async fn cancel(&self) -> anyhow::Result<bool> {
+        let pid = *self.running.lock().unwrap();
+        if let Some(pid) = pid {
+            sqlx::query("SELECT pg_cancel_backend($1)").bind(pid).execute(&self.pool).await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     fn database_type(&self) -> DatabaseType {
         DatabaseType::Postgres
     }
@@ -515,87 +646,22 @@ impl Pool for PostgresPool {
 }
 
 impl PostgresPool {
-    async fn get_json_records(
-        &self,
-        database: &Database,
-        table: &Table,
-        page: u16,
-        filter: Option<String>,
-    ) -> anyhow::Result<Vec<serde_json::Value>> {
-        let query = if let Some(filter) = filter {
-            format!(
-                r#"SELECT to_json("{table}".*) FROM "{database}"."{table_schema}"."{table}" WHERE {filter} LIMIT {limit} OFFSET {page}"#,
-                database = database.name,
-                table = table.name,
-                filter = filter,
-                table_schema = table.schema.clone().unwrap_or_else(|| "public".to_string()),
-                page = page,
-                limit = self.page_size
-            )
-        } else {
-            format!(
-                r#"SELECT to_json("{table}".*) FROM "{database}"."{table_schema}"."{table}" LIMIT {limit} OFFSET {page}"#,
-                database = database.name,
-                table = table.name,
-                table_schema = table.schema.clone().unwrap_or_else(|| "public".to_string()),
-                page = page,
-                limit = self.page_size
-            )
-        };
-        let json: Vec<(serde_json::Value,)> =
-            sqlx::query_as(query.as_str()).fetch_all(&self.pool).await?;
-        Ok(json.iter().map(|v| v.clone().0).collect())
-    }
-
     async fn get_column_metas(&self, table: &Table) -> anyhow::Result<Vec<ColumnMeta>> {
-        let query = r#"WITH
-        columns AS (
-          SELECT
+        let query = r#"SELECT
             s.column_name,
-            s.column_default,
-            s.is_nullable,
-            s.character_maximum_length,
             CASE
             WHEN s.data_type IN ('ARRAY', 'USER-DEFINED') THEN format_type(f.atttypid, f.atttypmod)
             ELSE s.data_type
-            END,
-            s.identity_generation
+            END AS data_type
           FROM pg_attribute f
-          JOIN pg_class c ON c.oid = f.attrelid JOIN pg_type t ON t.oid = f.atttypid
-          LEFT JOIN pg_attrdef d ON d.adrelid = c.oid AND d.adnum = f.attnum
+          JOIN pg_class c ON c.oid = f.attrelid
           LEFT JOIN pg_namespace n ON n.oid = c.relnamespace
           LEFT JOIN information_schema.columns s ON s.column_name = f.attname AND s.table_name = c.relname AND s.table_schema = n.nspname
           WHERE c.relkind = 'r'::char
           AND n.nspname = $1
           AND c.relname = $2
           AND f.attnum > 0
-          ORDER BY f.attnum
-        ),
-        column_constraints AS (
-          SELECT att.attname column_name, tmp.name, tmp.type , tmp.definition
-          FROM (
-            SELECT unnest(con.conkey) AS conkey,
-                   pg_get_constraintdef(con.oid, true) AS definition,
-                   cls.oid AS relid,
-                   con.conname AS name,
-                   con.contype AS type
-            FROM   pg_constraint con
-            JOIN   pg_namespace nsp ON nsp.oid = con.connamespace
-            JOIN   pg_class cls ON cls.oid = con.conrelid
-            WHERE  nsp.nspname = $1
-            AND    cls.relname = $2
-            AND    array_length(con.conkey, 1) = 1
-          ) tmp
-          JOIN pg_attribute att ON tmp.conkey = att.attnum AND tmp.relid = att.attrelid
-        ),
-        check_constraints AS (
-          SELECT column_name, name, definition
-          FROM   column_constraints
-          WHERE  type = 'c'
-        )
-      SELECT    columns.*, checks.name, checks.definition
-      FROM      columns
-      LEFT JOIN check_constraints checks USING (column_name);"#;
+          ORDER BY f.attnum"#;
         let schema = table.pg_schema();
         let mut rows = sqlx::query(query)
                 .bind(&schema)
@@ -604,33 +670,10 @@ impl PostgresPool {
         let mut columns: Vec<ColumnMeta> = vec![];
         while let Some(row) = rows.try_next().await? {
             let column_name: Option<String> = row.try_get("column_name")?;
-            let column_default: Option<String> = row.try_get("column_default")?;
-            let is_nullable: Option<String> = row.try_get("is_nullable")?;
-            let max_len: Option<i32> = row.try_get("character_maximum_length")?;
             let data_type: Option<String> = row.try_get("data_type")?;
-            let identity_generation: Option<String> = row.try_get("identity_generation")?;
-            let check_name: Option<String> = row.try_get("name")?;
-            let check_definition: Option<String> = row.try_get("definition")?;
-            let max_len = max_len.unwrap_or(0);
-            let column_name = column_name.map(|m| m.trim_matches('"').to_string()).unwrap_or("".to_string());
-            let mut is_auto_increment = false;
-            if column_default.as_ref().filter(|c| c.starts_with("nextval(")).is_some() {
-                is_auto_increment = true;
-            }
-            let nullable = is_nullable.map(|s| s == "YES").is_some();
-            let data_type = data_type.unwrap_or("".to_string());
-            let check = if check_name.is_some() && check_definition.is_some() {
-                Some(ColumnConstraint{ definition: check_definition.unwrap(), name: check_name.unwrap() })
-            } else { None };
             columns.push(ColumnMeta {
-                name: column_name,
-                default: column_default,
-                nullable: nullable,
-                length: max_len,
-                data_type: data_type,
-                identity_generation: identity_generation,
-                is_auto_increment: is_auto_increment,
-                check: check, 
+                name: column_name.map(|m| m.trim_matches('"').to_string()).unwrap_or("".to_string()),
+                data_type: data_type.unwrap_or("".to_string()),
             });
         };
         Ok(columns)
@@ -638,82 +681,698 @@ impl PostgresPool {
 
 }
 
+// a duplicate fails the copy, so skip and upsert copy into a temporary table and insert from there
+async fn copy_rows(conn: &mut PgConnection, import: &Import, rows: &[Vec<Param>]) -> anyhow::Result<u64> {
+    let database_type = DatabaseType::Postgres;
+    let columns = import.column_list(&database_type);
+    let mut tx = conn.begin().await?;
+    let target = match import.conflict {
+        Conflict::Fail => import.table.clone(),
+        _ => {
+            let sql = format!("CREATE TEMP TABLE gobang_import ON COMMIT DROP AS SELECT {} FROM {} WITH NO DATA", columns, import.table);
+            sqlx::query(&sql).execute(&mut tx).await?;
+            "pg_temp.gobang_import".to_string()
+        },
+    };
+    let mut copy = tx.copy_in_raw(&format!("COPY {} ({}) FROM STDIN", target, columns)).await?;
+    copy.send(copy_text(rows).as_bytes()).await?;
+    let mut rows_affected = copy.finish().await?;
+    if import.conflict != Conflict::Fail {
+        let sql = format!("INSERT INTO {} ({}) SELECT {} FROM {}{}", import.table, columns, columns, target, import.on_conflict(&database_type));
+        rows_affected = sqlx::query(&sql).execute(&mut tx).await?.rows_affected();
+    }
+    tx.commit().await?;
+    Ok(rows_affected)
+}
+
+// a statement a cursor can be declared for
+fn is_select(query: &str) -> bool {
+    let keyword = query.trim_start().split(|c: char| !c.is_ascii_alphabetic()).next().unwrap_or_default();
+    ["SELECT", "VALUES", "TABLE"].iter().any(|k| keyword.eq_ignore_ascii_case(k))
+}
+
+// the first batch of a select that is not the last statement, through a cursor so the rest of its rows is never sent
+async fn first_batch(conn: &mut PgConnection, query: &str, columns: Vec<String>, sink: &mut RowSink) -> anyhow::Result<()> {
+    // both are the same only in the first command of a transaction, so no transaction block is open.
+    // a plain &str goes as a simple query, a prepared one sets the statement time again when it runs
+    let autocommit: bool = (&mut *conn).fetch_one("SELECT transaction_timestamp() = statement_timestamp()").await?.try_get(0)?;
+    if autocommit {
+        sqlx::query("BEGIN").execute(&mut *conn).await?;
+    }
+    let rows = async {
+        // not cached, the same FETCH returns other columns for the next cursor
+        sqlx::query(&format!("DECLARE gobang_batch NO SCROLL CURSOR FOR {}", query)).persistent(false).execute(&mut *conn).await?;
+        let rows = sqlx::query(&format!("FETCH {} FROM gobang_batch", sink.limit)).persistent(false).fetch_all(&mut *conn).await?;
+        sqlx::query("CLOSE gobang_batch").execute(&mut *conn).await?;
+        anyhow::Ok(rows)
+    }.await;
+    if autocommit {
+        sqlx::query(if rows.is_ok() { "COMMIT" } else { "ROLLBACK" }).execute(&mut *conn).await?;
+    }
+    let mut headers = vec![];
+    let mut batch = vec![];
+    for row in rows? {
+        let mut new_row = vec![];
+        for column in row.columns() {
+            let row = convert_column_value_to_string(&row, column)?;
+            new_row.push(row.0);
+            if batch.is_empty() { headers.push(row.1); };
+        }
+        batch.push(new_row);
+    }
+    if headers.is_empty() {
+        headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
+    }
+    sink.send(&headers, batch, false).await?;
+    Ok(())
+}
+
+async fn query_rows(conn: &mut PgConnection, query: &str) -> anyhow::Result<QueryResult> {
+    let query = query.trim();
+    // WITH, PRAGMA, VALUES, EXPLAIN and the like return rows too, as in stream
+    let columns = (&mut *conn).prepare(query).await?
+        .columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>();
+    if !columns.is_empty() {
+        let mut rows = sqlx::query(query).fetch(&mut *conn);
+        let mut headers = vec![];
+        let mut records = vec![];
+        while let Some(row) = rows.try_next().await? {
+            let mut new_row = vec![];
+            for column in row.columns() {
+                let row = convert_column_value_to_string(&row, column)?;                 
+                new_row.push(row.0);
+                if records.len() == 0 { headers.push(row.1); };
+            }
+            records.push(new_row)
+        }
+        if headers.is_empty() {
+            headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
+        }
+        return Ok(QueryResult {
+            headers,
+            rows: records,
+            updated_rows: 0,
+        });
+    }
+
+    let result = sqlx::query(query).execute(&mut *conn).await?;
+    Ok(QueryResult {
+        headers: vec![],
+        rows: vec![],
+        updated_rows: result.rows_affected(),
+    })
+}
+
+fn bind_statement(statement: &Statement) -> sqlx::query::Query<'_, sqlx::Postgres, sqlx::postgres::PgArguments> {
+    let mut query = sqlx::query(&statement.sql);
+    for param in &statement.params {
+        query = match param {
+            Param::Null => query.bind(None::<String>),
+            Param::Int(v) => query.bind(*v),
+            Param::Float(v) => query.bind(*v),
+            Param::Bool(v) => query.bind(*v),
+            Param::Text(v) => query.bind(v.as_str()),
+            Param::Bytes(v) => query.bind(v.as_slice()),
+        };
+    }
+    query
+}
+
 fn convert_column_value_to_string(row: &PgRow, column: &PgColumn) -> anyhow::Result<(Value, Header)> {
     let column_name = column.name();
-    if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i16> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i32> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i64> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<rust_decimal::Decimal> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<&[u8]> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((value.map_or(Value::default(), |values| {
-            format!(
-                "\\x{}",
-                values
-                    .iter()
-                    .map(|v| format!("{:02x}", v))
-                    .collect::<String>()
-            ).into()
-        }), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<NaiveDate> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: String = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((Value::new(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<chrono::DateTime<chrono::Utc>> = value;
-        let header = Header::new(column_name.to_string(), ColType::Date);
-        let t = value.map(|t| t.to_string().strip_suffix(" UTC").unwrap().to_string());
-        Ok((get_or_null!(t), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<chrono::DateTime<chrono::Local>> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<NaiveDateTime> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<NaiveDate> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<NaiveTime> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<serde_json::Value> = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get::<Option<bool>, _>(column_name) {
-        let value: Option<bool> = value;
-        let header = Header::new(column_name.to_string(), ColType::Boolean);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<Vec<String>> = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((value.map_or(Value::default(), |v| Value::new(v.join(","))), header))
+    // by position, a join can return two columns with the same name
+    let index = column.ordinal();
+    let header = |typ| Header::new(column_name.to_string(), typ);
+    if is::<bool>(column) {
+        let value: Option<bool> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Bool), header(ColType::Boolean)))
+    } else if is::<i8>(column) {
+        // the one-byte "char" type
+        let value: Option<i8> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::Text((v as u8 as char).to_string()).into()), header(ColType::VarChar)))
+    } else if is::<i16>(column) {
+        let value: Option<i16> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Int), header(ColType::Int)))
+    } else if is::<i32>(column) {
+        let value: Option<i32> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Int), header(ColType::Int)))
+    } else if is::<i64>(column) {
+        let value: Option<i64> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Int), header(ColType::Int)))
+    } else if is::<Oid>(column) {
+        let value: Option<Oid> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::UInt(v.0.into()).into()), header(ColType::Int)))
+    } else if is::<f32>(column) {
+        let value: Option<f32> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Float), header(ColType::Float)))
+    } else if is::<f64>(column) {
+        let value: Option<f64> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Float), header(ColType::Float)))
+    } else if is::<Decimal>(column) {
+        // from the text, NaN, infinity and more than 28 digits do not fit a Decimal and stay text.
+        // sqlx would pad the scale to whole base-10000 digits and panics on too many digits
+        let raw = row.try_get_raw(index)?;
+        if raw.is_null() {
+            return Ok((Value::default(), header(ColType::Float)))
+        }
+        let bytes = raw.as_bytes().unwrap_or_default();
+        let text = match raw.format() {
+            PgValueFormat::Text => Some(String::from_utf8_lossy(bytes).to_string()),
+            PgValueFormat::Binary => decode_binary("NUMERIC", bytes),
+        };
+        let text = text.ok_or_else(|| anyhow::anyhow!("cannot decode the numeric value of {}", column.name()))?;
+        match Decimal::from_str_exact(&text) {
+            Ok(value) => Ok((Datum::Decimal(value).into(), header(ColType::Float))),
+            Err(_) => Ok((Datum::Text(text).into(), header(ColType::VarChar))),
+        }
+    } else if is::<PgMoney>(column) {
+        let value: Option<PgMoney> = row.try_get(index)?;
+        // assumes the server's lc_monetary has two fraction digits
+        Ok((value.map_or(Value::default(), |v| Datum::Decimal(v.to_decimal(2)).into()), header(ColType::Float)))
+    } else if is::<String>(column) {
+        let value: Option<String> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Text), header(ColType::VarChar)))
+    } else if is::<&[u8]>(column) {
+        let value: Option<&[u8]> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::Bytes(v.to_vec()).into()), header(ColType::Unknown)))
+    } else if is::<Uuid>(column) {
+        let value: Option<Uuid> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::Text(v.to_string()).into()), header(ColType::VarChar)))
+    } else if is::<NaiveDate>(column) {
+        let value: Option<NaiveDate> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Date), header(ColType::Date)))
+    } else if is::<NaiveTime>(column) {
+        let value: Option<NaiveTime> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Time), header(ColType::Date)))
+    } else if is::<PgTimeTz>(column) {
+        let value: Option<PgTimeTz> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::Text(format!("{}{}", v.time, v.offset)).into()), header(ColType::Date)))
+    } else if is::<NaiveDateTime>(column) {
+        let value: Option<NaiveDateTime> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::DateTime), header(ColType::Date)))
+    } else if is::<DateTime<Utc>>(column) {
+        let value: Option<DateTime<Utc>> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Timestamp), header(ColType::Date)))
+    } else if is::<PgInterval>(column) {
+        let value: Option<PgInterval> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::Text(interval_to_string(&v)).into()), header(ColType::VarChar)))
+    } else if is::<serde_json::Value>(column) {
+        let value: Option<serde_json::Value> = row.try_get(index)?;
+        Ok((get_or_null!(value, Datum::Json), header(ColType::Json)))
+    } else if is::<PgLTree>(column) {
+        let value: Option<PgLTree> = row.try_get(index)?;
+        Ok((value.map_or(Value::default(), |v| Datum::Text(v.to_string()).into()), header(ColType::VarChar)))
+    } else if let Some(value) = convert_array(row, column)? {
+        Ok((value, header(ColType::VarChar)))
+    } else if let Some(value) = convert_range(row, column)? {
+        Ok((value, header(ColType::VarChar)))
     } else {
-        anyhow::bail!(
-            "column type not implemented: `{}` {}",
-            column_name,
-            column.type_info().clone().name()
-        )
+        // enums, domains over unknown types, extensions
+        let value = row.try_get_raw(index)?;
+        if value.is_null() {
+            return Ok((Value::default(), header(ColType::Unknown)))
+        }
+        let typ = column.type_info().name();
+        let bytes = value.as_bytes().unwrap_or_default();
+        if value.format() == PgValueFormat::Text {
+            Ok((Datum::Text(String::from_utf8_lossy(bytes).to_string()).into(), header(ColType::VarChar)))
+        } else if let Some(text) = decode_binary(typ, bytes) {
+            Ok((Datum::Text(text).into(), header(ColType::VarChar)))
+        } else {
+            // the binary form of an enum or a text-like type is its text
+            match std::str::from_utf8(bytes) {
+                Ok(text) if !text.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+                    Ok((Datum::Text(text.to_string()).into(), header(ColType::VarChar)))
+                },
+                _ => Ok((Datum::Bytes(bytes.to_vec()).into(), header(ColType::Unknown))),
+            }
+        }
+    }
+}
+
+macro_rules! try_array {
+    ($row:expr, $column:expr, $typ:ty, $datum:path) => {
+        if is::<Vec<Option<$typ>>>($column) {
+            let value: Option<Vec<Option<$typ>>> = $row.try_get($column.ordinal())?;
+            return Ok(Some(value.map_or(Value::default(), |values| {
+                Datum::Array(values.into_iter().map(|v| v.map_or(Datum::Null, |v| $datum(v.into()))).collect()).into()
+            })))
+        }
+    };
+}
+
+fn convert_array(row: &PgRow, column: &PgColumn) -> anyhow::Result<Option<Value>> {
+    try_array!(row, column, bool, Datum::Bool);
+    try_array!(row, column, i16, Datum::Int);
+    try_array!(row, column, i32, Datum::Int);
+    try_array!(row, column, i64, Datum::Int);
+    try_array!(row, column, f32, Datum::Float);
+    try_array!(row, column, f64, Datum::Float);
+    try_array!(row, column, Decimal, Datum::Decimal);
+    try_array!(row, column, String, Datum::Text);
+    try_array!(row, column, NaiveDate, Datum::Date);
+    try_array!(row, column, NaiveTime, Datum::Time);
+    try_array!(row, column, NaiveDateTime, Datum::DateTime);
+    try_array!(row, column, DateTime<Utc>, Datum::Timestamp);
+    try_array!(row, column, serde_json::Value, Datum::Json);
+    if is::<Vec<Option<Uuid>>>(column) {
+        let value: Option<Vec<Option<Uuid>>> = row.try_get(column.ordinal())?;
+        return Ok(Some(value.map_or(Value::default(), |values| {
+            Datum::Array(values.into_iter().map(|v| v.map_or(Datum::Null, |v| Datum::Text(v.to_string()))).collect()).into()
+        })))
+    }
+    Ok(None)
+}
+
+macro_rules! try_range {
+    ($row:expr, $column:expr, $empty:expr, $typ:ty) => {
+        if is::<PgRange<$typ>>($column) {
+            let value: Option<PgRange<$typ>> = $row.try_get($column.ordinal())?;
+            let text = |v: PgRange<$typ>| if $empty { "empty".to_string() } else { v.to_string() };
+            return Ok(Some(value.map_or(Value::default(), |v| Datum::Text(text(v)).into())))
+        }
+    };
+}
+
+fn convert_range(row: &PgRow, column: &PgColumn) -> anyhow::Result<Option<Value>> {
+    // sqlx decodes an empty range as `(,)`, the flags byte tells them apart
+    let raw = row.try_get_raw(column.ordinal())?;
+    let empty = !raw.is_null() && raw.format() == PgValueFormat::Binary
+        && raw.as_bytes().ok().and_then(|b| b.first()).is_some_and(|flags| flags & 0x01 != 0);
+    try_range!(row, column, empty, i32);
+    try_range!(row, column, empty, i64);
+    try_range!(row, column, empty, Decimal);
+    try_range!(row, column, empty, NaiveDate);
+    try_range!(row, column, empty, NaiveDateTime);
+    try_range!(row, column, empty, DateTime<Utc>);
+    Ok(None)
+}
+
+// pick the decoder by the column type, a null matches any type
+fn is<T: sqlx::Type<Postgres>>(column: &PgColumn) -> bool {
+    T::compatible(column.type_info())
+}
+
+// the way postgres prints an interval with the default intervalstyle
+fn interval_to_string(interval: &PgInterval) -> String {
+    let plural = |n: i32, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    let mut parts = vec![];
+    if interval.months / 12 != 0 {
+        parts.push(plural(interval.months / 12, "year"));
+    }
+    if interval.months % 12 != 0 {
+        parts.push(plural(interval.months % 12, "mon"));
+    }
+    if interval.days != 0 {
+        parts.push(plural(interval.days, "day"));
+    }
+    if interval.microseconds != 0 || parts.is_empty() {
+        let sign = if interval.microseconds < 0 { "-" } else { "" };
+        let micros = interval.microseconds.unsigned_abs();
+        let secs = micros / 1_000_000;
+        let mut time = format!("{}{:02}:{:02}:{:02}", sign, secs / 3600, secs / 60 % 60, secs % 60);
+        if !micros.is_multiple_of(1_000_000) {
+            time.push_str(format!(".{:06}", micros % 1_000_000).trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    parts.join(" ")
+}
+
+// binary formats of the built-in types sqlx has no decoder for without extra features
+fn decode_binary(typ: &str, bytes: &[u8]) -> Option<String> {
+    match typ {
+        "INET" | "CIDR" => {
+            // family, bits, is_cidr, address length, address
+            let (family, bits, address) = (*bytes.first()?, *bytes.get(1)?, bytes.get(4..)?);
+            let (ip, max) = match (family, address.len()) {
+                (2, 4) => (IpAddr::from(<[u8; 4]>::try_from(address).ok()?), 32),
+                (3, 16) => (IpAddr::from(<[u8; 16]>::try_from(address).ok()?), 128),
+                _ => return None,
+            };
+            if typ == "INET" && bits == max {
+                Some(ip.to_string())
+            } else {
+                Some(format!("{}/{}", ip, bits))
+            }
+        },
+        "MACADDR" | "MACADDR8" => Some(bytes.iter().map(|b| format!("{:02x}", b)).join(":")),
+        "BIT" | "VARBIT" => {
+            let len = i32::from_be_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+            let bits = bytes.get(4..)?;
+            Some((0..len).map(|i| if bits.get(i / 8)? & (0x80 >> (i % 8)) == 0 { Some('0') } else { Some('1') }).collect::<Option<String>>()?)
+        },
+        "NUMERIC" => {
+            // digit count, weight of the first base-10000 digit, sign, display scale, digits
+            let word = |i: usize| Some(u16::from_be_bytes(bytes.get(i..i + 2)?.try_into().ok()?));
+            let (count, weight, sign, scale) = (word(0)? as i64, word(2)? as i16 as i64, word(4)?, word(6)? as i64);
+            match sign {
+                0xC000 => return Some("NaN".to_string()),
+                0xD000 => return Some("Infinity".to_string()),
+                0xF000 => return Some("-Infinity".to_string()),
+                _ => {},
+            }
+            let digit = |i: i64| if (0..count).contains(&i) { word(8 + 2 * i as usize) } else { Some(0) };
+            let mut text = if sign == 0x4000 { "-".to_string() } else { String::new() };
+            if weight < 0 {
+                text.push('0');
+            } else {
+                text.push_str(&digit(0)?.to_string());
+                for i in 1..=weight {
+                    text.push_str(&format!("{:04}", digit(i)?));
+                }
+            }
+            if scale > 0 {
+                let fraction = (1..=(scale + 3) / 4).map(|k| digit(weight + k).map(|d| format!("{:04}", d))).collect::<Option<String>>()?;
+                text.push('.');
+                text.push_str(&fraction[..scale as usize]);
+            }
+            Some(text)
+        },
+        "POINT" => {
+            let x = f64::from_be_bytes(bytes.get(..8)?.try_into().ok()?);
+            let y = f64::from_be_bytes(bytes.get(8..16)?.try_into().ok()?);
+            Some(format!("({},{})", x, y))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_to_string() {
+        let interval = |months, days, microseconds| interval_to_string(&PgInterval { months, days, microseconds });
+        assert_eq!(interval(14, 3, 14_706_780_000), "1 year 2 mons 3 days 04:05:06.78");
+        assert_eq!(interval(0, -1, -1_000_000), "-1 days -00:00:01");
+        assert_eq!(interval(1, 1, 0), "1 mon 1 day");
+        assert_eq!(interval(0, 0, 0), "00:00:00");
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        assert_eq!(decode_binary("INET", &[2, 32, 0, 4, 192, 168, 0, 1]).as_deref(), Some("192.168.0.1"));
+        assert_eq!(decode_binary("CIDR", &[2, 8, 1, 4, 10, 0, 0, 0]).as_deref(), Some("10.0.0.0/8"));
+        assert_eq!(decode_binary("MACADDR", &[8, 0, 0x2b, 1, 2, 3]).as_deref(), Some("08:00:2b:01:02:03"));
+        // 12345678901234567890123456789.5, too many digits for a Decimal
+        let numeric = [0, 9, 0, 7, 0, 0, 0, 1, 0, 1, 9, 0x29, 0x1a, 0x85, 0, 0x7b, 0x11, 0xd7, 0x22, 0xc5, 9, 0x29, 0x1a, 0x85, 0x13, 0x88];
+        assert_eq!(decode_binary("NUMERIC", &numeric).as_deref(), Some("12345678901234567890123456789.5"));
+        // -0.0012
+        assert_eq!(decode_binary("NUMERIC", &[0, 1, 0xff, 0xff, 0x40, 0, 0, 4, 0, 12]).as_deref(), Some("-0.0012"));
+        assert_eq!(decode_binary("NUMERIC", &[0, 0, 0, 0, 0xc0, 0, 0, 0]).as_deref(), Some("NaN"));
+        assert_eq!(decode_binary("VARBIT", &[0, 0, 0, 10, 0b0101_0000, 0b1100_0000]).as_deref(), Some("0101000011"));
+        assert_eq!(decode_binary("INET", &[2, 32]), None);
+        assert_eq!(decode_binary("TSVECTOR", &[0]), None);
+    }
+
+    // needs a server: GOBANG_TEST_POSTGRES=postgres://user@localhost/db cargo test -- --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_ddl_round_trip() {
+        use crate::sql::token::tokenizer::Tokenizer;
+
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        let run = |script: String| {
+            let pool = &pool;
+            async move {
+                let tokenizer = Tokenizer::try_from(DatabaseType::Postgres).unwrap();
+                for range in tokenizer.statements(&script) {
+                    let statement = script[range].to_string();
+                    pool.execute(&statement).await.map_err(|e| anyhow::anyhow!("{}: {}", statement, e)).unwrap();
+                }
+            }
+        };
+        run("DROP SCHEMA IF EXISTS ddl_test CASCADE;
+            CREATE SCHEMA ddl_test;
+            CREATE FUNCTION ddl_test.touch() RETURNS trigger LANGUAGE plpgsql AS 'BEGIN RETURN NEW; END';
+            CREATE TABLE ddl_test.customers (id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY, email text COLLATE \"C\" UNIQUE);
+            CREATE TABLE ddl_test.events (
+                id serial,
+                customer_id bigint NOT NULL REFERENCES ddl_test.customers ON DELETE CASCADE,
+                kind varchar(20) DEFAULT 'click' CHECK (kind <> ''),
+                amount numeric(10, 2),
+                doubled numeric GENERATED ALWAYS AS (amount * 2) STORED,
+                created_at timestamptz NOT NULL DEFAULT now(),
+                PRIMARY KEY (id, created_at),
+                CONSTRAINT positive CHECK (amount > 0) NOT VALID
+            ) PARTITION BY RANGE (created_at);
+            CREATE TABLE ddl_test.events_2024 PARTITION OF ddl_test.events FOR VALUES FROM ('2024-01-01') TO ('2025-01-01');
+            CREATE TABLE ddl_test.events_rest PARTITION OF ddl_test.events DEFAULT;
+            CREATE INDEX events_kind ON ddl_test.events (kind) WHERE kind IS NOT NULL;
+            CREATE TRIGGER touch BEFORE UPDATE ON ddl_test.events FOR EACH ROW EXECUTE FUNCTION ddl_test.touch();
+            ALTER TABLE ddl_test.events ENABLE ROW LEVEL SECURITY;
+            CREATE POLICY own ON ddl_test.events AS RESTRICTIVE FOR SELECT TO PUBLIC USING (customer_id > 0);
+            COMMENT ON TABLE ddl_test.events IS 'what customers did';
+            COMMENT ON COLUMN ddl_test.events.kind IS 'it''s a kind';
+            CREATE TABLE ddl_test.notes (body text);
+            CREATE UNLOGGED TABLE ddl_test.pinned (rank int) INHERITS (ddl_test.notes) WITH (fillfactor = 70);".to_string()).await;
+
+        // customers last, dropping it takes the foreign key of events
+        for name in ["events", "events_2024", "pinned", "customers"] {
+            let database = Database::new("-".to_string(), vec![]);
+            let table = Table {
+                name: name.to_string(),
+                create_time: None,
+                update_time: None,
+                engine: None,
+                rows: None,
+                size: None,
+                schema: Some("ddl_test".to_string()),
+            };
+            let ddl = DatabaseType::Postgres.show_schema(&pool, &database, &table).await.unwrap();
+            run(format!("DROP TABLE ddl_test.{} CASCADE", name)).await;
+            run(ddl.clone()).await;
+            assert_eq!(DatabaseType::Postgres.show_schema(&pool, &database, &table).await.unwrap(), ddl);
+        }
+        // the partitions got the index of the parent back
+        let indexes = pool.execute("SELECT count(*) FROM pg_indexes WHERE schemaname = 'ddl_test' AND indexdef LIKE '%(kind)%'").await.unwrap();
+        match indexes {
+            ExecuteResult::Read { rows, .. } => assert_eq!(rows[0][0].data, "3"),
+            _ => panic!("not a read"),
+        }
+        run("DROP SCHEMA ddl_test CASCADE".to_string()).await;
+    }
+
+    // needs a server, like test_ddl_round_trip
+    #[tokio::test]
+    #[ignore]
+    async fn test_import_copy() {
+        use crate::database::Import;
+
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        for sql in [
+            "DROP SCHEMA IF EXISTS import_test CASCADE",
+            "CREATE SCHEMA import_test",
+            "CREATE TABLE import_test.users (id serial PRIMARY KEY, name text NOT NULL DEFAULT 'x', avatar bytea, active boolean)",
+        ] {
+            pool.execute(sql).await.unwrap();
+        }
+        let mut import = Import {
+            table: "\"import_test\".\"users\"".to_string(),
+            columns: vec!["id".to_string(), "avatar".to_string(), "active".to_string()],
+            key: vec!["id".to_string()],
+            conflict: Conflict::Fail,
+        };
+        let rows = || vec![
+            vec![Param::Text("1".to_string()), Param::Bytes(vec![0xde, 0xad]), Param::Bool(true)],
+            vec![Param::Int(2), Param::Null, Param::Text("f".to_string())],
+        ];
+        assert_eq!(pool.import(&import, rows()).await.unwrap(), 2);
+        assert!(pool.import(&import, rows()).await.is_err());
+        import.conflict = Conflict::Skip;
+        // the temporary table of the first batch is gone with its transaction
+        for _ in 0..2 {
+            assert_eq!(pool.import(&import, rows()).await.unwrap(), 0);
+        }
+        import.conflict = Conflict::Upsert;
+        import.columns = vec!["id".to_string(), "name".to_string(), "active".to_string()];
+        let rows = vec![vec![Param::Int(2), Param::Text("tab\there".to_string()), Param::Null]];
+        assert_eq!(pool.import(&import, rows).await.unwrap(), 1);
+        let result = pool.query("SELECT id::text, name, encode(avatar, 'hex'), active::text FROM import_test.users ORDER BY id").await.unwrap();
+        let rows = result.rows.iter().map(|row| row.iter().map(|v| v.data.as_str()).collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![vec!["1", "x", "dead", "true"], vec!["2", "tab\there", "NULL", "NULL"]]);
+        pool.execute("DROP SCHEMA import_test CASCADE").await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_numeric_text() {
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        let result = pool.query("SELECT 'NaN'::numeric, 123456789012345678901234567890.125::numeric, -0.00012::numeric, 1.50::numeric").await.unwrap();
+        let row = result.rows[0].iter().map(|v| v.data.as_str()).collect::<Vec<_>>();
+        assert_eq!(row, vec!["NaN", "123456789012345678901234567890.125", "-0.00012", "1.50"]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_definition_quoted_name() {
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        for sql in [
+            "DROP SCHEMA IF EXISTS \"definition\"\"test\" CASCADE",
+            "CREATE SCHEMA \"definition\"\"test\"",
+            "CREATE VIEW \"definition\"\"test\".\"say \"\"hi\"\"\" AS SELECT 1 AS n",
+        ] {
+            pool.execute(sql).await.unwrap();
+        }
+        let object = Object { name: "say \"hi\"".to_string(), kind: ObjectKind::View, schema: Some("definition\"test".to_string()), table: None };
+        let definition = pool.get_definition(&Database::new("postgres".to_string(), vec![]), &object).await.unwrap();
+        assert!(definition.starts_with("CREATE OR REPLACE VIEW \"definition\"\"test\".\"say \"\"hi\"\"\" AS"), "{}", definition);
+        // the definition runs as is
+        pool.execute(&definition).await.unwrap();
+        pool.execute("DROP SCHEMA \"definition\"\"test\" CASCADE").await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_delete_without_key() {
+        use crate::database::{Page, RowKeys};
+
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        for sql in [
+            "DROP SCHEMA IF EXISTS keyless_test CASCADE",
+            "CREATE SCHEMA keyless_test",
+            "CREATE TABLE keyless_test.events (doc json, at point, body xml, note text)",
+            "INSERT INTO keyless_test.events VALUES ('{\"a\": [1, 2]}', '(1,2)', '<a>1</a>', 'x'), ('{\"a\": 3}', '(1,2)', '<a>1</a>', 'x')",
+        ] {
+            pool.execute(sql).await.unwrap();
+        }
+        let database = Database::new("postgres".to_string(), vec![]);
+        let table = Table { name: "events".to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: Some("keyless_test".to_string()) };
+        let (headers, rows) = pool.get_records(&database, &table, &Page::new(None, vec![]), Some("note = 'x'".to_string())).await.unwrap();
+        let column_types = DatabaseType::Postgres.column_types(&pool, &database, &table).await.unwrap();
+        let keys = RowKeys { headers, rows: vec![rows[0].clone()], exact: false };
+        let statement = DatabaseType::Postgres.delete_rows_by_column(&database, &table, &keys, &column_types);
+        assert_eq!(pool.execute_transaction(&[statement]).await.unwrap(), 1);
+        let result = pool.query("SELECT doc::text FROM keyless_test.events").await.unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_ne!(result.rows[0][0].data, rows[0][0].data);
+        pool.execute("DROP SCHEMA keyless_test CASCADE").await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_stream_earlier_select() {
+        use crate::event::{Event, Store};
+        use tokio::sync::mpsc;
+
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        for queries in [
+            vec!["SELECT n FROM generate_series(1, 1000000) n", "SELECT 'next' AS name"],
+            // inside a transaction block the cursor leaves it open
+            vec!["BEGIN", "SELECT n FROM generate_series(1, 1000000) n", "SELECT 'next' AS name", "ROLLBACK"],
+        ] {
+            let queries = queries.into_iter().map(String::from).collect::<Vec<_>>();
+            let (tx, mut rx) = mpsc::channel(8);
+            let (mut sink, _more) = RowSink::new(2, Store::new(tx));
+            pool.stream(&queries, &mut sink).await.unwrap();
+            let mut batches = vec![];
+            while let Ok(event) = rx.try_recv() {
+                if let Event::QueryRows(batch) = event { batches.push(batch) }
+            }
+            let batch = batches.iter().find(|batch| batch.rows.len() == 2).unwrap();
+            assert_eq!(batch.headers[0].name, "n");
+            assert_eq!(batch.rows.iter().map(|row| row[0].data.as_str()).collect::<Vec<_>>(), ["1", "2"]);
+            assert!(!batch.more);
+            let batch = batches.iter().find(|batch| batch.headers.first().is_some_and(|h| h.name == "name")).unwrap();
+            assert_eq!(batch.rows[0][0].data, "next");
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_query_table() {
+        use crate::database::query_table;
+
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let pool: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        for sql in [
+            "DROP SCHEMA IF EXISTS yank_test CASCADE",
+            "CREATE SCHEMA yank_test",
+            "CREATE TABLE yank_test.\"Items\" (shop int, id int, name text, PRIMARY KEY (shop, id))",
+            "CREATE TABLE public.yank_test_notes (note text)",
+        ] {
+            pool.execute(sql).await.unwrap();
+        }
+        let table = query_table(&pool, "select name from yank_test.\"Items\" where id > 1").await.unwrap().unwrap();
+        assert_eq!(table.path, "\"yank_test\".\"Items\"");
+        assert_eq!(table.key, vec!["shop", "id"]);
+        // found on the search path, without a key
+        let table = query_table(&pool, "SELECT * FROM Yank_Test_Notes").await.unwrap().unwrap();
+        assert_eq!(table.path, "\"public\".\"yank_test_notes\"");
+        assert!(table.key.is_empty());
+        assert_eq!(query_table(&pool, "select * from missing_table").await.unwrap(), None);
+        pool.execute("DROP TABLE public.yank_test_notes").await.unwrap();
+        pool.execute("DROP SCHEMA yank_test CASCADE").await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_dump_restore() {
+        use crate::database::{dump_database, restore_database};
+        use crate::event::Store;
+        use tokio::sync::mpsc;
+
+        let url = std::env::var("GOBANG_TEST_POSTGRES").expect("GOBANG_TEST_POSTGRES is not set");
+        let server: Box<dyn Pool> = Box::new(PostgresPool::new(&url, 100).await.unwrap());
+        for sql in [
+            "DROP DATABASE IF EXISTS gobang_dump_source",
+            "DROP DATABASE IF EXISTS gobang_dump_target",
+            "CREATE DATABASE gobang_dump_source",
+            "CREATE DATABASE gobang_dump_target",
+        ] {
+            server.execute(sql).await.unwrap();
+        }
+        let database_url = |name: &str| format!("{}/{}", url.rsplit_once('/').unwrap().0, name);
+        let source: Box<dyn Pool> = Box::new(PostgresPool::new(&database_url("gobang_dump_source"), 100).await.unwrap());
+        for sql in [
+            "CREATE SCHEMA app",
+            "CREATE TABLE app.users (id serial PRIMARY KEY, name text NOT NULL, balance numeric(12, 2), tags text[], meta jsonb,
+                created timestamptz, avatar bytea, best_post integer)",
+            "CREATE TABLE app.posts (id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY, user_id integer REFERENCES app.users (id),
+                parent_id integer REFERENCES app.posts (id), title text, words integer GENERATED ALWAYS AS (length(title)) STORED)",
+            "ALTER TABLE app.users ADD FOREIGN KEY (best_post) REFERENCES app.posts (id)",
+            "CREATE TABLE app.events (at date NOT NULL, name text) PARTITION BY RANGE (at)",
+            "CREATE TABLE app.events_2024 PARTITION OF app.events FOR VALUES FROM ('2024-01-01') TO ('2025-01-01')",
+            "INSERT INTO app.users (name, balance, tags, meta, created, avatar) VALUES
+                ('tab\there', 10.50, '{a,\"b c\"}', '{\"k\": [1, 2]}', '2024-05-01 10:00:00+00', '\\x00ff'),
+                ('back\\slash', NULL, NULL, NULL, NULL, NULL)",
+            "INSERT INTO app.posts (user_id, title) VALUES (1, 'first'), (2, NULL)",
+            "UPDATE app.posts SET parent_id = 1 WHERE id = 2",
+            "UPDATE app.users SET best_post = 2 WHERE id = 1",
+            "INSERT INTO app.events VALUES ('2024-03-01', 'launch')",
+        ] {
+            source.execute(sql).await.unwrap();
+        }
+        let path = std::env::temp_dir().join(format!("gobang_dump_{}.sql", std::process::id()));
+        let (tx, _rx) = mpsc::channel(64);
+        let store = Store::new(tx);
+        // the partition comes with the partitioned table
+        assert_eq!(dump_database(&source, "gobang_dump_source", &path, &store).await.unwrap(), (3, 5));
+
+        let target: Box<dyn Pool> = Box::new(PostgresPool::new(&database_url("gobang_dump_target"), 100).await.unwrap());
+        restore_database(&target, &path, &store).await.unwrap();
+        let rows = |result: QueryResult| result.rows.iter().map(|row| row.iter().map(|v| v.data.clone()).collect::<Vec<_>>()).collect::<Vec<_>>();
+        for query in [
+            "SELECT id, name, balance::text, tags::text, meta::text, created::text, encode(avatar, 'hex'), best_post FROM app.users ORDER BY id",
+            "SELECT id, user_id, parent_id, title, words FROM app.posts ORDER BY id",
+            "SELECT tableoid::regclass::text, at::text, name FROM app.events",
+            "SELECT nextval('app.users_id_seq'), (SELECT count(*) FROM pg_constraint WHERE contype = 'f')",
+        ] {
+            assert_eq!(rows(target.query(query).await.unwrap()), rows(source.query(query).await.unwrap()));
+        }
+        source.close().await;
+        target.close().await;
+        std::fs::remove_file(&path).unwrap();
+        for sql in ["DROP DATABASE gobang_dump_source WITH (FORCE)", "DROP DATABASE gobang_dump_target WITH (FORCE)"] {
+            server.execute(sql).await.unwrap();
+        }
     }
 }
@@ -1,16 +1,29 @@
-use crate::get_or_null;
 use crate::config::DatabaseType;
-use super::{ExecuteResult, QueryResult, Pool, TableRow, Header, ColType, Value};
+use super::{statement_error, ExecuteResult, PlanNode, QueryResult, Pool, RowSink, Running, TableRow, Statement, Param, Page, Header, ColType, Value, Datum};
 use async_trait::async_trait;
-use database_tree::{Child, Database, Table};
+use database_tree::{Child, Database, Object, ObjectKind, Table};
 use futures::TryStreamExt;
-use sqlx::sqlite::{SqliteColumn, SqlitePoolOptions, SqliteRow};
-use sqlx::{Column as _, Row as _, TypeInfo as _};
+use sqlx::decode::Decode;
+use sqlx::sqlite::{Sqlite, SqliteColumn, SqliteConnection, SqlitePoolOptions, SqliteRow, SqliteValueRef};
+use sqlx::{Column as _, Executor as _, Row as _, Statement as _, TypeInfo as _, ValueRef as _};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 
 pub struct SqlitePool {
     pool: sqlx::sqlite::SqlitePool,
     page_size: u16,
+    running: Mutex<Option<()>>, // while a query can be canceled
+    interrupted: &'static AtomicBool, // read by the progress handler of the connections
+}
+
+// a non-zero return interrupts the statement the connection steps
+extern "C" fn progress_handler(interrupted: *mut c_void) -> c_int {
+    let interrupted = unsafe { &*(interrupted as *const AtomicBool) };
+    interrupted.load(Ordering::SeqCst) as c_int
 }
 
 impl SqlitePool {
@@ -21,8 +34,31 @@ impl SqlitePool {
                 .acquire_timeout(Duration::from_secs(5))
                 .connect(database_url)
                 .await?,
+            running: Mutex::new(None),
+            // never freed, a connection may outlive the pool on its worker thread
+            interrupted: Box::leak(Box::new(AtomicBool::new(false))),
         })
     }
+
+    // the handler is set on the locked connection, so it goes with the connection
+    async fn running(&self, conn: &mut SqliteConnection) -> anyhow::Result<Running<'_, ()>> {
+        let mut handle = conn.lock_handle().await?;
+        let interrupted = self.interrupted as *const AtomicBool as *mut c_void;
+        unsafe { libsqlite3_sys::sqlite3_progress_handler(handle.as_raw_handle().as_ptr(), 1000, Some(progress_handler), interrupted) };
+        // a cancel that came after the last query finished
+        self.interrupted.store(false, Ordering::SeqCst);
+        Ok(Running::new(&self.running, ()))
+    }
+
+    // sqlx steps an interrupted statement once more, which runs it again from the start.
+    // the flag stays set until the connection is free, so that run stops at once as well
+    async fn settle<T>(&self, conn: &mut SqliteConnection, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        if self.interrupted.load(Ordering::SeqCst) {
+            conn.lock_handle().await?;
+            self.interrupted.store(false, Ordering::SeqCst);
+        }
+        result
+    }
 }
 
 pub struct Constraint {
@@ -153,59 +189,108 @@ impl TableRow for Index {
 impl Pool for SqlitePool {
     async fn execute(&self, query: &str) -> anyhow::Result<ExecuteResult> {
         let query = query.trim();
-        if query.to_uppercase().starts_with("SELECT") {
-            let result = self.query(query).await?;
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        let result = query_rows(&mut conn, query).await;
+        let result = self.settle(&mut conn, result).await?;
+        // a statement with result columns always has headers
+        if !result.headers.is_empty() {
             return Ok(ExecuteResult::Read {
                 headers: result.headers,
                 rows: result.rows,
                 database: Database {
                     name: "-".to_string(),
                     children: Vec::new(),
+                    loaded: true,
                 },
                 table: Table {
                     name: "-".to_string(),
                     create_time: None,
                     update_time: None,
                     engine: None,
+                    rows: None,
+                    size: None,
                     schema: None,
                 },
             });
         }
-
-        let result = sqlx::query(query).execute(&self.pool).await?;
         Ok(ExecuteResult::Write {
-            updated_rows: result.rows_affected(),
+            updated_rows: result.updated_rows,
         })
     }
 
-    async fn query(&self, query: &str) -> anyhow::Result<QueryResult> {
-        let query = query.trim();
-        if query.to_uppercase().starts_with("SELECT") {
-            let mut rows = sqlx::query(query).fetch(&self.pool);
-            let mut headers = vec![];
-            let mut records = vec![];
-            while let Some(row) = rows.try_next().await? {
-                let mut new_row = vec![];
-                for column in row.columns() {
-                    let row = convert_column_value_to_string(&row, column)?;
-                    new_row.push(row.0);
-                    if records.len() == 0 { headers.push(row.1); };
+    async fn stream(&self, queries: &[String], sink: &mut RowSink) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let running = self.running(&mut conn).await?;
+        for (i, query) in queries.iter().enumerate() {
+            let last = i + 1 == queries.len();
+            sink.start(i, last);
+            // false once no more rows are wanted
+            let result: anyhow::Result<bool> = async {
+                let columns = (&mut *conn).prepare(query.as_str()).await?
+                    .columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>();
+                if columns.is_empty() {
+                    let result = sqlx::query(query).execute(&mut *conn).await?;
+                    sink.affected(result.rows_affected()).await?;
+                    return Ok(true);
+                }
+                let mut rows = sqlx::query(query).fetch(&mut *conn);
+                let mut headers = vec![];
+                let mut batch = vec![];
+                while let Some(row) = rows.try_next().await? {
+                    let mut new_row = vec![];
+                    for column in row.columns() {
+                        let row = convert_column_value_to_string(&row, column)?;
+                        new_row.push(row.0);
+                        if !sink.sent() && batch.is_empty() { headers.push(row.1); }
+                    }
+                    batch.push(new_row);
+                    if batch.len() == sink.limit {
+                        if !last {
+                            // only the last result set stays open, dropping the rows stops the statement
+                            sink.send(&headers, std::mem::take(&mut batch), false).await?;
+                            return Ok(true);
+                        }
+                        running.pause();
+                        let more = sink.send(&headers, std::mem::take(&mut batch), true).await?;
+                        running.resume();
+                        if !more { return Ok(false) }
+                    }
+                }
+                if !sink.sent() && headers.is_empty() {
+                    headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
                 }
-                records.push(new_row)
+                sink.send(&headers, batch, false).await?;
+                Ok(true)
+            }.await;
+            if !self.settle(&mut conn, result).await.map_err(|e| statement_error(e, i, queries.len()))? {
+                return Ok(());
             }
-            return Ok(QueryResult {
-                headers,
-                rows: records,
-                updated_rows: 0,
-            });
         }
+        Ok(())
+    }
 
-        let result = sqlx::query(query).execute(&self.pool).await?;
-        Ok(QueryResult {
-            headers: vec![],
-            rows: vec![],
-            updated_rows: result.rows_affected(),
-        })
+    async fn execute_statement(&self, statement: &Statement) -> anyhow::Result<u64> {
+        let result = bind_statement(statement).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn execute_transaction(&self, statements: &[Statement]) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut rows_affected = 0;
+        for statement in statements {
+            let result = bind_statement(statement).execute(&mut tx).await?;
+            // dropping tx without commit rolls it back
+            statement.check_rows_affected(result.rows_affected())?;
+            rows_affected += result.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(rows_affected)
+    }
+
+    async fn query(&self, query: &str) -> anyhow::Result<QueryResult> {
+        let mut conn = self.pool.acquire().await?;
+        query_rows(&mut conn, query).await
     }
 
     async fn get_databases(&self) -> anyhow::Result<Vec<Database>> {
@@ -215,63 +300,93 @@ impl Pool for SqlitePool {
             .iter()
             .map(|table| table.get(0))
             .collect::<Vec<String>>();
-        let mut list = vec![];
-        for db in databases {
-            list.push(Database::new(
-                db.clone(),
-                self.get_tables(db.clone()).await?,
-            ))
-        }
-        Ok(list)
+        Ok(databases.into_iter().map(Database::unloaded).collect())
     }
 
     async fn get_tables(&self, _database: String) -> anyhow::Result<Vec<Child>> {
         let mut rows =
-            sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name").fetch(&self.pool);
+            sqlx::query("SELECT name, type, tbl_name FROM sqlite_master WHERE type IN ('table', 'view', 'trigger') ORDER BY name").fetch(&self.pool);
         let mut tables = Vec::new();
+        let mut objects = Vec::new();
         while let Some(row) = rows.try_next().await? {
-            tables.push(Table {
+            let kind = match row.try_get::<String, _>("type")?.as_str() {
+                "view" => ObjectKind::View,
+                "trigger" => ObjectKind::Trigger,
+                _ => {
+                    tables.push(Table {
+                        name: row.try_get("name")?,
+                        create_time: None,
+                        update_time: None,
+                        engine: None,
+                        rows: None,
+                        size: None,
+                        schema: None,
+                    });
+                    continue;
+                },
+            };
+            objects.push(Object {
                 name: row.try_get("name")?,
-                create_time: None,
-                update_time: None,
-                engine: None,
+                kind,
                 schema: None,
+                table: Some(row.try_get("tbl_name")?).filter(|_| kind == ObjectKind::Trigger),
             })
         }
-        Ok(tables.into_iter().map(|table| table.into()).collect())
+        drop(rows);
+        // dbstat is missing when sqlite is built without it, the leaf cells of a table are its rows
+        let stats: HashMap<String, (i64, i64)> =
+            sqlx::query_as::<_, (String, i64, i64)>(
+                "SELECT m.tbl_name, SUM(s.pgsize), SUM(CASE WHEN s.name = m.tbl_name AND s.pagetype = 'leaf' THEN s.ncell ELSE 0 END) \
+                 FROM dbstat s JOIN sqlite_master m ON m.name = s.name GROUP BY m.tbl_name",
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map(|stats| stats.into_iter().map(|(name, size, cells)| (name, (size, cells))).collect())
+            .unwrap_or_default();
+        // sqlite_stat1 is there after ANALYZE, its first number is the row count of the table
+        let analyzed: HashMap<String, i64> =
+            sqlx::query_as::<_, (String, String)>("SELECT tbl, stat FROM sqlite_stat1")
+                .fetch_all(&self.pool)
+                .await
+                .map(|stats| {
+                    stats.into_iter()
+                        .filter_map(|(name, stat)| Some((name, stat.split(' ').next()?.parse().ok()?)))
+                        .collect()
+                })
+                .unwrap_or_default();
+        // an estimate, the count_rows key counts exactly
+        for table in tables.iter_mut() {
+            let (size, cells) = stats.get(&table.name).copied().unzip();
+            table.rows = analyzed.get(&table.name).copied().or(cells).and_then(|rows| u64::try_from(rows).ok());
+            table.size = size.and_then(|size| u64::try_from(size).ok());
+        }
+        objects.sort_by_key(|o| o.kind);
+        Ok(tables.into_iter().map(Child::from).chain(objects.into_iter().map(Child::from)).collect())
+    }
+
+    async fn get_definition(&self, _database: &Database, object: &Object) -> anyhow::Result<String> {
+        let definition: String = sqlx::query_scalar("SELECT sql FROM sqlite_master WHERE name = ?")
+            .bind(&object.name)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(format!("{};", definition))
     }
 
     async fn get_records(
         &self,
         database: &Database,
         table: &Table,
-        page: u16,
+        page: &Page,
         filter: Option<String>,
-        orderby: Option<String>,
     ) -> anyhow::Result<(Vec<Header>, Vec<Vec<Value>>)> {
-        let orderby = if orderby.is_none() { "".to_string() } else { format!("ORDER BY {}", orderby.unwrap()) };
-        let query = if let Some(filter) = filter {
-            format!(
-                "SELECT * FROM `{table}` WHERE {filter} {orderby} LIMIT {page}, {limit}",
-                table = table.name,
-                filter = filter,
-                orderby = orderby,
-                page = page,
-                limit = self.page_size
-            )
-        } else {
-            format!(
-                "SELECT * FROM `{}` {orderby} LIMIT {page}, {limit}",
-                table.name,
-                orderby = orderby,
-                page = page,
-                limit = self.page_size
-            )
-        };
-        let mut rows = sqlx::query(query.as_str()).fetch(&self.pool);
+        let query = self.database_type().records_query(database, table, "*", page, filter.as_deref(), self.page_size);
+        let mut conn = self.pool.acquire().await?;
+        let _running = self.running(&mut conn).await?;
+        let rows = sqlx::query(query.as_str()).fetch_all(&mut *conn).await.map_err(Into::into);
+        let rows = self.settle(&mut conn, rows).await?;
         let mut headers = vec![];
         let mut records = vec![];
-        while let Some(row) = rows.try_next().await? {
+        for row in rows {
             // headers = row
             //     .columns()
             //     .iter()
@@ -407,69 +522,468 @@ impl Pool for SqlitePool {
         Ok(foreign_keys)
     }
 
+    async fn explain(&self, query: &str, analyze: bool) -> anyhow::Result<PlanNode> {
+        if analyze {
+            anyhow::bail!("EXPLAIN ANALYZE is only supported for postgres");
+        }
+        let rows = sqlx::query(&format!("EXPLAIN QUERY PLAN {}", query)).fetch_all(&self.pool).await?;
+        let rows = rows.iter()
+            .map(|row| Ok((row.try_get("id")?, row.try_get("parent")?, row.try_get("detail")?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(PlanNode::from_sqlite(&rows))
+    }
+
     async fn close(&self) {
         self.pool.close().await;
     }
+
+    async fn cancel(&self) -> anyhow::Result<bool> {
+        // the lock keeps the query from finishing in between, a flag set after it would stop the next one
+        let running = self.running.lock().unwrap();
+        if running.is_some() {
+            self.interrupted.store(true, Ordering::SeqCst);
+            return Ok(true);
+        }
+        Ok(false)
+    }
     
     fn database_type(&self) -> DatabaseType {
         DatabaseType::Sqlite
     }
 }
 
+async fn query_rows(conn: &mut SqliteConnection, query: &str) -> anyhow::Result<QueryResult> {
+    let query = query.trim();
+    // WITH, PRAGMA, VALUES, EXPLAIN and the like return rows too, as in stream
+    let columns = (&mut *conn).prepare(query).await?
+        .columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>();
+    if !columns.is_empty() {
+        let mut rows = sqlx::query(query).fetch(&mut *conn);
+        let mut headers = vec![];
+        let mut records = vec![];
+        while let Some(row) = rows.try_next().await? {
+            let mut new_row = vec![];
+            for column in row.columns() {
+                let row = convert_column_value_to_string(&row, column)?;
+                new_row.push(row.0);
+                if records.len() == 0 { headers.push(row.1); };
+            }
+            records.push(new_row)
+        }
+        if headers.is_empty() {
+            headers = columns.into_iter().map(|name| Header::new(name, ColType::Unknown)).collect();
+        }
+        return Ok(QueryResult {
+            headers,
+            rows: records,
+            updated_rows: 0,
+        });
+    }
+
+    let result = sqlx::query(query).execute(&mut *conn).await?;
+    Ok(QueryResult {
+        headers: vec![],
+        rows: vec![],
+        updated_rows: result.rows_affected(),
+    })
+}
+
+fn bind_statement(statement: &Statement) -> sqlx::query::Query<'_, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'_>> {
+    let mut query = sqlx::query(&statement.sql);
+    for param in &statement.params {
+        query = match param {
+            Param::Null => query.bind(None::<String>),
+            Param::Int(v) => query.bind(*v),
+            Param::Float(v) => query.bind(*v),
+            Param::Bool(v) => query.bind(*v),
+            Param::Text(v) => query.bind(v.as_str()),
+            Param::Bytes(v) => query.bind(v.as_slice()),
+        };
+    }
+    query
+}
+
 fn convert_column_value_to_string(
     row: &SqliteRow,
     column: &SqliteColumn,
 ) -> anyhow::Result<(Value, Header)> {
-    let column_name = column.name();
-    if let Ok(value) = row.try_get(column_name) {
-        let value: Option<String> = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<&str> = value;
-        let header = Header::new(column_name.to_string(), ColType::VarChar);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i16> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i32> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<i64> = value;
-        let header = Header::new(column_name.to_string(), ColType::Int);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<f32> = value;
-        let header = Header::new(column_name.to_string(),  ColType::Float);
-        Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<f64> = value;
-        let header = Header::new(column_name.to_string(), ColType::Float);
-        Ok((get_or_null!(value), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<chrono::DateTime<chrono::Utc>> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<chrono::DateTime<chrono::Local>> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    // } else if let Ok(value) = row.try_get(column_name) {
-    //     let value: Option<NaiveDateTime> = value;
-    //     let header = Header::new(column_name.to_string(), ColType::Date);
-    //     Ok((get_or_null!(value), header))
-    } else if let Ok(value) = row.try_get(column_name) {
-        let value: Option<bool> = value;
-        let header = Header::new(column_name.to_string(), ColType::Boolean);
-        Ok((get_or_null!(value), header))
-    } else {
-        anyhow::bail!(
-            "column type not implemented: `{}` {}",
-            column_name,
-            column.type_info().clone().name()
-        )
+    // the declared type, or the storage class of the first value when the column is an expression
+    let col_type = match column.type_info().name() {
+        "INTEGER" => ColType::Int,
+        "REAL" | "NUMERIC" => ColType::Float,
+        "BOOLEAN" => ColType::Boolean,
+        "DATE" | "TIME" | "DATETIME" => ColType::Date,
+        "BLOB" => ColType::Unknown,
+        _ => ColType::VarChar,
+    };
+    let header = Header::new(column.name().to_string(), col_type);
+    // any column can hold any type, each value is read by its own storage class
+    let value = row.try_get_raw(column.ordinal())?;
+    if value.is_null() {
+        return Ok((Value::default(), header))
+    }
+    let datum = match value.type_info().name() {
+        "INTEGER" => Datum::Int(decode(value)?),
+        "REAL" => Datum::Float(decode(value)?),
+        "BLOB" => Datum::Bytes(decode(value)?),
+        _ => Datum::Text(decode(value)?),
+    };
+    Ok((datum.into(), header))
+}
+
+fn decode<'r, T: Decode<'r, Sqlite>>(value: SqliteValueRef<'r>) -> anyhow::Result<T> {
+    T::decode(value).map_err(|e| anyhow::anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{RowBatch, RowKeys, SQLITE_ROWID};
+    use crate::event::{Event, Store};
+    use crate::sql::token::tokenizer::Tokenizer;
+    use std::convert::TryFrom;
+    use tokio::sync::mpsc;
+
+    async fn pool(ddl: &[&str]) -> Box<dyn Pool> {
+        let pool: Box<dyn Pool> = Box::new(SqlitePool::new("sqlite::memory:", 100).await.unwrap());
+        for sql in ddl {
+            pool.execute(sql).await.unwrap();
+        }
+        pool
+    }
+
+    fn table(name: &str) -> Table {
+        Table { name: name.to_string(), create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None }
+    }
+
+    #[tokio::test]
+    async fn test_primary_key_columns() {
+        let pool = pool(&[
+            "CREATE TABLE members (user_id INTEGER, group_id INTEGER, role TEXT, PRIMARY KEY (group_id, user_id))",
+            "CREATE TABLE logs (level TEXT, message TEXT)",
+            "CREATE VIEW warnings AS SELECT * FROM logs",
+        ]).await;
+        let database = Database::new("main".to_string(), vec![]);
+        let columns = DatabaseType::Sqlite.primary_key_columns(&pool, &database, &table("members")).await.unwrap();
+        assert_eq!(columns, vec!["group_id".to_string(), "user_id".to_string()]);
+        let columns = DatabaseType::Sqlite.primary_key_columns(&pool, &database, &table("logs")).await.unwrap();
+        assert_eq!(columns, vec![SQLITE_ROWID.to_string()]);
+        let columns = DatabaseType::Sqlite.primary_key_columns(&pool, &database, &table("warnings")).await.unwrap();
+        assert!(columns.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_objects() {
+        let pool = pool(&[
+            "CREATE TABLE logs (level TEXT, message TEXT)",
+            "CREATE VIEW warnings AS SELECT * FROM logs WHERE level = 'warn'",
+            "CREATE TRIGGER no_empty BEFORE INSERT ON logs BEGIN SELECT RAISE(ABORT, 'empty') WHERE NEW.message = ''; END",
+        ]).await;
+        let children = pool.get_tables("main".to_string()).await.unwrap();
+        let objects = children.iter().filter_map(|child| match child {
+            Child::Object(o) => Some((o.name.as_str(), o.kind, o.table.as_deref())),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(objects, [("warnings", ObjectKind::View, None), ("no_empty", ObjectKind::Trigger, Some("logs"))]);
+        assert!(matches!(&children[0], Child::Table(t) if t.name == "logs"));
+
+        let database = Database::new("main".to_string(), vec![]);
+        let view = Object { name: "warnings".to_string(), kind: ObjectKind::View, schema: None, table: None };
+        let definition = pool.get_definition(&database, &view).await.unwrap();
+        assert_eq!(definition, "CREATE VIEW warnings AS SELECT * FROM logs WHERE level = 'warn';");
+    }
+
+    #[tokio::test]
+    async fn test_table_stats() {
+        let pool = pool(&[
+            "CREATE TABLE logs (level TEXT, message TEXT)",
+            "CREATE INDEX logs_level ON logs (level)",
+            "INSERT INTO logs VALUES ('warn', NULL), ('info', 'ok'), ('info', 'ok')",
+        ]).await;
+        let children = pool.get_tables("main".to_string()).await.unwrap();
+        let table = match &children[0] {
+            Child::Table(table) => table,
+            child => panic!("expected a table, got {:?}", child),
+        };
+        assert_eq!(table.rows, Some(3));
+        // a page for the table and one for its index
+        assert_eq!(table.size, Some(2 * 4096));
+
+        // the estimate of the last analyze, not a count
+        pool.execute("ANALYZE").await.unwrap();
+        pool.execute("INSERT INTO logs VALUES ('info', 'late')").await.unwrap();
+        let children = pool.get_tables("main".to_string()).await.unwrap();
+        match &children[0] {
+            Child::Table(table) => assert_eq!(table.rows, Some(3)),
+            child => panic!("expected a table, got {:?}", child),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_rowids() {
+        let pool = pool(&[
+            "CREATE TABLE logs (level TEXT, message TEXT)",
+            "INSERT INTO logs VALUES ('warn', NULL), ('warn', NULL), ('info', 'ok')",
+        ]).await;
+        let database = Database::new("main".to_string(), vec![]);
+        let headers = vec![Header::new("level".to_string(), ColType::VarChar), Header::new("message".to_string(), ColType::VarChar)];
+        let row = vec![Value::new("warn".to_string()), Value::default()];
+        let keys = RowKeys { headers, rows: vec![row.clone(), row], exact: false };
+        let rowids = DatabaseType::Sqlite.sqlite_rowids(&pool, &database, &table("logs"), &keys, &[]).await.unwrap();
+        assert_eq!(rowids.iter().map(|v| v.data.as_str()).collect::<Vec<_>>(), vec!["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_transaction() {
+        let pool = pool(&[
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+            "INSERT INTO users VALUES (1, 'a'), (2, 'b')",
+        ]).await;
+        let update = |id: i64, expected_rows: Option<u64>| Statement {
+            sql: "UPDATE users SET name = 'z' WHERE id = ?".to_string(),
+            params: vec![Param::Int(id)],
+            expected_rows,
+        };
+        // the second update matches no row, so the first is rolled back
+        assert!(pool.execute_transaction(&[update(1, Some(1)), update(3, Some(1))]).await.is_err());
+        let result = pool.query("SELECT name FROM users WHERE id = 1").await.unwrap();
+        assert_eq!(result.rows[0][0].data, "a");
+
+        assert_eq!(pool.execute_transaction(&[update(1, Some(1)), update(2, None)]).await.unwrap(), 2);
+        let result = pool.query("SELECT name FROM users ORDER BY id").await.unwrap();
+        assert_eq!(result.rows.iter().map(|row| row[0].data.as_str()).collect::<Vec<_>>(), vec!["z", "z"]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_read() {
+        let pool = pool(&["CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)"]).await;
+        for sql in ["WITH t AS (SELECT 1 AS n) SELECT n FROM t", "VALUES (1)", "PRAGMA table_info(users)", "EXPLAIN SELECT 1"] {
+            match pool.execute(sql).await.unwrap() {
+                ExecuteResult::Read { rows, .. } => assert!(!rows.is_empty(), "{}", sql),
+                _ => panic!("{} is not a read", sql),
+            }
+        }
+        // no rows still has the headers
+        match pool.execute("SELECT id, name FROM users").await.unwrap() {
+            ExecuteResult::Read { headers, rows, .. } => {
+                assert_eq!(headers.iter().map(|h| h.name.as_str()).collect::<Vec<_>>(), vec!["id", "name"]);
+                assert!(rows.is_empty());
+            },
+            _ => panic!("select is not a read"),
+        }
+        match pool.execute("INSERT INTO users (name) VALUES ('a')").await.unwrap() {
+            ExecuteResult::Write { updated_rows } => assert_eq!(updated_rows, 1),
+            _ => panic!("insert is not a write"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import() {
+        use crate::database::{import_rows, Conflict, ExportFormat, ImportColumn, ImportRequest};
+
+        let pool = pool(&["CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT 'x', avatar BLOB)"]).await;
+        let path = std::env::temp_dir().join(format!("gobang_import_{}.csv", std::process::id()));
+        std::fs::write(&path, "name,ID,picture\na,1,\\xdead\nb,2,\n").unwrap();
+        let column = |name: &str, col_type: &str, field| ImportColumn { name: name.to_string(), col_type: col_type.to_string(), field };
+        let mut request = ImportRequest {
+            database: Database::new("main".to_string(), vec![]),
+            table: table("users"),
+            format: ExportFormat::Csv,
+            path: path.clone(),
+            columns: vec![column("id", "INTEGER", 1), column("avatar", "BLOB", 2)],
+            conflict: Conflict::Fail,
+        };
+        let (tx, _rx) = mpsc::channel(8);
+        let store = Store::new(tx);
+        assert_eq!(import_rows(&pool, &request, &store).await.unwrap(), (2, 2));
+        let result = pool.query("SELECT name, hex(avatar) FROM users ORDER BY id").await.unwrap();
+        assert_eq!(result.rows.iter().map(|row| (row[0].data.as_str(), row[1].data.as_str())).collect::<Vec<_>>(), [("x", "DEAD"), ("x", "")]);
+
+        assert!(import_rows(&pool, &request, &store).await.is_err());
+        request.conflict = Conflict::Skip;
+        assert_eq!(import_rows(&pool, &request, &store).await.unwrap(), (2, 0));
+        request.conflict = Conflict::Upsert;
+        request.columns.push(column("name", "TEXT", 0));
+        assert_eq!(import_rows(&pool, &request, &store).await.unwrap(), (2, 2));
+        let result = pool.query("SELECT name FROM users ORDER BY id").await.unwrap();
+        assert_eq!(result.rows.iter().map(|row| row[0].data.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        // the key decides what is a duplicate
+        request.columns.remove(0);
+        assert!(import_rows(&pool, &request, &store).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dump_restore() {
+        use crate::database::{dump_database, restore_database};
+
+        let pool = pool(&[
+            "CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users (id), title TEXT, body BLOB, words INTEGER GENERATED ALWAYS AS (length(title)))",
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, score REAL)",
+            "INSERT INTO users VALUES (1, 'o''neil', 1.5), (2, 'line
+
+break', NULL)",
+            "INSERT INTO posts (id, user_id, title, body) VALUES (1, 2, 'a;b', x'00ff'), (2, 1, NULL, NULL)",
+        ]).await;
+        let path = std::env::temp_dir().join(format!("gobang_dump_{}.sql", std::process::id()));
+        let (tx, _rx) = mpsc::channel(64);
+        let store = Store::new(tx);
+        assert_eq!(dump_database(&pool, "main", &path, &store).await.unwrap(), (2, 4));
+        let script = std::fs::read_to_string(&path).unwrap();
+        // users first, posts point at them
+        assert!(script.find("CREATE TABLE users").unwrap() < script.find("CREATE TABLE posts").unwrap());
+        // the generated column is left to the database
+        assert!(script.contains("INSERT INTO `posts` (`id`, `user_id`, `title`, `body`) VALUES"));
+
+        let restored = self::pool(&[]).await;
+        assert_eq!(restore_database(&restored, &path, &store).await.unwrap(), 4);
+        let query = "SELECT u.name, u.score, p.title, hex(p.body), p.words FROM posts p JOIN users u ON u.id = p.user_id ORDER BY p.id";
+        let rows = |result: QueryResult| result.rows.iter().map(|row| row.iter().map(|v| v.data.clone()).collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(rows(restored.query(query).await.unwrap()), rows(pool.query(query).await.unwrap()));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_records_keyset() {
+        let pool: Box<dyn Pool> = Box::new(SqlitePool::new("sqlite::memory:", 20).await.unwrap());
+        pool.execute("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)").await.unwrap();
+        for i in 1..=30 {
+            pool.execute(&format!("INSERT INTO items VALUES ({}, {})", i, if i % 3 == 0 { "NULL".to_string() } else { format!("'n{:02}'", i % 7) })).await.unwrap();
+        }
+        let database = Database::new("main".to_string(), vec![]);
+        let mut page = Page::new(Some("name desc".to_string()), vec!["id".to_string()]);
+        let (headers, mut records) = pool.get_records(&database, &table("items"), &page, None).await.unwrap();
+        assert_eq!(records.len(), 20);
+        // continue after the last row, as TableComponent::next_page does
+        let last = records.last().unwrap().clone();
+        let after = vec![Param::new(&headers[1], &last[1]), Param::new(&headers[0], &last[0])];
+        page.offset = records.len() as u64;
+        page.keyset.as_mut().unwrap().after = Some(after);
+        let (_, rest) = pool.get_records(&database, &table("items"), &page, None).await.unwrap();
+        records.extend(rest);
+        let mut ids = records.iter().map(|row| row[0].data.parse::<i64>().unwrap()).collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, (1..=30).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_types() {
+        let pool = pool(&[
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, v, flag BOOLEAN)",
+            "INSERT INTO t VALUES (1, 10, 1), (2, 1.5, 0), (3, 'text', NULL), (4, x'00ff', 1)",
+        ]).await;
+        let result = pool.execute("SELECT v, flag FROM t ORDER BY id").await.unwrap();
+        let (headers, rows) = match result {
+            ExecuteResult::Read { headers, rows, .. } => (headers, rows),
+            _ => panic!("not a read"),
+        };
+        assert_eq!(headers[1].col_type, ColType::Boolean);
+        assert_eq!(rows.iter().map(|row| row[0].datum.clone()).collect::<Vec<_>>(), [
+            Datum::Int(10),
+            Datum::Float(1.5),
+            Datum::Text("text".to_string()),
+            Datum::Bytes(vec![0, 255]),
+        ]);
+        assert_eq!(rows[3][0].data, "\\x00ff");
+        assert!(rows[2][1].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let pool = std::sync::Arc::new(pool(&[]).await);
+        assert!(!pool.cancel().await.unwrap());
+        let running = pool.clone();
+        let handle = tokio::spawn(async move {
+            running.execute("SELECT count(*) FROM (WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT x FROM c)").await
+        });
+        // once the statement is running a single cancel stops it
+        while !pool.cancel().await.unwrap() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let err = tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap().unwrap_err();
+        assert!(err.to_string().contains("interrupted"), "{}", err);
+        assert!(!pool.cancel().await.unwrap());
+        // the connection is free again, not running the statement once more
+        assert!(matches!(pool.execute("SELECT 1").await.unwrap(), ExecuteResult::Read { .. }));
+    }
+
+    async fn next_batch(rx: &mut mpsc::Receiver<Event>) -> Box<RowBatch> {
+        match rx.recv().await.unwrap() {
+            Event::QueryRows(batch) => batch,
+            event => panic!("unexpected event: {:?}", event),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let pool = std::sync::Arc::new(pool(&[
+            "CREATE TABLE t (id INTEGER PRIMARY KEY)",
+            "INSERT INTO t (id) VALUES (1), (2), (3), (4), (5)",
+        ]).await);
+        let (tx, mut rx) = mpsc::channel(8);
+        let (mut sink, more) = RowSink::new(2, Store::new(tx));
+        let running = pool.clone();
+        let task = tokio::spawn(async move {
+            running.stream(&["SELECT id FROM t ORDER BY id".to_string()], &mut sink).await.map(|_| sink.sent())
+        });
+        let batch = next_batch(&mut rx).await;
+        assert_eq!(batch.headers.iter().map(|h| h.name.as_str()).collect::<Vec<_>>(), ["id"]);
+        assert_eq!(batch.rows.iter().map(|row| row[0].data.as_str()).collect::<Vec<_>>(), ["1", "2"]);
+        assert!(!batch.append && batch.more);
+        assert_eq!(batch.rows[0][0].datum, Datum::Int(1));
+        more.send(()).await.unwrap();
+        let batch = next_batch(&mut rx).await;
+        assert_eq!(batch.rows.iter().map(|row| row[0].data.as_str()).collect::<Vec<_>>(), ["3", "4"]);
+        assert!(batch.append && batch.more);
+        // nobody asks for the rest
+        drop(more);
+        assert!(task.await.unwrap().unwrap());
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let (mut sink, _more) = RowSink::new(2, Store::new(tx));
+        pool.stream(&["DELETE FROM t WHERE id > 2".to_string()], &mut sink).await.unwrap();
+        let batch = next_batch(&mut rx).await;
+        assert_eq!(batch.updated_rows, Some(3));
+        assert!(batch.done);
+    }
+
+    #[tokio::test]
+    async fn test_stream_script() {
+        let pool = pool(&[]).await;
+        let script = "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);
+            INSERT INTO t (id, name) VALUES (1, 'a'), (2, 'b'), (3, 'c');
+            SELECT id FROM t ORDER BY id;
+            SELECT name FROM t WHERE id = 2";
+        let t = Tokenizer::try_from(DatabaseType::Sqlite).unwrap();
+        let queries = t.statements(script).into_iter().map(|range| script[range].to_string()).collect::<Vec<_>>();
+        let (tx, mut rx) = mpsc::channel(8);
+        let (mut sink, _more) = RowSink::new(2, Store::new(tx));
+        pool.stream(&queries, &mut sink).await.unwrap();
+
+        let batch = next_batch(&mut rx).await;
+        assert_eq!((batch.statement, batch.updated_rows), (0, Some(0)));
+        let batch = next_batch(&mut rx).await;
+        assert_eq!((batch.statement, batch.updated_rows), (1, Some(3)));
+        // only the last result set waits for more rows
+        let batch = next_batch(&mut rx).await;
+        assert_eq!(batch.statement, 2);
+        assert_eq!(batch.rows.iter().map(|row| row[0].data.as_str()).collect::<Vec<_>>(), ["1", "2"]);
+        assert!(!batch.more && !batch.done);
+        let batch = next_batch(&mut rx).await;
+        assert_eq!(batch.statement, 3);
+        assert_eq!(batch.headers[0].name, "name");
+        assert_eq!(batch.rows[0][0].data, "b");
+        assert!(batch.done);
+
+        let (tx, _rx) = mpsc::channel(8);
+        let (mut sink, _more) = RowSink::new(2, Store::new(tx));
+        let queries = ["DELETE FROM t", "SELECT * FROM missing", "DELETE FROM t"].map(String::from);
+        let e = pool.stream(&queries, &mut sink).await.unwrap_err();
+        assert!(e.to_string().starts_with("statement 2 of 3: "), "{}", e);
+        // the statements before the failing one ran
+        match pool.execute("SELECT COUNT(*) FROM t").await.unwrap() {
+            ExecuteResult::Read { rows, .. } => assert_eq!(rows[0][0].data, "0"),
+            _ => panic!("expected rows"),
+        }
     }
 }
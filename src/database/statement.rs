@@ -0,0 +1,225 @@
+use super::{ColType, Datum, Header, Value};
+use crate::config::DatabaseType;
+
+/// typed value bound to a placeholder
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Param {
+    // bind what the driver decoded, edited cells are text and go by the column type
+    pub fn new(header: &Header, value: &Value) -> Self {
+        match value.datum {
+            Datum::Null => return Param::Null,
+            Datum::Int(v) => return Param::Int(v),
+            Datum::UInt(v) if v <= i64::MAX as u64 => return Param::Int(v as i64),
+            Datum::Float(v) => return Param::Float(v),
+            Datum::Bool(v) => return Param::Bool(v),
+            // the display form is cut short
+            Datum::Bytes(ref v) => return Param::Bytes(v.clone()),
+            _ => {},
+        }
+        let data = value.data.as_str();
+        let text = || Param::Text(data.to_string());
+        match header.col_type {
+            ColType::Int => data.parse::<i64>().map(Param::Int).unwrap_or_else(|_| text()),
+            ColType::Float => data.parse::<f64>().map(Param::Float).unwrap_or_else(|_| text()),
+            ColType::Boolean => match data.to_lowercase().as_str() {
+                "true" | "t" | "1" => Param::Bool(true),
+                "false" | "f" | "0" => Param::Bool(false),
+                _ => text(),
+            },
+            _ => text(),
+        }
+    }
+}
+
+/// sql with placeholders and the params bound to them, in order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statement {
+    pub sql: String,
+    pub params: Vec<Param>,
+    // rows the statement must touch, checked before a transaction commits
+    pub expected_rows: Option<u64>,
+}
+
+/// columns that identify the selected rows, with their values for each row
+#[derive(Debug, Clone)]
+pub struct RowKeys {
+    pub headers: Vec<Header>,
+    pub rows: Vec<Vec<Value>>,
+    // false when the table has no key and rows are matched on every column
+    pub exact: bool,
+}
+
+impl Statement {
+
+    // push a param and return its placeholder, wrapped in a cast when the column type is known
+    pub fn bind(&mut self, database_type: &DatabaseType, param: Param, cast: Option<&String>) -> String {
+        self.params.push(param);
+        let placeholder = database_type.placeholder(self.params.len());
+        match cast {
+            Some(typ) => format!("CAST({} AS {})", placeholder, typ),
+            None => placeholder,
+        }
+    }
+
+    pub fn check_rows_affected(&self, rows_affected: u64) -> anyhow::Result<()> {
+        match self.expected_rows {
+            Some(expected) if expected != rows_affected => anyhow::bail!(
+                "expected {} rows but {} were affected by: {}", expected, rows_affected, self.sql
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DatabaseType {
+
+    pub fn placeholder(&self, index: usize) -> String {
+        match self {
+            DatabaseType::Postgres => format!("${}", index),
+            DatabaseType::Mssql => format!("@p{}", index),
+            _ => "?".to_string(),
+        }
+    }
+
+    pub fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            DatabaseType::Postgres => format!("\"{}\"", name.replace('"', "\"\"")),
+            DatabaseType::Mssql => format!("[{}]", name.replace(']', "]]")),
+            _ => format!("`{}`", name.replace('`', "``")),
+        }
+    }
+
+    pub fn literal(&self, param: &Param) -> String {
+        match param {
+            Param::Null => "NULL".to_string(),
+            Param::Int(v) => v.to_string(),
+            Param::Float(v) => v.to_string(),
+            Param::Bool(v) => match self {
+                DatabaseType::Sqlite | DatabaseType::Mssql => (if *v { "1" } else { "0" }).to_string(),
+                _ => (if *v { "TRUE" } else { "FALSE" }).to_string(),
+            },
+            Param::Text(v) => match self {
+                // backslash is an escape character in mysql string literals
+                DatabaseType::MySql => format!("'{}'", v.replace('\\', "\\\\").replace('\'', "''")),
+                _ => format!("'{}'", v.replace('\'', "''")),
+            },
+            Param::Bytes(v) => {
+                let hex = v.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+                match self {
+                    DatabaseType::Postgres => format!("'\\x{}'::bytea", hex),
+                    DatabaseType::Mssql => format!("0x{}", hex),
+                    _ => format!("X'{}'", hex),
+                }
+            },
+        }
+    }
+
+    // replace the placeholders outside of quotes with escaped literals
+    pub fn render(&self, statement: &Statement) -> String {
+        let chars = statement.sql.chars().collect::<Vec<_>>();
+        let mut sql = String::with_capacity(statement.sql.len());
+        let mut quote: Option<char> = None;
+        let mut next = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match quote {
+                Some(q) => if c == q { quote = None },
+                None => {
+                    if let Some((index, len)) = self.placeholder_at(&chars[i..], next) {
+                        if let Some(param) = statement.params.get(index) {
+                            sql.push_str(&self.literal(param));
+                            next = index + 1;
+                            i += len;
+                            continue
+                        }
+                    }
+                    quote = match c {
+                        '\'' | '"' | '`' => Some(c),
+                        '[' if matches!(self, DatabaseType::Mssql) => Some(']'),
+                        _ => None,
+                    };
+                }
+            }
+            sql.push(c);
+            i += 1;
+        }
+        sql
+    }
+
+    // index of the param and length of the placeholder at the start of `chars`
+    fn placeholder_at(&self, chars: &[char], next: usize) -> Option<(usize, usize)> {
+        let prefix: &[char] = match self {
+            DatabaseType::Postgres => &['$'],
+            DatabaseType::Mssql => &['@', 'p'],
+            _ => return if chars.first() == Some(&'?') { Some((next, 1)) } else { None },
+        };
+        if !chars.starts_with(prefix) {
+            return None
+        }
+        let digits = chars[prefix.len()..].iter().take_while(|c| c.is_ascii_digit()).collect::<String>();
+        let n = digits.parse::<usize>().ok().filter(|n| *n > 0)?;
+        Some((n - 1, prefix.len() + digits.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_new() {
+        let header = Header::new("id".to_string(), ColType::Int);
+        assert_eq!(Param::new(&header, &Value::new("42".to_string())), Param::Int(42));
+        assert_eq!(Param::new(&header, &Value::new("4.2".to_string())), Param::Text("4.2".to_string()));
+        assert_eq!(Param::new(&header, &Value::default()), Param::Null);
+        let header = Header::new("flag".to_string(), ColType::Boolean);
+        assert_eq!(Param::new(&header, &Value::new("true".to_string())), Param::Bool(true));
+        let header = Header::new("name".to_string(), ColType::VarChar);
+        assert_eq!(Param::new(&header, &Value::new("1".to_string())), Param::Text("1".to_string()));
+        assert_eq!(Param::new(&header, &Datum::Int(1).into()), Param::Int(1));
+        assert_eq!(Param::new(&header, &Datum::UInt(u64::MAX).into()), Param::Text(u64::MAX.to_string()));
+        assert_eq!(Param::new(&header, &Datum::Bytes(vec![0; 300]).into()), Param::Bytes(vec![0; 300]));
+    }
+
+    #[test]
+    fn test_render() {
+        let statement = Statement {
+            sql: "UPDATE `t?` SET `name` = ? WHERE `id` = ?".to_string(),
+            params: vec![Param::Text("it's \\ fine".to_string()), Param::Int(1)],
+            expected_rows: None,
+        };
+        assert_eq!(DatabaseType::MySql.render(&statement), r"UPDATE `t?` SET `name` = 'it''s \\ fine' WHERE `id` = 1");
+        assert_eq!(DatabaseType::Sqlite.render(&statement), r"UPDATE `t?` SET `name` = 'it''s \ fine' WHERE `id` = 1");
+
+        let statement = Statement {
+            sql: r#"UPDATE "$1" SET "name" = CAST($1 AS text) WHERE "id" = $2 OR "id" = $12"#.to_string(),
+            params: vec![Param::Text("a'b".to_string()), Param::Null],
+            expected_rows: None,
+        };
+        assert_eq!(DatabaseType::Postgres.render(&statement), r#"UPDATE "$1" SET "name" = CAST('a''b' AS text) WHERE "id" = NULL OR "id" = $12"#);
+    }
+
+    #[test]
+    fn test_literal_bytes() {
+        let bytes = Param::Bytes(vec![0xde, 0xad, 0x01]);
+        assert_eq!(DatabaseType::Postgres.literal(&bytes), r"'\xdead01'::bytea");
+        assert_eq!(DatabaseType::MySql.literal(&bytes), "X'dead01'");
+        assert_eq!(DatabaseType::Mssql.literal(&bytes), "0xdead01");
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(DatabaseType::MySql.quote_identifier("a`b"), "`a``b`");
+        assert_eq!(DatabaseType::Postgres.quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+}
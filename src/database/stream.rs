@@ -0,0 +1,115 @@
+use super::{Exporter, Header, Value};
+use crate::event::{Event, Store};
+use std::fs::File;
+use std::io::BufWriter;
+use tokio::sync::mpsc;
+
+/// what a statement run from the sql editor gave back, rows are handed over a batch at a time
+#[derive(Debug, Clone)]
+pub struct RowBatch {
+    pub statement: usize, // index of the statement in the script
+    pub headers: Vec<Header>,
+    pub rows: Vec<Vec<Value>>,
+    pub append: bool, // continues the rows of the previous batch
+    pub more: bool, // the query has rows left, they are fetched when asked for
+    pub updated_rows: Option<u64>, // the statement returns no rows
+    pub done: bool, // the last batch of the script
+}
+
+enum Target {
+    Editor(mpsc::Receiver<()>), // asks for each batch after the first
+    Export(Box<Exporter<BufWriter<File>>>), // takes every row
+}
+
+/// receives the results `Pool::stream` reads, and holds the query until more rows are wanted
+pub struct RowSink {
+    pub limit: usize, // rows per batch
+    store: Store,
+    target: Target,
+    statement: usize,
+    last: bool,
+    sent: bool,
+}
+
+impl RowSink {
+    // the sender asks for the next batch, dropping it ends the query
+    pub fn new(limit: usize, store: Store) -> (Self, mpsc::Sender<()>) {
+        let (tx, more) = mpsc::channel(1);
+        (Self { limit: limit.max(1), store, target: Target::Editor(more), statement: 0, last: true, sent: false }, tx)
+    }
+
+    // writes the rows to a file instead, the progress goes to the store
+    pub fn export(limit: usize, exporter: Exporter<BufWriter<File>>, store: Store) -> Self {
+        Self { limit: limit.max(1), store, target: Target::Export(Box::new(exporter)), statement: 0, last: true, sent: false }
+    }
+
+    // the rows written by an export
+    pub fn finish(self) -> anyhow::Result<u64> {
+        match self.target {
+            Target::Export(exporter) => exporter.finish(),
+            Target::Editor(_) => Ok(0),
+        }
+    }
+
+    // results that follow belong to the statement at `index`
+    pub fn start(&mut self, index: usize, last: bool) {
+        self.statement = index;
+        self.last = last;
+        self.sent = false;
+    }
+
+    pub fn sent(&self) -> bool {
+        self.sent
+    }
+
+    // false when no more rows are wanted
+    pub async fn send(&mut self, headers: &[Header], rows: Vec<Vec<Value>>, more: bool) -> anyhow::Result<bool> {
+        let more_rows = match &mut self.target {
+            Target::Export(exporter) => {
+                exporter.write(headers, &rows)?;
+                self.sent = true;
+                self.store.dispatch(Event::ExportProgress(exporter.rows)).await?;
+                return Ok(more)
+            },
+            Target::Editor(more_rows) => more_rows,
+        };
+        let batch = RowBatch {
+            statement: self.statement,
+            headers: headers.to_vec(),
+            rows,
+            append: self.sent,
+            more,
+            updated_rows: None,
+            done: self.last && !more,
+        };
+        self.sent = true;
+        self.store.dispatch(Event::QueryRows(Box::new(batch))).await?;
+        Ok(more && more_rows.recv().await.is_some())
+    }
+
+    pub async fn affected(&mut self, updated_rows: u64) -> anyhow::Result<()> {
+        if let Target::Export(_) = self.target {
+            anyhow::bail!("the statement returns no rows to export")
+        }
+        let batch = RowBatch {
+            statement: self.statement,
+            headers: vec![],
+            rows: vec![],
+            append: false,
+            more: false,
+            updated_rows: Some(updated_rows),
+            done: self.last,
+        };
+        self.sent = true;
+        self.store.dispatch(Event::QueryRows(Box::new(batch))).await
+    }
+}
+
+// names the statement that failed when the script has several
+pub fn statement_error(e: anyhow::Error, index: usize, count: usize) -> anyhow::Error {
+    if count > 1 {
+        anyhow::anyhow!("statement {} of {}: {}", index + 1, count, e)
+    } else {
+        e
+    }
+}
@@ -0,0 +1,241 @@
+use super::export::sql_literal;
+use super::{ExportFormat, Exporter, Header, Param, Pool, Value};
+use crate::config::DatabaseType;
+use database_tree::{Database, Table};
+use regex::Regex;
+use std::fmt;
+
+/// what `Y` copies the selection as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YankFormat {
+    Tsv,
+    Csv,
+    Markdown,
+    Json,
+    Insert,
+    Update,
+    Delete,
+    WhereIn,
+}
+
+impl YankFormat {
+    pub const ALL: [YankFormat; 8] = [
+        YankFormat::Tsv,
+        YankFormat::Csv,
+        YankFormat::Markdown,
+        YankFormat::Json,
+        YankFormat::Insert,
+        YankFormat::Update,
+        YankFormat::Delete,
+        YankFormat::WhereIn,
+    ];
+
+    // statements on the table of the rows
+    pub fn needs_table(self) -> bool {
+        matches!(self, YankFormat::Insert | YankFormat::Update | YankFormat::Delete)
+    }
+}
+
+impl fmt::Display for YankFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            YankFormat::Tsv => "tsv",
+            YankFormat::Csv => "csv",
+            YankFormat::Markdown => "markdown table",
+            YankFormat::Json => "json array of objects",
+            YankFormat::Insert => "insert",
+            YankFormat::Update => "update ... where key",
+            YankFormat::Delete => "delete ... where key",
+            YankFormat::WhereIn => "where column in (...)",
+        })
+    }
+}
+
+/// the table yanked statements go to
+#[derive(Debug, Clone, PartialEq)]
+pub struct YankTable {
+    pub path: String, // quoted
+    pub key: Vec<String>, // empty when the table has none
+}
+
+// a table of the tree
+pub async fn yank_table(pool: &Box<dyn Pool>, database: &Database, table: &Table) -> anyhow::Result<YankTable> {
+    let database_type = pool.database_type();
+    let key = database_type.primary_key_columns(pool, database, table).await?;
+    let path = match database_type {
+        DatabaseType::Mssql => database_type.table_path(database, table),
+        _ => database_type.insert_path(database, table),
+    };
+    Ok(YankTable { path, key })
+}
+
+// the table a sql editor result was read from, none unless the query reads a single one
+pub async fn query_table(pool: &Box<dyn Pool>, query: &str) -> anyhow::Result<Option<YankTable>> {
+    let database_type = pool.database_type();
+    let (qualifier, name) = match from_table(&database_type, query) {
+        Some(from) => from,
+        None => return Ok(None),
+    };
+    let mut table = Table { name, create_time: None, update_time: None, engine: None, rows: None, size: None, schema: None };
+    let database = match database_type {
+        // the schema the search path finds it in
+        DatabaseType::Postgres => {
+            table.schema = match qualifier {
+                Some(schema) => Some(schema),
+                None => {
+                    let name = database_type.literal(&Param::Text(database_type.quote_identifier(&table.name)));
+                    let result = pool.query(&format!("SELECT relnamespace::regnamespace::text FROM pg_class WHERE oid = to_regclass({})", name)).await?;
+                    match result.rows.first().and_then(|row| row.first()) {
+                        Some(schema) => Some(schema.data.trim_matches('"').to_string()),
+                        None => return Ok(None),
+                    }
+                },
+            };
+            String::new()
+        },
+        // the database the connection uses by default, a connection without one has no key to look up
+        DatabaseType::MySql => match qualifier {
+            Some(database) => database,
+            None => {
+                let result = pool.query("SELECT DATABASE()").await?;
+                match result.rows.first().and_then(|row| row.first()).filter(|v| !v.is_null()) {
+                    Some(database) => database.data.clone(),
+                    None => return Ok(Some(YankTable { path: database_type.quote_identifier(&table.name), key: vec![] })),
+                }
+            },
+        },
+        _ => String::new(),
+    };
+    yank_table(pool, &Database::new(database, vec![]), &table).await.map(Some)
+}
+
+// the table after `from` of a select without joins, and the schema or database before it
+fn from_table(database_type: &DatabaseType, query: &str) -> Option<(Option<String>, String)> {
+    const IDENTIFIER: &str = r#"[\w$]+|"(?:[^"]|"")+"|`(?:[^`]|``)+`|\[[^\]]+\]"#;
+    let from = Regex::new(&format!(r"(?is)^\s*select\b.*?\bfrom\s+({id})(?:\s*\.\s*({id}))?(.*)$", id = IDENTIFIER)).ok()?;
+    let captures = from.captures(query)?;
+    let rest = captures.get(3).map_or("", |m| m.as_str());
+    if Regex::new(r"(?i)\b(join|union|intersect|except)\b").ok()?.is_match(rest) || rest.trim_start().starts_with(',') {
+        return None
+    }
+    let unquote = |s: &str| match s.chars().next() {
+        Some('"') => s[1..s.len() - 1].replace("\"\"", "\""),
+        Some('`') => s[1..s.len() - 1].replace("``", "`"),
+        Some('[') => s[1..s.len() - 1].to_string(),
+        // postgres folds a name without quotes to lower case
+        _ if matches!(database_type, DatabaseType::Postgres) => s.to_lowercase(),
+        _ => s.to_string(),
+    };
+    let first = unquote(captures.get(1)?.as_str());
+    match captures.get(2) {
+        Some(name) => Some((Some(first), unquote(name.as_str()))),
+        None => Some((None, first)),
+    }
+}
+
+/// the selection as `format`. `rows` are the whole selected rows and `columns` the selected columns of them,
+/// the statements match a row by the key of `table`, or every column without one
+pub fn yank(format: YankFormat, database_type: &DatabaseType, table: Option<&YankTable>, headers: &[Header], rows: &[Vec<Value>], columns: &[usize]) -> anyhow::Result<String> {
+    let quote = |i: usize| database_type.quote_identifier(&headers[i].name);
+    let literal = |row: &[Value], i: usize| sql_literal(database_type, &headers[i], &row[i]);
+    let export = |format: ExportFormat, table: &str, columns: &[usize]| -> anyhow::Result<String> {
+        let mut text = vec![];
+        let mut exporter = Exporter::new(&mut text, format, database_type.clone(), table.to_string());
+        let headers = columns.iter().map(|&i| Header::new(headers[i].name.clone(), headers[i].col_type.clone())).collect::<Vec<_>>();
+        let rows = rows.iter().map(|row| columns.iter().map(|&i| row[i].clone()).collect()).collect::<Vec<_>>();
+        exporter.write(&headers, &rows)?;
+        exporter.finish()?;
+        Ok(String::from_utf8(text)?.trim_end().to_string())
+    };
+    let table = match table {
+        Some(table) => table,
+        None if format.needs_table() => anyhow::bail!("the rows are not from a single table, {} needs one", format),
+        None => &YankTable { path: String::new(), key: vec![] },
+    };
+    // a key column left out of the result cannot match the row
+    let key = table.key.iter().map(|k| headers.iter().position(|h| h.name == *k)).collect::<Option<Vec<_>>>()
+        .filter(|key| !key.is_empty())
+        .unwrap_or_else(|| (0..headers.len()).collect());
+    let condition = |row: &[Value]| key.iter().map(|&i| {
+        if row[i].is_null() { format!("{} IS NULL", quote(i)) } else { format!("{} = {}", quote(i), literal(row, i)) }
+    }).collect::<Vec<_>>().join(" AND ");
+
+    let text = match format {
+        YankFormat::Tsv => export(ExportFormat::Tsv, "", columns)?,
+        YankFormat::Csv => export(ExportFormat::Csv, "", columns)?,
+        YankFormat::Markdown => export(ExportFormat::Markdown, "", columns)?,
+        YankFormat::Json => export(ExportFormat::Json, "", columns)?,
+        // the whole rows, like a copy of them
+        YankFormat::Insert => export(ExportFormat::Sql, &table.path, &(0..headers.len()).collect::<Vec<_>>())?,
+        YankFormat::Update => {
+            let set = columns.iter().filter(|i| !key.contains(i) || key.len() == headers.len()).copied().collect::<Vec<_>>();
+            if set.is_empty() {
+                anyhow::bail!("select a column that is not part of the key to update")
+            }
+            rows.iter().map(|row| {
+                let values = set.iter().map(|&i| format!("{} = {}", quote(i), literal(row, i))).collect::<Vec<_>>();
+                format!("UPDATE {} SET {} WHERE {};", table.path, values.join(", "), condition(row))
+            }).collect::<Vec<_>>().join("\n")
+        },
+        YankFormat::Delete => rows.iter().map(|row| format!("DELETE FROM {} WHERE {};", table.path, condition(row))).collect::<Vec<_>>().join("\n"),
+        YankFormat::WhereIn => {
+            let mut values = rows.iter().map(|row| columns.iter().map(|&i| literal(row, i)).collect::<Vec<_>>().join(", ")).collect::<Vec<_>>();
+            values.dedup();
+            match columns {
+                [column] => format!("WHERE {} IN ({})", quote(*column), values.join(", ")),
+                _ => format!(
+                    "WHERE ({}) IN ({})",
+                    columns.iter().map(|&i| quote(i)).collect::<Vec<_>>().join(", "),
+                    values.iter().map(|v| format!("({})", v)).collect::<Vec<_>>().join(", "),
+                ),
+            }
+        },
+    };
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_table, yank, DatabaseType, YankFormat, YankTable};
+    use crate::database::{ColType, Datum, Header, Value};
+
+    #[test]
+    fn test_from_table() {
+        let pg = DatabaseType::Postgres;
+        assert_eq!(from_table(&pg, "select * from Users where id = 1"), Some((None, "users".to_string())));
+        assert_eq!(from_table(&pg, "SELECT a\nFROM app.\"Order Items\" o LIMIT 5"), Some((Some("app".to_string()), "Order Items".to_string())));
+        assert_eq!(from_table(&DatabaseType::MySql, "select * from `shop`.`Items`"), Some((Some("shop".to_string()), "Items".to_string())));
+        assert_eq!(from_table(&pg, "select * from a join b on a.id = b.a_id"), None);
+        assert_eq!(from_table(&pg, "select * from a, b"), None);
+        assert_eq!(from_table(&pg, "select 1"), None);
+        assert_eq!(from_table(&pg, "update a set b = 1"), None);
+    }
+
+    #[test]
+    fn test_yank() {
+        let headers = vec![Header::new("id".to_string(), ColType::Int), Header::new("name".to_string(), ColType::VarChar), Header::new("note".to_string(), ColType::VarChar)];
+        let rows = vec![
+            vec![Value::from(Datum::Int(1)), Value::new("a,b".to_string()), Value::default()],
+            vec![Value::from(Datum::Int(2)), Value::new("it's".to_string()), Value::new("x".to_string())],
+        ];
+        let pg = DatabaseType::Postgres;
+        let table = YankTable { path: "\"public\".\"users\"".to_string(), key: vec!["id".to_string()] };
+        let text = |format, table: Option<&YankTable>, columns: &[usize]| yank(format, &pg, table, &headers, &rows, columns).unwrap();
+
+        assert_eq!(text(YankFormat::Tsv, None, &[1, 2]), "name\tnote\na,b\t\\N\nit's\tx");
+        assert_eq!(text(YankFormat::Csv, None, &[1]), "name\n\"a,b\"\nit's");
+        assert_eq!(text(YankFormat::Markdown, None, &[0, 1]), "| id | name |\n| --- | --- |\n| 1 | a,b |\n| 2 | it's |");
+        assert_eq!(text(YankFormat::Json, None, &[0, 2]), "[\n  {\"id\":1,\"note\":null},\n  {\"id\":2,\"note\":\"x\"}\n]");
+        assert_eq!(text(YankFormat::Insert, Some(&table), &[1]).lines().next(), Some("INSERT INTO \"public\".\"users\" (\"id\", \"name\", \"note\") VALUES (1, 'a,b', NULL);"));
+        assert_eq!(text(YankFormat::Update, Some(&table), &[1, 2]), "UPDATE \"public\".\"users\" SET \"name\" = 'a,b', \"note\" = NULL WHERE \"id\" = 1;\nUPDATE \"public\".\"users\" SET \"name\" = 'it''s', \"note\" = 'x' WHERE \"id\" = 2;");
+        // the key is not changed
+        assert!(yank(YankFormat::Update, &pg, Some(&table), &headers, &rows, &[0]).is_err());
+        assert_eq!(text(YankFormat::Delete, Some(&table), &[1]), "DELETE FROM \"public\".\"users\" WHERE \"id\" = 1;\nDELETE FROM \"public\".\"users\" WHERE \"id\" = 2;");
+        // without a key every column decides
+        let keyless = YankTable { key: vec![], ..table.clone() };
+        assert_eq!(text(YankFormat::Delete, Some(&keyless), &[0]).lines().next(), Some("DELETE FROM \"public\".\"users\" WHERE \"id\" = 1 AND \"name\" = 'a,b' AND \"note\" IS NULL;"));
+        assert_eq!(text(YankFormat::WhereIn, None, &[0]), "WHERE \"id\" IN (1, 2)");
+        assert_eq!(text(YankFormat::WhereIn, None, &[0, 1]), "WHERE (\"id\", \"name\") IN ((1, 'a,b'), (2, 'it''s'))");
+        assert!(yank(YankFormat::Delete, &pg, None, &headers, &rows, &[0]).is_err());
+    }
+}
@@ -1,5 +1,10 @@
 #![allow(dead_code)]
+use crate::app::QueryOutput;
+use crate::config::Connection;
+use crate::database::{ExportFormat, ExportSource, ImportRequest, RowBatch};
 use crate::event::Key;
+use database_tree::Child;
+use std::path::PathBuf;
 use crossterm::event;
 use tokio::sync::mpsc;
 use tokio::time::{self, Duration};
@@ -26,6 +31,20 @@ pub enum Event {
     RedrawTable(bool),
     OrderByTable((String, usize)),
     ToggleTree,
+    ExecuteSql(Vec<String>), // the statements of the sql editor script
+    ExplainSql(String, bool), // the statement, and whether to analyze it
+    CompareSchemas(Connection), // with the database selected in the tree
+    Export(ExportSource, ExportFormat, PathBuf),
+    ExportProgress(u64), // rows written so far
+    Import(ImportRequest),
+    ImportProgress(u64), // rows of the file loaded so far
+    Dump(String, PathBuf), // the database selected in the tree
+    DumpProgress(u64), // rows written so far
+    Restore(String, PathBuf),
+    RestoreProgress(usize, u8), // statements run so far, and the percent of the script read
+    QueryFinished(Result<Box<QueryOutput>, String>),
+    QueryRows(Box<RowBatch>),
+    TablesLoaded(String, Result<Vec<Child>, String>), // the children of an expanded database
     Tick,
 }
 
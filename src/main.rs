@@ -35,7 +35,7 @@ async fn main() -> anyhow::Result<()> {
     let mut terminal = Terminal::new(backend)?;
     let mut events = event::Events::new(250);
     let mut app = App::new(&config, events.sender());
-    app.update_databases_internal(connection.as_ref()).await?;
+    app.update_databases_internal(connection)?;
 
     terminal.clear()?;
 
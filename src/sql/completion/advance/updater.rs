@@ -40,9 +40,13 @@ impl Updater {
                     Child::Table(t)=> self.update_tables(vec![&t.name]),
                     Child::Schema(s) => {
                         self.update_schemas(vec![&s.name]);
-                        let tables = s.tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+                        let tables = s.tables.iter().map(|t| t.name.as_str())
+                            .chain(s.objects.iter().filter(|o| o.kind.is_relation()).map(|o| o.name.as_str()))
+                            .collect::<Vec<_>>();
                         self.update_tables(tables);
                     },
+                    Child::Object(o) if o.kind.is_relation() => self.update_tables(vec![&o.name]),
+                    Child::Object(_) => {},
                 };
             }
         }
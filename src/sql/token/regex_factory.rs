@@ -63,7 +63,6 @@ pub fn create_string_pattern(string_types: Vec<&str>) -> String {
             "N''" => r"((N'[^'\\]*(?:\\.[^'\\]*)*('|$))+)",
             "U&''" => r"((U&'[^'\\]*(?:\\.[^'\\]*)*('|$))+)",
             r#"U&"""# => r#"((U&"[^"\\]*(?:\\.[^"\\]*)*("|$))+)"#,
-            "$$" => r"(\$\w*\$)[\s\S]*?(\$\w*\$))",  // no backreference
             _ => unreachable!(),
         }
     }).collect::<Vec<_>>();
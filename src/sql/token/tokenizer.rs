@@ -1,3 +1,4 @@
+use std::ops::Range;
 use std::string::ToString;
 use regex::Regex;
 use super::token_type::TokenType;
@@ -78,6 +79,7 @@ pub struct Tokenizer {
     reserved_plain_regex: Regex,
     word_regex: Regex,
     string_regex: Regex,
+    dollar_quote_regex: Option<Regex>, // postgres `$tag$ ... $tag$`
     open_paren_regex: Regex,
     close_paren_regex: Regex,
     // indexed_placeholder_regex: Option<Regex>,
@@ -98,7 +100,9 @@ impl Tokenizer {
             reserved_newline_regex: regex_factory::create_reserved_word_regex(cfg.reserved_newline_words)?,
             reserved_plain_regex: regex_factory::create_reserved_word_regex(cfg.reserved_words)?,
             word_regex: regex_factory::create_word_regex(cfg.special_word_chars)?,
-            string_regex: regex_factory::create_string_regex(cfg.string_types.clone())?,
+            string_regex: regex_factory::create_string_regex(cfg.string_types.iter().copied().filter(|t| *t != "$$").collect())?,
+            // the closing tag has to match the opening one, the regex crate has no backreferences
+            dollar_quote_regex: if cfg.string_types.contains(&"$$") { Some(Regex::new(r"^\$([A-Za-z_]\w*)?\$")?) } else { None },
             open_paren_regex: regex_factory::create_paren_regex(cfg.open_parens)?,
             close_paren_regex: regex_factory::create_paren_regex(cfg.close_parens)?,
             // indexed_placeholder_regex: regex_factory::create_placeholder_regex(cfg.indexed_placeholder_types, r"[0-9]*").ok(),
@@ -128,6 +132,48 @@ impl Tokenizer {
         tokens
     }
 
+    // byte ranges of the statements in a script, split on `;` outside strings and comments.
+    // comments around a statement are left out, and so are statements with nothing else
+    pub fn statements(&self, input: &str) -> Vec<Range<usize>> {
+        let mut statements = vec![];
+        let mut start = 0;
+        loop {
+            let (statement, end) = self.next_statement(&input[start..]);
+            statements.extend(statement.map(|r| start + r.start..start + r.end));
+            match end {
+                Some(end) => start += end,
+                None => return statements,
+            }
+        }
+    }
+
+    // the first statement of `input` as in `statements`, and where the `;` that ends it ends.
+    // no end means the statement runs on, a string or comment left open goes to the end of `input`
+    pub fn next_statement(&self, input: &str) -> (Option<Range<usize>>, Option<usize>) {
+        let mut current: Option<Range<usize>> = None;
+        let mut token: Option<Token> = None;
+        let mut index = 0;
+        while index < input.len() {
+            index += self.get_whitespace_count(&input[index..]);
+            if index >= input.len() {
+                break
+            }
+            token = self.get_next_token(&input[index..], token);
+            let t = match token.as_ref() {
+                Some(t) => t,
+                None => break,
+            };
+            let end = index + t.value.len();
+            match t.typ {
+                TokenType::LineComment | TokenType::BlockComment => {},
+                TokenType::Operator if t.value == ";" => return (current, Some(end)),
+                _ => current = Some(current.map_or(index, |c| c.start)..end),
+            }
+            index = end;
+        }
+        (current, None)
+    }
+
     fn get_whitespace_count(&self, input: &str) -> usize {
         self.whitespace_regex.find(input).map(|s| s.as_str().len()).unwrap_or(0)
     }
@@ -158,9 +204,17 @@ impl Tokenizer {
     }
 
     fn get_string_token(&self, input: &str) -> Option<Token> {
+        check_some!(self.get_dollar_quote_token(input));
         get_token_on_first_match(input, &self.string_regex, TokenType::String)
     }
 
+    fn get_dollar_quote_token(&self, input: &str) -> Option<Token> {
+        let tag = self.dollar_quote_regex.as_ref()?.find(input)?.as_str();
+        // unterminated runs to the end, like the other string types
+        let end = input[tag.len()..].find(tag).map_or(input.len(), |i| tag.len() + i + tag.len());
+        Some(Token::new(TokenType::String, &input[..end]))
+    }
+
     fn get_open_paren_token(&self, input: &str) -> Option<Token> {
         get_token_on_first_match(input, &self.open_paren_regex, TokenType::OpenParen)
     }
@@ -297,6 +351,27 @@ mod tests {
         assert_eq!(token.value, input)
     }
 
+    #[test]
+    fn test_statements() {
+        let t = Standard{}.tokenizer().unwrap();
+        let input = "select 1;\n-- a comment\nselect ';' as x -- trailing\n ; ;\n/* only a comment */;\ninsert into t values (1)";
+        let statements = t.statements(input).into_iter().map(|r| &input[r]).collect::<Vec<_>>();
+        assert_eq!(statements, ["select 1", "select ';' as x", "insert into t values (1)"]);
+        assert!(t.statements(" -- nothing\n").is_empty());
+        assert_eq!(t.next_statement("-- x\nselect 'a;\n"), (Some(5..16), None));
+        assert_eq!(t.next_statement("select 'a;\nb';\nselect 2"), (Some(0..13), Some(14)));
+    }
+
+    #[test]
+    fn test_dollar_quote() {
+        let t = crate::sql::lang::PostgreSQL{}.tokenizer().unwrap();
+        let input = "do $body$ begin perform 1; end $body$; select $$a;b$$";
+        let statements = t.statements(input).into_iter().map(|r| &input[r]).collect::<Vec<_>>();
+        assert_eq!(statements, ["do $body$ begin perform 1; end $body$", "select $$a;b$$"]);
+        // positional params are no quote
+        assert_eq!(t.statements("select $1; select $2").len(), 2);
+    }
+
     #[test]
     fn test_get_operator_token() {
         let standard = Standard{};